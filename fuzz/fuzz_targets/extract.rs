@@ -0,0 +1,52 @@
+//! Checks that extraction doesn't panic on arbitrary input.
+//!
+//! Run with `cargo fuzz run extract` in the repository root.
+#![no_main]
+// the fields are extracted only to exercise the extractors
+#![allow(dead_code)]
+
+use html_extractor::incremental::IncrementalExtractor;
+use html_extractor::options::ExtractOptions;
+use html_extractor::provenance::ProvenanceExtractor;
+use html_extractor::{html_extractor, HtmlExtractor};
+use libfuzzer_sys::fuzz_target;
+
+html_extractor! {
+    Fuzz {
+        #![provenance]
+        #![incremental]
+        first: String = (text of "p"),
+        nth: Option<String> = (text[2] of "div", optional),
+        attr: Vec<usize> = (attr["data-n"] of "[data-n]", collect),
+        inner: Option<String> = (inner_html of "ul", optional, normalize_whitespace, lowercase),
+        (a: String, b: usize) = (text of "h1", capture with r"^(\w+)\s*(\d+)?$"),
+        unique: String = (text of "#id", unique, replace "a" with "b", uppercase),
+        presence: bool = (presence of "a[href]"),
+        nested: Vec<FuzzInner> = (elem of "section", collect),
+        styles: html_extractor::style::StyleSheet = (elem of "html"),
+        shadow: Vec<FuzzInner> = (shadow of "[data-shadow]", collect),
+        color: Vec<String> = (style["color"] of "[style]", collect),
+    }
+    FuzzInner {
+        title: Option<String> = (text of "h2", optional),
+        deeper: Option<Box<FuzzInner>> = (elem of "section", optional),
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Fuzz::extract_from_bytes(data);
+    let html = String::from_utf8_lossy(data);
+    let options = ExtractOptions::new().suggestions(true);
+    let _ = Fuzz::extract_from_str_with_options(&html, &options);
+    let _ = Fuzz::extract_from_fragment(&html);
+    match Fuzz::extract_with_provenance_from_str(&html) {
+        Ok((fuzz, _)) => {
+            let fingerprint = Fuzz::fingerprint_from_str(&html);
+            let _ = Fuzz::reextract_from_str(fuzz, &fingerprint, &html);
+        }
+        Err(err) => {
+            let _ = err.render_plain(&html);
+        }
+    }
+    let _ = html_extractor::style::StyleSheet::parse(&html);
+});
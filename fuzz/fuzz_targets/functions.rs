@@ -0,0 +1,40 @@
+//! Checks that the functions for extracted strings and elements don't panic on arbitrary input.
+//!
+//! Run with `cargo fuzz run functions` in the repository root.
+#![no_main]
+
+use html_extractor::parsers::{self, NumberLocale};
+use html_extractor::{entities, render, style};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+
+    let _ = parsers::emails(&input);
+    let _ = parsers::phone_numbers(&input);
+    let _ = parsers::data_uri(&input);
+    let _ = parsers::localized_number::<f64>(&NumberLocale::COMMA)(&input);
+    let _ = parsers::number_in::<i64>(NumberLocale::POINT_APOSTROPHE)(&input);
+    let _ = NumberLocale::from_tag(&input);
+    let _ = parsers::decimal(&input);
+    let _ = parsers::decimal_or_none(&input);
+    let _ = parsers::datetime(&input);
+    let _ = parsers::datetime_in(chrono::Utc)(&input);
+    let _ = parsers::zoned_datetime(&chrono_tz::Tz::Asia__Tokyo)(&input);
+    let _ = parsers::mime(&input);
+    let _ = parsers::url_with_base(url::Url::parse("https://example.com/a/").unwrap())(&input);
+    let _ = parsers::uuid(&input);
+    let _ = parsers::with_language(&input);
+    let _ = entities::decode(&input);
+
+    let sheet = style::StyleSheet::parse(&input);
+    let _ = sheet.property(&input, &input);
+    let _ = style::parse_declarations(&input);
+
+    let html = html_extractor::scraper::Html::parse_document(&input);
+    let root = html.root_element();
+    let _ = render::html_to_text(&root);
+    for elem in root.descendants().filter_map(html_extractor::scraper::ElementRef::wrap) {
+        let _ = style::inline_style(&elem, "color");
+    }
+});
@@ -0,0 +1,384 @@
+//! `#[derive(HtmlExtractor)]`, which translates the `#[extract(..)]` attributes into the syntax of `html_extractor!`,
+//! and `#[extractor]`, which does the same for structures without the derive.
+
+use super::*;
+
+/// Emits the structure without the `#[extract(..)]` attributes, and the implementations.
+/// The arguments are the struct options, the same as `#[extract(..)]` on the structure.
+pub(crate) fn attribute(args: TokenStream, mut input: syn::DeriveInput) -> TokenStream {
+    let mut item = input.clone();
+    item.attrs.retain(|attr| !attr.path.is_ident("extract"));
+    if let syn::Data::Struct(data) = &mut item.data {
+        for field in data.fields.iter_mut() {
+            field.attrs.retain(|attr| !attr.path.is_ident("extract"));
+        }
+    }
+    if !args.is_empty() {
+        input.attrs.push(syn::parse_quote!(#[extract(#args)]));
+    }
+    let impls = derive(input);
+    quote!(#item #impls)
+}
+
+pub(crate) fn derive(input: syn::DeriveInput) -> TokenStream {
+    let fields = match input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => fields.named,
+        _ => abort!(
+            input.ident,
+            "only structures with named fields are supported"
+        ),
+    };
+
+    let mut options = StructOptions::default();
+    let mut root = None;
+    for args in extract_attrs(&input.attrs) {
+        for (key, value) in args {
+            match (&*key.to_string(), value) {
+                ("root", Some(value)) => root = value.into_iter().next(),
+                ("after_extract", Some(value)) => options.after_extract = Some(value),
+                ("validate", Some(value)) => options.validate = Some(value),
+                ("incremental", None) => options.incremental = Some(key),
+                ("provenance", None) => options.provenance = Some(key),
+                ("no_trim", None) => options.no_trim = true,
+                ("context", Some(value)) => {
+                    let args = match value.into_iter().next() {
+                        Some(Group(g)) if g.delimiter() == Delimiter::Parenthesis => g.stream(),
+                        _ => abort!(key, "expected `context(name: Type)`"),
+                    };
+                    options.context = Some(StructOptions::parse_context(args));
+                }
+                ("context", None) => abort!(key, "expected `context(name: Type)`"),
+                ("after_extract", None) | ("validate", None) | ("root", None) => {
+                    abort!(key, "expected `{} = ..`", key)
+                }
+                ("incremental", Some(_)) | ("provenance", Some(_)) | ("no_trim", Some(_)) => {
+                    abort!(key, "`{}` does not take a value", key)
+                }
+                _ => abort!(key, "unknown struct option `{}`", key),
+            }
+        }
+    }
+
+    let defs = Definitions::default();
+    let fields: Vec<_> = fields
+        .into_iter()
+        .map(|field| {
+            let name = TokenTree::Ident(field.ident.clone().unwrap());
+            let extractor = extractor_tokens(&name, &field.attrs);
+            let field = SingleField {
+                attr: cfg_attrs(&field.attrs),
+                vis: Visibility::Private,
+                name,
+                ty: field.ty.to_token_stream().into_iter().collect(),
+                member: None,
+            };
+            let mut extractor_ts = extractor.into_token_stream().into_iter().peekable();
+            if let Some(init) = parse_skip(&mut extractor_ts) {
+                return Field::Skip { field, init };
+            }
+            let extractor = Extractor::parse(&mut extractor_ts, &defs);
+            // the first capture group is parsed into the field
+            if extractor.capture.is_some() {
+                Field::Tuple {
+                    fields: vec![field],
+                    extractor,
+                }
+            } else {
+                Field::Single { field, extractor }
+            }
+        })
+        .collect();
+    let fields = options.apply(fields);
+
+    let mut vis_ts = input.vis.to_token_stream();
+    vis_ts.extend(Some(TokenTree::Ident(input.ident.clone())));
+    Struct {
+        attr: cfg_attrs(&input.attrs),
+        vis: Visibility::parse(&mut vis_ts.into_iter().peekable()),
+        name: TokenTree::Ident(input.ident),
+        generics: input.generics,
+        options,
+        fields,
+        tuple: false,
+        derived: true,
+        root,
+    }
+    .into_token_stream()
+}
+
+/// The `#[cfg(..)]` attributes, which are also attached to the implementations.
+fn cfg_attrs(attrs: &[syn::Attribute]) -> Attributes {
+    Attributes {
+        tokens: attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cfg"))
+            .flat_map(|attr| attr.to_token_stream())
+            .collect(),
+    }
+}
+
+/// Returns the arguments of the `#[extract(..)]` attributes, as the keys and the values after `=`.
+fn extract_attrs(attrs: &[syn::Attribute]) -> Vec<Vec<(TokenTree, Option<TokenStream>)>> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("extract"))
+        .map(|attr| {
+            let mut ts: TokenStreamIter = match attr.tokens.clone().into_iter().next() {
+                Some(Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                    g.stream().into_iter().peekable()
+                }
+                _ => abort!(attr, "expected `#[extract(..)]`"),
+            };
+            let mut args = Vec::new();
+            while !ts.is_finished() {
+                let key = ts.next_ex("identifier");
+                let value = if ts.peek().map(|tt| tt.to_string()).as_deref() == Some("=") {
+                    ts.next();
+                    let value = parse_expression(&mut ts)
+                        .into_iter()
+                        .collect::<TokenStream>();
+                    if value.is_empty() {
+                        abort!(key, "expected a value after `{} =`", key);
+                    }
+                    Some(value)
+                } else if let Some(Group(g)) = ts.peek() {
+                    // `replace("from", "to")`
+                    let g = Group(g.clone());
+                    ts.next();
+                    Some(g.into_token_stream())
+                } else {
+                    None
+                };
+                args.push((key, value));
+                ts.expect_or_none(",");
+            }
+            args
+        })
+        .collect()
+}
+
+/// Translates the `#[extract(..)]` attributes of a field into the extractor part, `(text of "..", ..)`.
+fn extractor_tokens(name: &TokenTree, attrs: &[syn::Attribute]) -> TokenTree {
+    let args = extract_attrs(attrs)
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    if args.is_empty() {
+        abort!(name, "field `{}` has no `#[extract(..)]` attribute", name);
+    }
+    let find = |key: &str| args.iter().find(|(k, _)| k.to_string() == key);
+    let selector = find("selector").map(|(_, v)| v.clone());
+    let fold_from = find("fold_from").map(|(_, v)| v.clone());
+    let nth = find("nth").map(|(_, v)| v.clone());
+
+    // `skip` and `init = ..` are translated into `(skip, init with ..)`
+    if find("skip").is_some() {
+        let mut ts = quote!(skip,);
+        for (key, value) in &args {
+            match (&*key.to_string(), value) {
+                ("skip", None) => {}
+                ("init", Some(value)) => ts.extend(quote!(init with #value)),
+                ("skip", Some(_)) => abort!(key, "`skip` does not take a value"),
+                ("init", None) => abort!(key, "expected `init = ..`"),
+                _ => abort!(key, "`{}` cannot be used with `skip`", key),
+            }
+        }
+        return TokenTree::Group(proc_macro2::Group::new(Delimiter::Parenthesis, ts));
+    }
+
+    let mut ts = TokenStream::new();
+    for (key, value) in &args {
+        let has_value = value.is_some();
+        let no_value = || {
+            if value.is_some() {
+                abort!(key, "`{}` does not take a value", key);
+            }
+        };
+        let value = || {
+            value
+                .clone()
+                .unwrap_or_else(|| abort!(key, "expected `{} = ..`", key))
+        };
+        let selector = || {
+            selector
+                .clone()
+                .flatten()
+                .unwrap_or_else(|| abort!(key, "`{}` requires `selector = \"..\"`", key))
+        };
+        ts.extend(match &*key.to_string() {
+            "selector" | "nth" => {
+                value();
+                continue;
+            }
+            "text" => {
+                let value = value();
+                match &nth {
+                    Some(nth) => {
+                        let nth = nth
+                            .clone()
+                            .unwrap_or_else(|| abort!(key, "expected `nth = ..`"));
+                        quote!(text[#nth] of #value,)
+                    }
+                    None => quote!(text of #value,),
+                }
+            }
+            "attr" => {
+                let (value, selector) = (value(), selector());
+                quote!(attr[#value] of #selector,)
+            }
+            // `attrs = ["a", "b"]` is translated into `attrs["a", "b"] of ..` with the selector
+            "attrs" => {
+                let value = value();
+                match value.clone().into_iter().next() {
+                    Some(Group(g)) if g.delimiter() == Delimiter::Bracket => {
+                        let selector = selector();
+                        quote!(attrs #value of #selector,)
+                    }
+                    _ => quote!(attrs of #value,),
+                }
+            }
+            "style" => {
+                let (value, selector) = (value(), selector());
+                quote!(style[#value] of #selector,)
+            }
+            "elem" | "shadow" | "dataset" | "own_text" | "text_all" | "texts" | "next_text" | "inner_html" | "inner_markdown" | "sanitized_html" | "rendered_text" | "position" | "presence" => {
+                let value = value();
+                quote!(#key of #value,)
+            }
+            "capture" => {
+                let value = value();
+                quote!(capture with #value,)
+            }
+            "capture_by_name" => {
+                let value = value();
+                quote!(capture by name with #value,)
+            }
+            "parse" => {
+                let value = value();
+                quote!(parse with #value,)
+            }
+            "map" => {
+                let value = value();
+                quote!(map with #value,)
+            }
+            "filter" => {
+                let value = value();
+                quote!(filter with #value,)
+            }
+            // `key(text of "..")` and `key = (text of "..")` are translated into `key with (text of "..")`,
+            // and `group(..)` into `group by (..)`
+            "key" | "value" | "group" | "zip" => {
+                let value = value();
+                match value.clone().into_iter().next() {
+                    Some(Group(g)) if g.delimiter() == Delimiter::Parenthesis => {}
+                    _ => abort!(key, "expected `{}(..)`", key),
+                }
+                if key.to_string() == "group" {
+                    quote!(group by #value,)
+                } else {
+                    quote!(#key with #value,)
+                }
+            }
+            // `fold = f` and `fold_from = init` are translated into `fold from init with f`
+            "fold" => {
+                let value = value();
+                match &fold_from {
+                    Some(init) => {
+                        let init = init
+                            .clone()
+                            .unwrap_or_else(|| abort!(key, "expected `fold_from = ..`"));
+                        quote!(fold from #init with #value,)
+                    }
+                    None => quote!(fold with #value,),
+                }
+            }
+            "fold_from" => {
+                value();
+                if find("fold").is_none() {
+                    abort!(key, "`fold_from` requires `fold = ..`");
+                }
+                continue;
+            }
+            "join" => {
+                let value = value();
+                quote!(join with #value,)
+            }
+            "validate" => {
+                let value = value();
+                quote!(validate with #value,)
+            }
+            "from_page" => {
+                let value = value();
+                quote!(from page #value,)
+            }
+            "replace" => {
+                let mut args: TokenStreamIter = match value().into_iter().next() {
+                    Some(Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                        g.stream().into_iter().peekable()
+                    }
+                    _ => abort!(key, "expected `replace(\"from\", \"to\")`"),
+                };
+                let from = args.next_ex("literal string");
+                args.expect(",");
+                let to = args.next_ex("literal string");
+                quote!(replace #from with #to,)
+            }
+            "default" => match find("default").and_then(|(_, v)| v.clone()) {
+                Some(value) => quote!(default with #value,),
+                None => quote!(or default,),
+            },
+            "init" => abort!(key, "`init` requires `skip`"),
+            // `collect(skip = 1, limit = 10, enumerate, at_least = 1)` is translated into
+            // `collect skip 1 limit 10 enumerate at least 1`
+            "collect" if has_value => {
+                let mut args: TokenStreamIter = match value().into_iter().next() {
+                    Some(Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+                        g.stream().into_iter().peekable()
+                    }
+                    _ => abort!(
+                        key,
+                        "expected `collect(skip = .., limit = .., enumerate, at_least = .., at_most = ..)`"
+                    ),
+                };
+                let mut ts = quote!(collect);
+                while !args.is_finished() {
+                    let modifier = args.next_ex("`skip`, `limit`, `enumerate`, `at_least` or `at_most`");
+                    let modifier_ts = match &*modifier.to_string() {
+                        "enumerate" => {
+                            ts.extend(quote!(#modifier));
+                            args.expect_or_none(",");
+                            continue;
+                        }
+                        "skip" | "limit" => quote!(#modifier),
+                        "at_least" => quote!(at least),
+                        "at_most" => quote!(at most),
+                        _ => abort!(
+                            modifier,
+                            "expected `skip`, `limit`, `enumerate`, `at_least` or `at_most`, found `{}`",
+                            modifier
+                        ),
+                    };
+                    args.expect("=");
+                    let count = args.next_ex("count");
+                    ts.extend(quote!(#modifier_ts #count));
+                    args.expect_or_none(",");
+                }
+                quote!(#ts,)
+            }
+            "optional" | "unique" | "normalize_whitespace" | "no_trim" | "lowercase"
+            | "uppercase" | "decode_entities" | "collect" => {
+                no_value();
+                quote!(#key,)
+            }
+            _ => abort!(
+                key,
+                "expected `text`, `attr`, `style`, `elem`, `shadow`, `attrs`, `dataset`, `own_text`, `text_all`, `texts`, `next_text`, `inner_html`, `inner_markdown`, `sanitized_html`, `rendered_text`, `position`, `presence`, `selector`, `nth`, `replace`, `normalize_whitespace`, `no_trim`, `lowercase`, `uppercase`, `decode_entities`, `capture`, `capture_by_name`, `collect`, `optional`, `default`, `unique`, `parse`, `map`, `filter`, `key`, `group`, `value`, `zip`, `fold`, `fold_from`, `join`, `validate`, `from_page`, `skip` or `init`, found `{}`",
+                key
+            ),
+        });
+    }
+    TokenTree::Group(proc_macro2::Group::new(Delimiter::Parenthesis, ts))
+}
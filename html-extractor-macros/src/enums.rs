@@ -0,0 +1,193 @@
+//! Enums, whose variants are chosen by the guards, `Variant if presence of ".." { .. }`.
+
+use super::*;
+
+pub(crate) struct Enum {
+    attr: Attributes,
+    vis: Visibility,
+    name: TokenTree,
+    variants: Vec<Variant>,
+}
+struct Variant {
+    attr: Attributes,
+    name: TokenTree,
+    /// The selector of `if presence of ..`.
+    guard: Option<TokenTree>,
+    /// The fields, or `None` for unit variants.
+    fields: Option<Vec<Field>>,
+}
+impl Enum {
+    pub(crate) fn parse(ts: &mut TokenStreamIter, defs: &Definitions) -> Enum {
+        let attr = Attributes::parse(ts);
+        let vis = Visibility::parse(ts);
+        ts.expect("enum");
+        let name = ts.next_ex("identifier");
+
+        let mut body_ts: TokenStreamIter = match ts.next_ex("{{..}}") {
+            Group(g) if g.delimiter() == Delimiter::Brace => g.stream().into_iter().peekable(),
+            tt => abort!(tt, "expected {{..}}, found `{}`", tt),
+        };
+        let mut variants = Vec::new();
+        while !body_ts.is_finished() {
+            let attr = Attributes::parse(&mut body_ts);
+            let name = body_ts.next_ex("identifier");
+            let guard = if body_ts.peek().map(|tt| tt.to_string()).as_deref() == Some("if") {
+                body_ts.next();
+                body_ts.expect("presence");
+                body_ts.expect("of");
+                let selector = defs.parse_selector(&mut body_ts);
+                if let Err(err) = scraper::Selector::parse(&get_literal_str_value(&selector)) {
+                    abort!(selector, "cannot parse the selector: {:?}", err);
+                }
+                Some(selector)
+            } else {
+                None
+            };
+            let fields = match body_ts.peek() {
+                Some(Group(g)) if g.delimiter() == Delimiter::Brace => {
+                    let mut fields_ts = g.stream().into_iter().peekable();
+                    body_ts.next();
+                    let fields = defs.parse_fields(&mut fields_ts, &mut Vec::new());
+                    for field in &fields {
+                        if let Some(page) = field.extractor().and_then(|e| e.page.as_ref()) {
+                            abort!(page, "`from page ..` cannot be used in enums");
+                        }
+                        for field in field.single_fields() {
+                            if !matches!(field.vis, Visibility::Private) {
+                                abort!(
+                                    field.name,
+                                    "fields of enum variants cannot have visibility"
+                                );
+                            }
+                        }
+                    }
+                    Some(fields)
+                }
+                _ => None,
+            };
+            body_ts.expect_or_none(",");
+            variants.push(Variant {
+                attr,
+                name,
+                guard,
+                fields,
+            });
+        }
+        if let Some(i) = variants.iter().position(|v| v.guard.is_none()) {
+            if let Some(next) = variants.get(i + 1) {
+                abort!(
+                    next.name,
+                    "variant `{}` is unreachable, since variant `{}` has no guard",
+                    next.name,
+                    variants[i].name
+                );
+            }
+        }
+
+        Enum {
+            attr,
+            vis,
+            name,
+            variants,
+        }
+    }
+}
+impl ToTokens for Enum {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let attr = &self.attr;
+        let vis = &self.vis;
+        let name = &self.name;
+        let cfg = self.attr.cfg();
+
+        let variant_def = self.variants.iter().map(|variant| {
+            let attr = &variant.attr;
+            let variant_name = &variant.name;
+            let fields = variant.fields.as_ref().map(|fields| {
+                let field_def = fields.iter().map(|f| f.def_tokens(false));
+                quote!({ #(#field_def)* })
+            });
+            quote!(#attr #variant_name #fields,)
+        });
+
+        let variant_extract = self.variants.iter().map(|variant| {
+            let variant_name = &variant.name;
+            let variant_cfg = variant.attr.cfg();
+            let extract = match &variant.fields {
+                Some(fields) => {
+                    let field_extract = fields.iter().map(|f| f.extract_tokens(name));
+                    let field_init = fields.iter().map(|f| f.init_tokens());
+                    quote! {
+                        #(#field_extract)*
+                        ::std::result::Result::Ok(#name::#variant_name { #(#field_init)* })
+                    }
+                }
+                None => quote!(::std::result::Result::Ok(#name::#variant_name)),
+            };
+            match &variant.guard {
+                Some(guard) => quote! {
+                    #(#variant_cfg)*
+                    {
+                        #_crate::lazy_static::lazy_static! {
+                            static ref GUARD: #_crate::scraper::Selector = #_crate::scraper::Selector::parse(#guard).unwrap();
+                        }
+                        if __elem.select(&*GUARD).next().is_some() {
+                            return { #extract };
+                        }
+                    }
+                },
+                // the last variant, which is the fallback
+                None if variant_cfg.is_empty() => quote!({ #extract }),
+                // the fallback may be removed, so the error below is still needed
+                None => quote! {
+                    #(#variant_cfg)*
+                    {
+                        return { #extract };
+                    }
+                },
+            }
+        });
+        let fallback = self.variants.iter().find(|v| v.guard.is_none());
+        let conditional_fallback = fallback.is_some_and(|v| !v.attr.cfg().is_empty());
+        let allow_unreachable = if conditional_fallback {
+            Some(quote!(#[allow(unreachable_code)]))
+        } else {
+            None
+        };
+        let no_variant = if fallback.is_none() || conditional_fallback {
+            Some(quote! {
+                ::std::result::Result::Err(#_crate::error::Error::InvalidInput(
+                    ::std::borrow::Cow::Borrowed(::std::concat!(
+                        "extracting enum `",
+                        ::std::stringify!(#name),
+                        "`, no variant matched"
+                    ))
+                ))
+            })
+        } else {
+            None
+        };
+
+        tokens.extend(quote! {
+            #attr
+            #vis enum #name {
+                #(#variant_def)*
+            }
+            #(#cfg)*
+            impl #_crate::HtmlExtractor for #name {
+                fn extract(__elem: &#_crate::scraper::ElementRef) -> ::std::result::Result<Self, #_crate::Error> {
+                    <Self as #_crate::HtmlExtractor>::extract_with_options(__elem, &#_crate::options::ExtractOptions::new())
+                }
+                #allow_unreachable
+                fn extract_with_options(
+                    __elem: &#_crate::scraper::ElementRef,
+                    __options: &#_crate::options::ExtractOptions,
+                ) -> ::std::result::Result<Self, #_crate::Error> {
+                    __options.check()?;
+                    #(#variant_extract)*
+                    #no_variant
+                }
+            }
+        });
+    }
+}
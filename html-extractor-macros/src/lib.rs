@@ -1,21 +1,48 @@
 use proc_macro::TokenStream as TokenStream1;
 use proc_macro2::{Delimiter, TokenStream, TokenTree, TokenTree::*};
 use proc_macro_error::*;
-use quote::{quote, ToTokens};
+use quote::{format_ident, quote, ToTokens};
 
 #[proc_macro_error]
 #[proc_macro]
 pub fn html_extractor(input: TokenStream1) -> TokenStream1 {
-    let mut input_iter: TokenStreamIter = TokenStream::from(input).into_iter().peekable();
+    let (defs, input) = Definitions::parse(TokenStream::from(input));
+    let mut input_iter: TokenStreamIter = input.into_iter().peekable();
 
-    let mut structs = Vec::new();
+    let mut items = Vec::new();
     while !input_iter.is_finished() {
-        structs.push(Struct::parse(&mut input_iter));
+        let mut lookahead = input_iter.clone();
+        Attributes::parse(&mut lookahead);
+        Visibility::parse(&mut lookahead);
+        if lookahead.peek_ex_str("identifier") == "enum" {
+            items.push(enums::Enum::parse(&mut input_iter, &defs).into_token_stream());
+        } else {
+            items.push(Struct::parse(&mut input_iter, &defs).into_token_stream());
+        }
     }
 
-    quote!(#(#structs)*).into()
+    quote!(#(#items)*).into()
+}
+
+#[proc_macro_error]
+#[proc_macro_derive(HtmlExtractor, attributes(extract))]
+pub fn derive_html_extractor(input: TokenStream1) -> TokenStream1 {
+    derive::derive(syn::parse_macro_input!(input as syn::DeriveInput)).into()
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn extractor(args: TokenStream1, input: TokenStream1) -> TokenStream1 {
+    derive::attribute(
+        TokenStream::from(args),
+        syn::parse_macro_input!(input as syn::DeriveInput),
+    )
+    .into()
 }
 
+mod derive;
+mod enums;
+
 lazy_static::lazy_static! {
     static ref CRATE: String = proc_macro_crate::crate_name("html-extractor").unwrap();
 }
@@ -131,43 +158,504 @@ impl Attributes {
         Attributes { tokens }
     }
 }
+impl Attributes {
+    fn doc(&self) -> Vec<TokenStream> {
+        self.named("doc")
+    }
+    /// The `#[cfg(..)]` attributes, which are attached to all the code generated for the item.
+    fn cfg(&self) -> Vec<TokenStream> {
+        self.named("cfg")
+    }
+    fn named(&self, name: &str) -> Vec<TokenStream> {
+        self.tokens
+            .chunks(2)
+            .filter(|attr| match &attr[1] {
+                Group(g) => g
+                    .stream()
+                    .into_iter()
+                    .next()
+                    .is_some_and(|tt| tt.to_string() == name),
+                _ => false,
+            })
+            .map(|attr| attr.iter().cloned().collect())
+            .collect()
+    }
+}
 impl ToTokens for Attributes {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         tokens.extend(self.tokens.clone());
     }
 }
 
+/// Definitions shared by the structures in a macro invocation.
+#[derive(Default)]
+struct Definitions {
+    mixins: std::collections::HashMap<String, TokenStream>,
+    selectors: std::collections::HashMap<String, TokenTree>,
+}
+impl Definitions {
+    /// Takes the definitions out of the input, and returns them and the rest of the input.
+    fn parse(input: TokenStream) -> (Definitions, TokenStream) {
+        let mut defs = Definitions::default();
+        let mut rest = TokenStream::new();
+        let mut ts: TokenStreamIter = input.into_iter().peekable();
+        while let Some(tt) = ts.next() {
+            let is_selectors = matches!(&tt, Ident(i) if i == "selectors")
+                && matches!(ts.peek(), Some(Group(g)) if g.delimiter() == Delimiter::Brace
+                    && g.stream().into_iter().nth(1).map(|tt| tt.to_string()).as_deref() == Some("="));
+            if is_selectors {
+                let mut body_ts: TokenStreamIter = match ts.next() {
+                    Some(Group(g)) => g.stream().into_iter().peekable(),
+                    _ => unreachable!(),
+                };
+                while !body_ts.is_finished() {
+                    defs.define_selector(&mut body_ts);
+                    body_ts.expect_or_none(",");
+                }
+                continue;
+            }
+            // `selector NAME = "..";`
+            let is_selector = matches!(&tt, Ident(i) if i == "selector")
+                && matches!(ts.peek(), Some(Ident(_)))
+                && ts.clone().nth(1).map(|tt| tt.to_string()).as_deref() == Some("=");
+            if is_selector {
+                defs.define_selector(&mut ts);
+                ts.expect(";");
+                continue;
+            }
+            let is_mixin = matches!(&tt, Ident(i) if i == "mixin")
+                && matches!(ts.peek(), Some(Ident(_)))
+                && matches!(ts.clone().nth(1), Some(Group(g)) if g.delimiter() == Delimiter::Brace);
+            if !is_mixin {
+                rest.extend(Some(tt));
+                continue;
+            }
+            let name = ts.next_ex("identifier");
+            let body = match ts.next_ex("{{..}}") {
+                Group(g) => g.stream(),
+                _ => unreachable!(),
+            };
+            if defs.mixins.insert(name.to_string(), body).is_some() {
+                abort!(name, "mixin `{}` is defined more than once", name);
+            }
+        }
+        (defs, rest)
+    }
+
+    /// Parses `NAME = selector` and defines the selector alias.
+    fn define_selector(&mut self, ts: &mut TokenStreamIter) {
+        let name = ts.next_ex("identifier");
+        ts.expect("=");
+        let selector = self.parse_selector(ts);
+        if let Err(err) = scraper::Selector::parse(&get_literal_str_value(&selector)) {
+            abort!(selector, "cannot parse the selector: {:?}", err);
+        }
+        if self.selectors.insert(name.to_string(), selector).is_some() {
+            abort!(name, "selector alias `{}` is defined more than once", name);
+        }
+    }
+
+    /// Parses a selector, which is literal strings and selector aliases concatenated with `+`,
+    /// into the literal string.
+    fn parse_selector(&self, ts: &mut TokenStreamIter) -> TokenTree {
+        let first = self.selector(ts.next_ex("literal string or selector alias"));
+        if ts.peek().map(|tt| tt.to_string()).as_deref() != Some("+") {
+            return first;
+        }
+        let mut selector = get_literal_str_value(&first);
+        while ts.peek().map(|tt| tt.to_string()).as_deref() == Some("+") {
+            ts.next();
+            let part = self.selector(ts.next_ex("literal string or selector alias"));
+            selector.push_str(&get_literal_str_value(&part));
+        }
+        let mut literal = proc_macro2::Literal::string(&selector);
+        literal.set_span(first.span());
+        TokenTree::Literal(literal)
+    }
+
+    /// Parses the selector of a target, and the index or `last` in `[..]` after it as the position.
+    /// The navigations like `parent of` before the selector are returned in the order they are applied.
+    fn parse_target_selector(
+        &self,
+        ts: &mut TokenStreamIter,
+    ) -> (TokenTree, Option<TokenStream>, Vec<Navigation>) {
+        let mut navigation = Vec::new();
+        while let Some(step) = self.parse_navigation(ts) {
+            navigation.push(step);
+        }
+        navigation.reverse();
+        let selector = self.parse_target_selector_list(ts);
+        let position = match ts.peek() {
+            Some(Group(g)) if g.delimiter() == Delimiter::Bracket => {
+                let position = g.stream();
+                if position.is_empty() {
+                    abort!(g, "expected an index in `[..]`");
+                }
+                ts.next();
+                Some(position)
+            }
+            _ => None,
+        };
+        (selector, position, navigation)
+    }
+
+    /// Parses a navigation like `parent of`, `closest ".." of` or `next of`, which is followed by the selector or another navigation.
+    fn parse_navigation(&self, ts: &mut TokenStreamIter) -> Option<Navigation> {
+        let mut lookahead = ts.clone();
+        let name = match lookahead.next() {
+            Some(Ident(ident)) if ident == "parent" || ident == "closest" || ident == "next" => {
+                ident
+            }
+            _ => return None,
+        };
+        // a selector alias with the same name is followed by `[..]`, `,`, `+`, `and` or `else`, but not by `of`
+        if name == "closest" {
+            match lookahead.peek() {
+                Some(Literal(_)) => {}
+                Some(Ident(ident)) if ident != "and" && ident != "else" => {}
+                _ => return None,
+            }
+            let selector = self.parse_selector(&mut lookahead);
+            if let Err(err) = scraper::Selector::parse(&get_literal_str_value(&selector)) {
+                abort!(selector, "cannot parse the selector: {:?}", err);
+            }
+            lookahead.expect("of");
+            *ts = lookahead;
+            return Some(Navigation::Closest { selector });
+        }
+        if name == "next" {
+            if lookahead.next().map(|tt| tt.to_string()).as_deref() != Some("of") {
+                return None;
+            }
+            *ts = lookahead;
+            return Some(Navigation::Next);
+        }
+        let levels = match lookahead.peek() {
+            Some(Group(g)) if g.delimiter() == Delimiter::Bracket => {
+                let levels = g.stream().into_iter().collect::<Vec<_>>();
+                lookahead.next();
+                Some(levels)
+            }
+            _ => None,
+        };
+        if lookahead.next().map(|tt| tt.to_string()).as_deref() != Some("of") {
+            return None;
+        }
+        *ts = lookahead;
+        let levels = match levels.as_deref() {
+            None => 1,
+            Some([Literal(lit)]) => match lit.to_string().parse::<usize>() {
+                Ok(n) if n > 0 => n,
+                _ => abort!(lit, "expected a positive integer in `parent[..]`"),
+            },
+            Some(_) => abort!(name, "expected a positive integer in `parent[..]`"),
+        };
+        Some(Navigation::Parent { levels })
+    }
+
+    /// Parses a selector, which may also be a macro call like `format!(..)` evaluated at extraction time.
+    /// Such a selector is returned as a group in parentheses.
+    /// The selectors joined with `and` are combined into a selector list, which matches the elements in document order.
+    /// `self` is returned as `":scope"`, which selects the element being extracted from.
+    fn parse_target_selector_list(&self, ts: &mut TokenStreamIter) -> TokenTree {
+        if let Some(Ident(ident)) = ts.peek().filter(|tt| tt.to_string() == "self") {
+            let mut literal = proc_macro2::Literal::string(":scope");
+            literal.set_span(ident.span());
+            ts.next();
+            if let Some(tt) = ts
+                .peek()
+                .filter(|tt| ["+", "and"].contains(&&*tt.to_string()))
+            {
+                abort!(tt, "`self` cannot be combined with other selectors");
+            }
+            return TokenTree::Literal(literal);
+        }
+        let mut lookahead = ts.clone();
+        let is_macro_call = matches!(lookahead.next(), Some(Ident(_)))
+            && matches!(lookahead.next(), Some(Punct(p)) if p.as_char() == '!')
+            && matches!(lookahead.next(), Some(Group(_)));
+        if is_macro_call {
+            let call = (0..3).map(|_| ts.next().unwrap()).collect::<TokenStream>();
+            if let Some(and) = ts.peek().filter(|tt| tt.to_string() == "and") {
+                abort!(
+                    and,
+                    "selectors evaluated at extraction time cannot be joined with `and`"
+                );
+            }
+            return TokenTree::Group(proc_macro2::Group::new(Delimiter::Parenthesis, call));
+        }
+        let first = self.parse_selector(ts);
+        if ts.peek().map(|tt| tt.to_string()).as_deref() != Some("and") {
+            return first;
+        }
+        let mut selector = get_literal_str_value(&first);
+        while ts.peek().map(|tt| tt.to_string()).as_deref() == Some("and") {
+            ts.next();
+            let part = self.parse_selector(ts);
+            selector.push_str(", ");
+            selector.push_str(&get_literal_str_value(&part));
+        }
+        let mut literal = proc_macro2::Literal::string(&selector);
+        literal.set_span(first.span());
+        TokenTree::Literal(literal)
+    }
+
+    /// Resolves a selector alias into the literal string.
+    fn selector(&self, tt: TokenTree) -> TokenTree {
+        match &tt {
+            Ident(name) => match self.selectors.get(&name.to_string()) {
+                Some(selector) => selector.clone(),
+                None => abort!(name, "selector alias `{}` is not defined", name),
+            },
+            _ => tt,
+        }
+    }
+
+    /// Parses the field definitions, expanding `use Mixin;`.
+    fn parse_fields(&self, ts: &mut TokenStreamIter, mixin_stack: &mut Vec<String>) -> Vec<Field> {
+        let mut fields = Vec::new();
+        while !ts.is_finished() {
+            if ts.peek_ex_str("field").as_str() == "use" {
+                ts.next();
+                let name = ts.next_ex("identifier");
+                let body = match self.mixins.get(&name.to_string()) {
+                    Some(body) => body,
+                    None => abort!(name, "mixin `{}` is not defined", name),
+                };
+                if mixin_stack.contains(&name.to_string()) {
+                    abort!(name, "mixin `{}` uses itself", name);
+                }
+                mixin_stack.push(name.to_string());
+                fields.extend(
+                    self.parse_fields(&mut body.clone().into_iter().peekable(), mixin_stack),
+                );
+                mixin_stack.pop();
+                match ts.next() {
+                    Some(tt) if tt.to_string() == ";" || tt.to_string() == "," => {}
+                    Some(tt) => abort!(tt, "expected `;`, found `{}`", tt),
+                    None => {}
+                }
+                continue;
+            }
+            if ts.peek_ex_str("field").as_str() == "in" {
+                ts.next();
+                let scope = self.parse_selector(ts);
+                if let Err(err) = scraper::Selector::parse(&get_literal_str_value(&scope)) {
+                    abort!(scope, "cannot parse the selector: {:?}", err);
+                }
+                let mut body_ts: TokenStreamIter = match ts.next_ex("{{..}}") {
+                    Group(g) if g.delimiter() == Delimiter::Brace => {
+                        g.stream().into_iter().peekable()
+                    }
+                    tt => abort!(tt, "expected `{{..}}`, found `{}`", tt),
+                };
+                for mut field in self.parse_fields(&mut body_ts, mixin_stack) {
+                    if let Some(extractor) = field.extractor_mut() {
+                        extractor.scopes.insert(0, scope.clone());
+                    }
+                    fields.push(field);
+                }
+                if ts.peek().map(|tt| tt.to_string()).as_deref() == Some(",") {
+                    ts.next();
+                }
+                continue;
+            }
+            fields.push(Field::parse(ts, self));
+            ts.expect_or_none(",");
+        }
+        fields
+    }
+}
+
+#[derive(Default)]
+struct StructOptions {
+    after_extract: Option<TokenStream>,
+    validate: Option<TokenStream>,
+    getters: bool,
+    incremental: Option<TokenTree>,
+    provenance: Option<TokenTree>,
+    /// The name and the type of the context, `#![context(name: Type)]`.
+    context: Option<(TokenTree, Vec<TokenTree>)>,
+    /// Whether `no_trim` applies to all the fields, `#![no_trim]`.
+    no_trim: bool,
+}
+impl StructOptions {
+    fn parse(ts: &mut TokenStreamIter) -> StructOptions {
+        let mut options = StructOptions::default();
+        while ts.peek().map(|tt| tt.to_string()).as_deref() == Some("#")
+            && ts.clone().nth(1).map(|tt| tt.to_string()).as_deref() == Some("!")
+        {
+            ts.advance(2);
+            let mut option_ts: TokenStreamIter = match ts.next_ex("`[..]`") {
+                Group(g) if g.delimiter() == Delimiter::Bracket => {
+                    g.stream().into_iter().peekable()
+                }
+                tt => abort!(tt, "expected `[..]`, found `{}`", tt),
+            };
+            let name = option_ts.next_ex("struct option");
+            let args = match option_ts.next() {
+                Some(Group(g)) if g.delimiter() == Delimiter::Parenthesis => Some(g.stream()),
+                Some(tt) => abort!(tt, "expected `(..)`, found `{}`", tt),
+                None => None,
+            };
+            let expect_args = |args: Option<TokenStream>| {
+                args.unwrap_or_else(|| abort!(name, "expected `{}(..)`", name))
+            };
+            match &*name.to_string() {
+                "after_extract" => options.after_extract = Some(expect_args(args)),
+                "validate" => options.validate = Some(expect_args(args)),
+                "getters" => {
+                    if let Some(args) = args {
+                        abort!(args, "`getters` does not take arguments");
+                    }
+                    options.getters = true;
+                }
+                "incremental" => {
+                    if let Some(args) = args {
+                        abort!(args, "`incremental` does not take arguments");
+                    }
+                    options.incremental = Some(name);
+                }
+                "provenance" => {
+                    if let Some(args) = args {
+                        abort!(args, "`provenance` does not take arguments");
+                    }
+                    options.provenance = Some(name);
+                }
+                "context" => {
+                    options.context = Some(StructOptions::parse_context(expect_args(args)))
+                }
+                "no_trim" => {
+                    if let Some(args) = args {
+                        abort!(args, "`no_trim` does not take arguments");
+                    }
+                    options.no_trim = true;
+                }
+                _ => abort!(name, "unknown struct option `{}`", name),
+            }
+        }
+        options
+    }
+    /// Applies the options for the fields to them.
+    fn apply(&self, mut fields: Vec<Field>) -> Vec<Field> {
+        if self.no_trim {
+            fields
+                .iter_mut()
+                .filter_map(Field::extractor_mut)
+                .for_each(Extractor::no_trim);
+        }
+        if let Some((_, ty)) = &self.context {
+            fields
+                .iter_mut()
+                .filter_map(Field::extractor_mut)
+                .for_each(|extractor| extractor.context(ty));
+        }
+        if self.provenance.is_some() {
+            fields
+                .iter_mut()
+                .filter_map(Field::extractor_mut)
+                .for_each(|extractor| extractor.record = true);
+        }
+        fields
+    }
+    /// Parses the arguments of `#![context(name: Type)]`.
+    fn parse_context(args: TokenStream) -> (TokenTree, Vec<TokenTree>) {
+        let mut ts = args.into_iter().peekable();
+        let name = ts.next_ex("identifier");
+        ts.expect(":");
+        let ty = parse_type(&mut ts);
+        if let Some(tt) = ts.next() {
+            abort!(tt, "expected `)`, found `{}`", tt);
+        }
+        (name, ty)
+    }
+}
+
 struct Struct {
     attr: Attributes,
     vis: Visibility,
     name: TokenTree,
+    generics: syn::Generics,
+    options: StructOptions,
     fields: Vec<Field>,
+    /// Whether the structure is a tuple structure, `Name(Type = (..), ..);`.
+    tuple: bool,
+    /// Whether the structure is defined by the user and only the implementations are generated.
+    derived: bool,
+    /// The selector given with `Name in ".."`, for `extract_all`.
+    root: Option<TokenTree>,
 }
 impl Struct {
-    fn parse(ts: &mut TokenStreamIter) -> Struct {
+    fn parse(ts: &mut TokenStreamIter, defs: &Definitions) -> Struct {
         let attr = Attributes::parse(ts);
         let vis = Visibility::parse(ts);
         let name = ts.next_ex("identifier");
+        let mut generics = parse_generics(ts);
 
-        let mut fields = Vec::new();
-        match ts.next_ex("{{..}}") {
+        let root = if ts.peek_ex_str("`in` or {{..}}") == "in" {
+            ts.next();
+            Some(defs.parse_selector(ts))
+        } else {
+            None
+        };
+
+        if ts.peek_ex_str("`where`, {{..}} or (..)") == "where" {
+            generics.where_clause = Some(parse_where_clause(
+                ts,
+                |tt| matches!(tt, Group(g) if g.delimiter() == Delimiter::Brace),
+            ));
+        }
+
+        let options;
+        let fields;
+        let mut tuple = false;
+        match ts.next_ex("{{..}} or (..)") {
             Group(g) if g.delimiter() == Delimiter::Brace => {
                 let mut body_ts = g.stream().into_iter().peekable();
-                while !body_ts.is_finished() {
-                    fields.push(Field::parse(&mut body_ts));
-                    body_ts.expect_or_none(",");
+                options = StructOptions::parse(&mut body_ts);
+                fields = options.apply(defs.parse_fields(&mut body_ts, &mut Vec::new()));
+            }
+            Group(g) if g.delimiter() == Delimiter::Parenthesis => {
+                let mut body_ts = g.stream().into_iter().peekable();
+                options = StructOptions::parse(&mut body_ts);
+                if options.getters {
+                    abort!(g, "`getters` cannot be used for tuple structures");
+                }
+                fields = options.apply(Field::parse_positional(&mut body_ts, defs));
+                tuple = true;
+                // `where` comes after the fields in tuple structures
+                if ts.peek().map(|tt| tt.to_string()).as_deref() == Some("where") {
+                    generics.where_clause =
+                        Some(parse_where_clause(ts, |tt| tt.to_string() == ";"));
+                }
+                if ts.peek().map(|tt| tt.to_string()).as_deref() == Some(";") {
+                    ts.next();
                 }
             }
-            tt => abort!(tt, "expected {{..}}, found `{}`", tt),
+            tt => abort!(tt, "expected {{..}} or (..), found `{}`", tt),
         }
 
         Struct {
             attr,
             vis,
             name,
+            generics,
+            options,
             fields,
+            tuple,
+            derived: false,
+            root,
         }
     }
+    /// The first selector built at extraction time, if any.
+    fn runtime_selector(&self) -> Option<&TokenTree> {
+        self.fields
+            .iter()
+            .filter_map(|f| f.extractor())
+            .flat_map(|e| e.targets())
+            .find(|t| t.is_runtime())
+            .map(|t| t.selector())
+    }
 }
 impl ToTokens for Struct {
     fn to_tokens(&self, tokens: &mut TokenStream) {
@@ -175,25 +663,451 @@ impl ToTokens for Struct {
         let vis = &self.vis;
         let name = &self.name;
 
-        let field_def = self.fields.iter().map(|f| f.def_tokens());
+        let field_def = self
+            .fields
+            .iter()
+            .map(|f| f.def_tokens(self.options.getters));
         let field_extract = self.fields.iter().map(|f| f.extract_tokens(&self.name));
         let field_init = self.fields.iter().map(|f| f.init_tokens());
 
         let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let generics = &self.generics;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let cfg = &self.attr.cfg();
 
-        tokens.extend(quote!(
-            #attr
-            #vis struct #name {
-                #(#field_def)*
-            }
-            impl #_crate::HtmlExtractor for #name {
-                fn extract(__elem: &#_crate::scraper::ElementRef) -> ::std::result::Result<Self, #_crate::Error> {
-                    #(#field_extract)*
-                    ::std::result::Result::Ok(Self {
-                        #(#field_init)*
-                    })
+        let after_extract = self.options.after_extract.as_ref().map(|after_extract| {
+            quote! {
+                (#after_extract)(&mut __extracted)?;
+            }
+        });
+
+        let validate_on = |target: TokenStream| {
+            self.options.validate.as_ref().map(|validate| {
+                quote! {
+                    (#validate)(#target).map_err(|e| #_crate::error::Error::Validation {
+                        struct_name: ::std::stringify!(#name),
+                        source: ::std::convert::Into::into(e),
+                    })?;
+                }
+            })
+        };
+        let validate = validate_on(quote!(&__extracted));
+
+        if let Some((context, _)) = &self.options.context {
+            if let Some(option) = self
+                .options
+                .incremental
+                .iter()
+                .chain(&self.options.provenance)
+                .next()
+            {
+                abort!(
+                    option,
+                    "`{}` and `{}` cannot be used for the same struct",
+                    option,
+                    context
+                );
+            }
+            if let Some(page) = self
+                .fields
+                .iter()
+                .find_map(|f| f.extractor().and_then(|e| e.page.as_ref()))
+            {
+                abort!(
+                    page,
+                    "`context` and `from page ..` cannot be used for the same struct"
+                );
+            }
+            if let Some(root) = &self.root {
+                abort!(
+                    root,
+                    "`context` and the root selector cannot be used for the same struct"
+                );
+            }
+        }
+        // binds the context given to the method to its name
+        let bind_context = self.options.context.as_ref().map(|(context, _)| {
+            quote! {
+                #[allow(unused_variables)]
+                let #context = __context;
+            }
+        });
+
+        // the options of the methods that don't take them
+        let default_options = quote! {
+            let __options = &#_crate::options::ExtractOptions::new();
+        };
+        // the fields of `#![provenance]` structures record into it, which is given only by `extract_with_provenance`
+        let no_provenance = self.options.provenance.as_ref().map(|_| {
+            quote! {
+                #[allow(unused_mut)]
+                let mut __provenance: ::std::option::Option<&mut #_crate::provenance::Provenance> = ::std::option::Option::None;
+            }
+        });
+
+        let getters = if self.options.getters {
+            let getter = self.fields.iter().map(|f| f.getter_tokens());
+            Some(quote! {
+                #(#cfg)*
+                impl #impl_generics #name #ty_generics #where_clause {
+                    #(#getter)*
+                }
+            })
+        } else {
+            None
+        };
+
+        let incremental = self.options.incremental.as_ref().map(|option| {
+            if self.options.after_extract.is_some() {
+                abort!(option, "`incremental` and `after_extract` cannot be used for the same struct");
+            }
+            if let Some(page) = self.fields.iter().find_map(|f| f.extractor().and_then(|e| e.page.as_ref())) {
+                abort!(page, "`incremental` and `from page ..` cannot be used for the same struct");
+            }
+            if let Some(selector) = self.runtime_selector() {
+                abort!(selector, "`{}` and selectors built at extraction time cannot be used for the same struct", option);
+            }
+            let field_fingerprint = self.fields.iter().map(|f| f.fingerprint_tokens());
+            let field_reextract = self.fields.iter().map(|f| f.reextract_tokens(&self.name));
+            let field_init = self.fields.iter().map(|f| f.init_tokens());
+            let reextract_body = quote! {
+                #default_options
+                #no_provenance
+                let __fingerprint = <Self as #_crate::incremental::IncrementalExtractor>::fingerprint(__elem);
+                let mut __recomputed = ::std::vec::Vec::new();
+                #(#field_reextract)*
+                let __extracted = Self {
+                    #(#field_init)*
+                };
+                #validate
+                ::std::result::Result::Ok(#_crate::incremental::Reextracted {
+                    data: __extracted,
+                    fingerprint: __fingerprint,
+                    recomputed: __recomputed,
+                })
+            };
+            quote! {
+                #(#cfg)*
+                impl #impl_generics #_crate::incremental::IncrementalExtractor for #name #ty_generics #where_clause {
+                    fn fingerprint(__elem: &#_crate::scraper::ElementRef) -> #_crate::incremental::Fingerprint {
+                        #[allow(unused_mut)]
+                        let mut __fingerprint = #_crate::incremental::Fingerprint::default();
+                        #(#field_fingerprint)*
+                        __fingerprint
+                    }
+                    fn reextract(
+                        __previous: Self,
+                        __previous_fingerprint: &#_crate::incremental::Fingerprint,
+                        __elem: &#_crate::scraper::ElementRef,
+                    ) -> ::std::result::Result<#_crate::incremental::Reextracted<Self>, #_crate::Error> {
+                        #reextract_body
+                    }
+                }
+            }
+        });
+
+        let provenance = self.options.provenance.as_ref().map(|option| {
+            if let Some(page) = self.fields.iter().find_map(|f| f.extractor().and_then(|e| e.page.as_ref())) {
+                abort!(page, "`{}` and `from page ..` cannot be used for the same struct", option);
+            }
+            if let Some(selector) = self.runtime_selector() {
+                abort!(selector, "`{}` and selectors built at extraction time cannot be used for the same struct", option);
+            }
+            quote! {
+                #(#cfg)*
+                impl #impl_generics #_crate::provenance::ProvenanceExtractor for #name #ty_generics #where_clause {
+                    fn extract_with_provenance(
+                        __elem: &#_crate::scraper::ElementRef,
+                    ) -> ::std::result::Result<(Self, #_crate::provenance::Provenance), #_crate::Error> {
+                        let mut __provenance = #_crate::provenance::Provenance::default();
+                        let __extracted = Self::__extract_recording(
+                            __elem,
+                            &#_crate::options::ExtractOptions::new(),
+                            ::std::option::Option::Some(&mut __provenance),
+                        )?;
+                        ::std::result::Result::Ok((__extracted, __provenance))
+                    }
+                }
+            }
+        });
+
+        let extract_body = quote! {
+            #bind_context
+            #(#field_extract)*
+            #[allow(unused_mut)]
+            let mut __extracted = Self {
+                #(#field_init)*
+            };
+            #after_extract
+            #validate
+            ::std::result::Result::Ok(__extracted)
+        };
+
+        // the names given with `from page ..`, in the order of the fields
+        let mut pages = Vec::new();
+        for page in self
+            .fields
+            .iter()
+            .filter_map(|f| f.extractor().and_then(|e| e.page.as_ref()))
+        {
+            let page_name = get_literal_str_value(page);
+            if page_name == "main" {
+                abort!(page, "the page `main` is the main page, from which the fields without `from page ..` are extracted");
+            }
+            if !pages.iter().any(|(name, _)| *name == page_name) {
+                pages.push((page_name, page));
+            }
+        }
+        let multi_page = pages.first().map(|_| {
+            if let Some(root) = &self.root {
+                abort!(root, "the root selector and `from page ..` cannot be used for the same struct");
+            }
+            let page_literals = pages.iter().map(|(_, page)| page);
+            quote! {
+                #(#cfg)*
+                impl #impl_generics #name #ty_generics #where_clause {
+                    /// Parses multiple HTML strings with their page names and extracts data from them.
+                    ///
+                    /// See [`extract_multi_from_elements`](Self::extract_multi_from_elements) for the page names.
+                    #vis fn extract_multi(pages: &[(&str, &str)]) -> ::std::result::Result<Self, #_crate::Error> {
+                        let docs = pages
+                            .iter()
+                            .map(|(page, html_str)| (*page, #_crate::scraper::Html::parse_document(html_str)))
+                            .collect::<::std::vec::Vec<_>>();
+                        let roots = docs
+                            .iter()
+                            .map(|(page, html)| (*page, html.root_element()))
+                            .collect::<::std::vec::Vec<_>>();
+                        Self::extract_multi_from_elements(&roots, &#_crate::options::ExtractOptions::new())
+                    }
+                    /// Extracts data from multiple elements with their page names.
+                    ///
+                    /// The page named `main` is the main page, from which the fields without `from page ..` are extracted.
+                    /// The other pages must be named as in `from page ..` of the fields, and each name can be given only once.
+                    #vis fn extract_multi_from_elements(
+                        pages: &[(&str, #_crate::scraper::ElementRef)],
+                        options: &#_crate::options::ExtractOptions,
+                    ) -> ::std::result::Result<Self, #_crate::Error> {
+                        for (i, (page, _)) in pages.iter().enumerate() {
+                            if *page != "main" && ![#(#page_literals),*].contains(page) {
+                                return ::std::result::Result::Err(#_crate::error::Error::InvalidInput(
+                                    ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
+                                        "extracting struct `",
+                                        ::std::stringify!(#name),
+                                        "`, page `{}` is not used by any field"
+                                    ), page))
+                                ));
+                            }
+                            if pages[..i].iter().any(|(other, _)| other == page) {
+                                return ::std::result::Result::Err(#_crate::error::Error::InvalidInput(
+                                    ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
+                                        "extracting struct `",
+                                        ::std::stringify!(#name),
+                                        "`, page `{}` is given more than once"
+                                    ), page))
+                                ));
+                            }
+                        }
+                        let (_, main) = pages.iter().find(|(page, _)| *page == "main").ok_or(
+                            #_crate::error::Error::InvalidInput(::std::borrow::Cow::Borrowed(::std::concat!(
+                                "extracting struct `",
+                                ::std::stringify!(#name),
+                                "`, the main page `main` is not given"
+                            )))
+                        )?;
+                        Self::__extract_pages(main, pages, options)
+                    }
+                    #[doc(hidden)]
+                    fn __extract_pages(
+                        __elem: &#_crate::scraper::ElementRef,
+                        __pages: &[(&str, #_crate::scraper::ElementRef)],
+                        __options: &#_crate::options::ExtractOptions,
+                    ) -> ::std::result::Result<Self, #_crate::Error> {
+                        #extract_body
+                    }
+                }
+            }
+        });
+        // `#![provenance]` structures extract with a method that records the provenance if it is given
+        let (extract_body, recording) = match &self.options.provenance {
+            Some(_) => (
+                quote! {
+                    Self::__extract_recording(__elem, __options, ::std::option::Option::None)
+                },
+                Some(quote! {
+                    #(#cfg)*
+                    impl #impl_generics #name #ty_generics #where_clause {
+                        #[doc(hidden)]
+                        #[allow(unused_mut)]
+                        fn __extract_recording(
+                            __elem: &#_crate::scraper::ElementRef,
+                            __options: &#_crate::options::ExtractOptions,
+                            mut __provenance: ::std::option::Option<&mut #_crate::provenance::Provenance>,
+                        ) -> ::std::result::Result<Self, #_crate::Error> {
+                            #extract_body
+                        }
+                    }
+                }),
+            ),
+            None => (extract_body, None),
+        };
+
+        let field_into = self
+            .fields
+            .iter()
+            .map(|f| f.extract_into_tokens(&self.name));
+        let field_replace = self.fields.iter().map(|f| f.replace_tokens());
+        let field_restore = self.fields.iter().map(|f| f.restore_tokens());
+        let extracted_fields = self.fields.iter().filter(|f| f.extractor().is_some());
+        let result_names = extracted_fields
+            .clone()
+            .map(|f| format_ident!("__result_{}", f.key_str()))
+            .collect::<Vec<_>>();
+        let field_cfg = extracted_fields.map(|f| f.cfg()).collect::<Vec<_>>();
+        let after_extract_into = self.options.after_extract.as_ref().map(|after_extract| {
+            quote! {
+                (#after_extract)(self)?;
+            }
+        });
+        let validate_into = validate_on(quote!(&*self));
+        let extract_into_body = quote! {
+            #bind_context
+            #default_options
+            #no_provenance
+            #(#field_into)*
+            #(
+                #(#field_cfg)*
+                let #result_names = match __policy {
+                    #_crate::UpdatePolicy::AllOrNothing => ::std::result::Result::Ok(#result_names?),
+                    _ => #result_names,
+                };
+            )*
+            #[allow(unused_mut)]
+            let mut __failures = ::std::vec::Vec::new();
+            #(#field_replace)*
+            let __checked = (|| -> ::std::result::Result<(), #_crate::Error> {
+                #after_extract_into
+                #validate_into
+                ::std::result::Result::Ok(())
+            })();
+            if let ::std::result::Result::Err(e) = __checked {
+                #(#field_restore)*
+                return ::std::result::Result::Err(e);
+            }
+            ::std::result::Result::Ok(__failures)
+        };
+
+        let extract_all = self.root.as_ref().map(|root| {
+            if let Err(err) = scraper::Selector::parse(&get_literal_str_value(root)) {
+                abort!(root, "cannot parse the selector: {:?}", err);
+            }
+            quote! {
+                #(#cfg)*
+                impl #impl_generics #name #ty_generics #where_clause {
+                    /// Returns an iterator that lazily extracts data from each element that matches the root selector.
+                    #vis fn extract_iter<'__a>(
+                        __elem: &#_crate::scraper::ElementRef<'__a>,
+                    ) -> impl ::std::iter::Iterator<Item = ::std::result::Result<Self, #_crate::Error>> + '__a
+                    where
+                        Self: '__a,
+                    {
+                        #_crate::lazy_static::lazy_static! {
+                            static ref ROOT: #_crate::scraper::Selector = #_crate::scraper::Selector::parse(#root).unwrap();
+                        }
+                        __elem.select(&*ROOT).map(|__root| <Self as #_crate::HtmlExtractor>::extract(&__root))
+                    }
+                    /// Extracts data from each element that matches the root selector.
+                    #vis fn extract_all(
+                        __elem: &#_crate::scraper::ElementRef,
+                    ) -> ::std::result::Result<::std::vec::Vec<Self>, #_crate::Error> {
+                        Self::extract_iter(__elem).collect()
+                    }
+                    /// Parses HTML string and extracts data from each element that matches the root selector.
+                    #vis fn extract_all_from_str(
+                        html_str: &str,
+                    ) -> ::std::result::Result<::std::vec::Vec<Self>, #_crate::Error> {
+                        let html = #_crate::scraper::Html::parse_document(html_str);
+                        Self::extract_all(&html.root_element())
+                    }
                 }
             }
+        });
+
+        // multi-page structures are extracted only with `extract_multi`, as the fields with `from page ..` need the pages
+        let extract_impl = match &self.options.context {
+            _ if multi_page.is_some() => None,
+            Some((_, ty)) => Some(quote! {
+                #(#cfg)*
+                impl #impl_generics #_crate::context::ExtractWithContext<#(#ty)*> for #name #ty_generics #where_clause {
+                    fn extract_with_context_and_options(
+                        __elem: &#_crate::scraper::ElementRef,
+                        __context: &#(#ty)*,
+                        __options: &#_crate::options::ExtractOptions,
+                    ) -> ::std::result::Result<Self, #_crate::Error> {
+                        #extract_body
+                    }
+                    #[allow(clippy::redundant_closure_call)]
+                    fn extract_into_with_context(
+                        &mut self,
+                        __elem: &#_crate::scraper::ElementRef,
+                        __policy: #_crate::UpdatePolicy,
+                        __context: &#(#ty)*,
+                    ) -> ::std::result::Result<::std::vec::Vec<#_crate::error::FieldError>, #_crate::Error> {
+                        #extract_into_body
+                    }
+                }
+            }),
+            None => Some(quote! {
+                #(#cfg)*
+                impl #impl_generics #_crate::HtmlExtractor for #name #ty_generics #where_clause {
+                    fn extract(__elem: &#_crate::scraper::ElementRef) -> ::std::result::Result<Self, #_crate::Error> {
+                        <Self as #_crate::HtmlExtractor>::extract_with_options(__elem, &#_crate::options::ExtractOptions::new())
+                    }
+                    fn extract_with_options(
+                        __elem: &#_crate::scraper::ElementRef,
+                        __options: &#_crate::options::ExtractOptions,
+                    ) -> ::std::result::Result<Self, #_crate::Error> {
+                        #extract_body
+                    }
+                    #[allow(clippy::redundant_closure_call)]
+                    fn extract_into(
+                        &mut self,
+                        __elem: &#_crate::scraper::ElementRef,
+                        __policy: #_crate::UpdatePolicy,
+                    ) -> ::std::result::Result<::std::vec::Vec<#_crate::error::FieldError>, #_crate::Error> {
+                        #extract_into_body
+                    }
+                }
+            }),
+        };
+
+        let def = if self.derived {
+            None
+        } else if self.tuple {
+            Some(quote! {
+                #attr
+                #vis struct #name #generics (
+                    #(#field_def)*
+                ) #where_clause;
+            })
+        } else {
+            Some(quote! {
+                #attr
+                #vis struct #name #generics #where_clause {
+                    #(#field_def)*
+                }
+            })
+        };
+
+        tokens.extend(quote!(
+            #def
+            #getters
+            #extract_all
+            #multi_page
+            #recording
+            #incremental
+            #provenance
+            #extract_impl
         ));
     }
 }
@@ -207,9 +1121,14 @@ enum Field {
         fields: Vec<SingleField>,
         extractor: Extractor,
     },
+    /// A field that is not extracted, `(skip)` or `(skip, init with ..)`.
+    Skip {
+        field: SingleField,
+        init: Option<Vec<TokenTree>>,
+    },
 }
 impl Field {
-    fn parse(ts: &mut TokenStreamIter) -> Field {
+    fn parse(ts: &mut TokenStreamIter, defs: &Definitions) -> Field {
         match ts.peek_ex("(..), visibility or identifier") {
             Group(g) if g.delimiter() == Delimiter::Parenthesis => {
                 //Tuple
@@ -224,12 +1143,16 @@ impl Field {
 
                 ts.expect("=");
 
-                let extractor = Extractor::parse(ts);
+                let extractor = Extractor::parse(ts, defs);
 
-                if extractor.capture.is_none() {
+                if extractor.capture.is_none()
+                    && !extractor
+                        .targets()
+                        .all(|t| matches!(t, ExtractTarget::AttributeTuple { .. }))
+                {
                     abort!(
                         fields[0].name,
-                        "parsing to tuple fields requires capturing with regex"
+                        "parsing to tuple fields requires capturing with regex or `attrs[..] of ..`"
                     );
                 }
 
@@ -241,83 +1164,373 @@ impl Field {
 
                 ts.expect("=");
 
-                let extractor = Extractor::parse(ts);
+                Field::parse_single(field, ts, defs)
+            }
+        }
+    }
 
+    /// Parses the extractor of a single field, which may be `(skip)`.
+    fn parse_single(field: SingleField, ts: &mut TokenStreamIter, defs: &Definitions) -> Field {
+        match parse_skip(ts) {
+            Some(init) => Field::Skip { field, init },
+            None => {
+                let extractor = Extractor::parse(ts, defs);
                 Field::Single { field, extractor }
             }
         }
     }
 
-    fn def_tokens(&self) -> TokenStream {
-        let mut ts = TokenStream::new();
+    /// Parses the fields of a tuple structure, `Type = (..)`.
+    fn parse_positional(ts: &mut TokenStreamIter, defs: &Definitions) -> Vec<Field> {
+        let mut fields = Vec::new();
+        while !ts.is_finished() {
+            let attr = Attributes::parse(ts);
+            // removing a field would shift the indices of the following ones
+            if let Some(cfg) = attr.cfg().into_iter().next() {
+                abort!(
+                    cfg,
+                    "`#[cfg]` cannot be used on the fields of tuple structures"
+                );
+            }
+            let vis = Visibility::parse(ts);
+            let ty = parse_type(ts);
+            ts.expect("=");
+            let index = fields.len();
+            let field = SingleField {
+                attr,
+                vis,
+                name: Ident(format_ident!("__{}", index)),
+                ty,
+                member: Some(Literal(proc_macro2::Literal::usize_unsuffixed(index))),
+            };
+            fields.push(Field::parse_single(field, ts, defs));
+            ts.expect_or_none(",");
+        }
+        fields
+    }
+
+    /// The extractor, or `None` for skipped fields.
+    fn extractor(&self) -> Option<&Extractor> {
         match self {
-            Field::Single { field, .. } => {
-                let attr = &field.attr;
-                let vis = &field.vis;
-                let name = &field.name;
-                let ty = &field.ty;
-                ts.extend(quote!(
+            Field::Single { extractor, .. } => Some(extractor),
+            Field::Tuple { extractor, .. } => Some(extractor),
+            Field::Skip { .. } => None,
+        }
+    }
+    fn extractor_mut(&mut self) -> Option<&mut Extractor> {
+        match self {
+            Field::Single { extractor, .. } => Some(extractor),
+            Field::Tuple { extractor, .. } => Some(extractor),
+            Field::Skip { .. } => None,
+        }
+    }
+    fn single_fields(&self) -> Vec<&SingleField> {
+        match self {
+            Field::Single { field, .. } => vec![field],
+            Field::Tuple { fields, .. } => fields.iter().collect(),
+            Field::Skip { field, .. } => vec![field],
+        }
+    }
+    /// The `#[cfg(..)]` attributes of all the fields, since the fields of a tuple field are extracted together.
+    fn cfg(&self) -> Vec<TokenStream> {
+        self.single_fields()
+            .into_iter()
+            .flat_map(|f| f.attr.cfg())
+            .collect()
+    }
+    fn def_tokens(&self, getters: bool) -> TokenStream {
+        let cfg = self.cfg();
+        let mut ts = TokenStream::new();
+        for field in self.single_fields() {
+            let attr = &field.attr;
+            let vis = if getters { None } else { Some(&field.vis) };
+            let name = &field.name;
+            let ty = &field.ty;
+            ts.extend(match field.member {
+                Some(_) => quote!(
+                    #attr
+                    #vis #(#ty)*,
+                ),
+                None => quote!(
+                    #(#cfg)*
                     #attr
                     #vis #name: #(#ty)*,
-                ));
-            }
-            Field::Tuple { fields, .. } => {
-                for field in fields {
-                    let attr = &field.attr;
-                    let vis = &field.vis;
-                    let name = &field.name;
-                    let ty = &field.ty;
-                    ts.extend(quote!(
-                        #attr
-                        #vis #name: #(#ty)*,
-                    ));
+                ),
+            });
+        }
+        ts
+    }
+    fn getter_tokens(&self) -> TokenStream {
+        let cfg = self.cfg();
+        let mut ts = TokenStream::new();
+        for field in self.single_fields() {
+            let doc = field.attr.doc();
+            let vis = &field.vis;
+            let name = &field.name;
+            let ty = &field.ty;
+            ts.extend(quote!(
+                #(#cfg)*
+                #(#doc)*
+                #vis fn #name(&self) -> &#(#ty)* {
+                    &self.#name
                 }
-            }
+            ));
         }
         ts
     }
     fn extract_tokens(&self, struct_name: &TokenTree) -> TokenStream {
-        match self {
+        let value_ts = match self {
             Field::Single { field, extractor } => {
-                let name = &field.name;
-                let extractor_ts = extractor.to_tokens(struct_name, &field.name);
-                quote!(
-                    let #name = #extractor_ts;
-                )
+                let extractor_ts = extractor.to_tokens(struct_name, &[field], false);
+                quote!((#extractor_ts,))
             }
             Field::Tuple { fields, extractor } => {
-                let names = fields.iter().map(|f| &f.name);
-                let extractor_ts = extractor.to_tokens(struct_name, &fields[0].name);
-                quote!(
-                    let (#(#names,)*) = #extractor_ts;
-                )
+                extractor.to_tokens(struct_name, &fields.iter().collect::<Vec<_>>(), true)
             }
-        }
-    }
-    fn init_tokens(&self) -> TokenStream {
-        match self {
-            Field::Single { field, .. } => {
-                let name = &field.name;
-                quote!(
-                    #name,
-                )
+            Field::Skip { init, .. } => {
+                let init_ts = match init {
+                    Some(expr) => quote!(#(#expr)*),
+                    None => quote!(::std::default::Default::default()),
+                };
+                quote!((#init_ts,))
             }
-            Field::Tuple { fields, .. } => {
-                let names = fields.iter().map(|f| &f.name);
-                quote!(
-                    #(#names,)*
-                )
+        };
+        let cfg = self.cfg();
+        let locals = self.locals();
+        let ty = self.types();
+        let bind_ts = self.bind_tokens(quote!((#(&#locals,)*)));
+        quote!(
+            #(#cfg)*
+            let (#(#locals,)*): (#(#ty,)*) = #value_ts;
+            #bind_ts
+        )
+    }
+    /// The types of the fields, which are given to the local variables explicitly,
+    /// since the uses of the references in the later fields can mislead type inference.
+    fn types(&self) -> Vec<TokenStream> {
+        self.single_fields()
+            .into_iter()
+            .map(|f| {
+                let ty = &f.ty;
+                quote!(#(#ty)*)
+            })
+            .collect()
+    }
+    /// The local variables that hold the values of the fields until the structure is constructed.
+    fn locals(&self) -> Vec<proc_macro2::Ident> {
+        self.single_fields()
+            .into_iter()
+            .map(|f| format_ident!("__field_{}", f.name.to_string().trim_start_matches("r#")))
+            .collect()
+    }
+    /// Binds the references to the values of the fields, given as a tuple, to the names of the fields prefixed with `field_`,
+    /// so that the expressions of the later fields can use them without shadowing the other items of the same names.
+    fn bind_tokens(&self, values: TokenStream) -> TokenStream {
+        let cfg = self.cfg();
+        let names = self
+            .single_fields()
+            .into_iter()
+            .map(|f| format_ident!("field_{}", f.name.to_string().trim_start_matches("r#")));
+        quote!(
+            #(#cfg)*
+            #[allow(unused_variables)]
+            let (#(#names,)*) = #values;
+        )
+    }
+    /// The name that represents the field, which is the first one for tuple fields.
+    fn key(&self) -> &TokenTree {
+        self.single_fields()[0].member()
+    }
+    /// The key without the `r#` prefix of raw identifiers, for generating local variable names.
+    fn key_str(&self) -> String {
+        self.key().to_string().trim_start_matches("r#").to_owned()
+    }
+    /// Skipped fields are kept as they are, so this and the following methods generate nothing else for them.
+    fn extract_into_tokens(&self, struct_name: &TokenTree) -> TokenStream {
+        let members = self
+            .single_fields()
+            .into_iter()
+            .map(|f| f.member())
+            .collect::<Vec<_>>();
+        let extractor = match self.extractor() {
+            Some(extractor) => extractor,
+            None => return self.bind_tokens(quote!((#(&self.#members,)*))),
+        };
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let result = format_ident!("__result_{}", self.key_str());
+        let ty = self.types();
+        let tuple = matches!(self, Field::Tuple { .. });
+        let extractor_ts = extractor.to_tokens(struct_name, &self.single_fields(), tuple);
+        let extractor_ts = if tuple {
+            extractor_ts
+        } else {
+            quote!((#extractor_ts,))
+        };
+        let names = self
+            .single_fields()
+            .into_iter()
+            .map(|f| &f.name)
+            .collect::<Vec<_>>();
+        // the previous values are used for the fields that fail
+        let bind_ts = self.bind_tokens(quote! {
+            match &#result {
+                ::std::result::Result::Ok((#(#names,)*)) => (#(#names,)*),
+                ::std::result::Result::Err(_) => (#(&self.#members,)*),
             }
+        });
+        let cfg = self.cfg();
+        quote! {
+            #(#cfg)*
+            let #result = (|| -> ::std::result::Result<(#(#ty,)*), #_crate::Error> {
+                ::std::result::Result::Ok(#extractor_ts)
+            })();
+            #bind_ts
         }
     }
+    fn replace_tokens(&self) -> TokenStream {
+        if self.extractor().is_none() {
+            return TokenStream::new();
+        }
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let key = self.key();
+        let result = format_ident!("__result_{}", self.key_str());
+        let previous = format_ident!("__previous_{}", self.key_str());
+        let names = self
+            .single_fields()
+            .into_iter()
+            .map(|f| &f.name)
+            .collect::<Vec<_>>();
+        let members = self.single_fields().into_iter().map(|f| f.member());
+        let cfg = self.cfg();
+        quote! {
+            #(#cfg)*
+            let #previous = match #result {
+                ::std::result::Result::Ok((#(#names,)*)) => {
+                    ::std::option::Option::Some((#(::std::mem::replace(&mut self.#members, #names),)*))
+                }
+                ::std::result::Result::Err(error) => {
+                    __failures.push(#_crate::error::FieldError {
+                        field: ::std::stringify!(#key),
+                        error,
+                    });
+                    ::std::option::Option::None
+                }
+            };
+        }
+    }
+    fn restore_tokens(&self) -> TokenStream {
+        if self.extractor().is_none() {
+            return TokenStream::new();
+        }
+        let previous = format_ident!("__previous_{}", self.key_str());
+        let names = self
+            .single_fields()
+            .into_iter()
+            .map(|f| &f.name)
+            .collect::<Vec<_>>();
+        let members = self.single_fields().into_iter().map(|f| f.member());
+        let cfg = self.cfg();
+        quote! {
+            #(#cfg)*
+            if let ::std::option::Option::Some((#(#names,)*)) = #previous {
+                #(self.#members = #names;)*
+            }
+        }
+    }
+    fn fingerprint_tokens(&self) -> TokenStream {
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let extractor = match self.extractor() {
+            Some(extractor) => extractor,
+            None => return TokenStream::new(),
+        };
+        let key = self.key();
+        // the elements of all the targets, since a change in any of them can change the chosen one
+        let mut selects = extractor.targets().map(|target| {
+            let selector_static = target.selector_static();
+            let select = target.select_tokens();
+            quote!({
+                #selector_static
+                #select
+            })
+        });
+        let first = selects.next();
+        let hash_ts = extractor.scope_tokens(
+            quote!(#_crate::incremental::hash_elements(#first #(.chain(#selects))*)),
+            quote!(#_crate::incremental::hash_elements(::std::iter::empty())),
+        );
+        let cfg = self.cfg();
+        quote!(
+            #(#cfg)*
+            {
+                __fingerprint.__push(::std::stringify!(#key), #hash_ts);
+            }
+        )
+    }
+    fn reextract_tokens(&self, struct_name: &TokenTree) -> TokenStream {
+        let key = self.key();
+        let locals = self.locals();
+        let ty = self.types();
+        let members = self.single_fields().into_iter().map(|f| f.member());
+        let cfg = self.cfg();
+        let bind_ts = self.bind_tokens(quote!((#(&#locals,)*)));
+        let extractor = match self.extractor() {
+            Some(extractor) => extractor,
+            // skipped fields are always kept
+            None => {
+                return quote!(
+                    #(#cfg)*
+                    let (#(#locals,)*): (#(#ty,)*) = (#(__previous.#members,)*);
+                    #bind_ts
+                )
+            }
+        };
+        let tuple = matches!(self, Field::Tuple { .. });
+        let extractor_ts = extractor.to_tokens(struct_name, &self.single_fields(), tuple);
+        let extractor_ts = if tuple {
+            extractor_ts
+        } else {
+            quote!((#extractor_ts,))
+        };
+        quote!(
+            #(#cfg)*
+            let (#(#locals,)*): (#(#ty,)*) = if __previous_fingerprint.get(::std::stringify!(#key))
+                == __fingerprint.get(::std::stringify!(#key))
+            {
+                (#(__previous.#members,)*)
+            } else {
+                __recomputed.push(::std::stringify!(#key));
+                #extractor_ts
+            };
+            #bind_ts
+        )
+    }
+    fn init_tokens(&self) -> TokenStream {
+        let cfg = self.cfg();
+        let cfg = quote!(#(#cfg)*);
+        let members = self.single_fields().into_iter().map(|f| f.member());
+        let locals = self.locals();
+        quote!(
+            #(
+                #cfg
+                #members: #locals,
+            )*
+        )
+    }
 }
 struct SingleField {
     attr: Attributes,
     vis: Visibility,
+    /// The name of the field, or the local variable for the fields of tuple structures.
     name: TokenTree,
     ty: Vec<TokenTree>,
+    /// The index of the field in tuple structures.
+    member: Option<TokenTree>,
 }
 impl SingleField {
+    /// The name or the index to access the field with.
+    fn member(&self) -> &TokenTree {
+        self.member.as_ref().unwrap_or(&self.name)
+    }
     fn parse(ts: &mut TokenStreamIter) -> Self {
         let attr = Attributes::parse(ts);
         let vis = Visibility::parse(ts);
@@ -325,31 +1538,59 @@ impl SingleField {
 
         ts.expect(":");
 
-        let mut ty = Vec::<TokenTree>::new();
-        while !ts.is_finished() && {
-            let peek = ts.peek_ex_str("`,` or `=`");
-            peek != "," && peek != "="
-        } {
-            ty.push(ts.next_ex(","));
-        }
+        let ty = parse_type(ts);
 
         Self {
             attr,
             vis,
             name,
             ty,
+            member: None,
         }
     }
 }
 
 struct Extractor {
     target: ExtractTarget,
+    /// The targets given with `else`, tried in order if the selector of the previous one matches nothing.
+    fallbacks: Vec<ExtractTarget>,
+    /// The selectors of the enclosing `in ".." {..}` blocks, from the outermost.
+    scopes: Vec<TokenTree>,
+    transforms: Vec<Transform>,
     capture: Option<TokenTree>,
+    /// Whether the capture groups are mapped to the fields by name, with `capture by name with ..`.
+    capture_by_name: bool,
     collector: ExtractCollector,
-    parser: Vec<TokenTree>,
+    parser: Option<Vec<TokenTree>>,
+    /// The function of `map with ..`, applied to each parsed value.
+    mapper: Option<Vec<TokenTree>>,
+    /// The predicate of `filter with ..`, which drops the collected items that fail it.
+    filter: Option<Vec<TokenTree>>,
+    /// The extractors of `key with (..)` and `value with (..)`, applied to each collected element
+    /// to make the `(key, value)` items.
+    key: Option<Box<Extractor>>,
+    value: Option<Box<Extractor>>,
+    /// Whether the key is given by `group by (..)`, which groups the values with the same key.
+    group: bool,
+    /// The extractor of `zip with (..)`, whose items are paired with the collected items.
+    zip: Option<Box<Extractor>>,
+    /// The initial value and the function of `fold from .. with ..`, which accumulate the items into the field.
+    fold: Option<(Option<Vec<TokenTree>>, Vec<TokenTree>)>,
+    /// The separator of `join with ".."`, which joins the strings of the matched elements before parsing.
+    join: Option<TokenTree>,
+    /// The predicate of `validate with ..`.
+    validator: Option<Vec<TokenTree>>,
+    page: Option<TokenTree>,
+    /// Whether the leading and trailing whitespace of the text is removed, which `no_trim` turns off.
+    trim: bool,
+    /// The type of the context of the structure, which is given to the nested structures.
+    context: Option<Vec<TokenTree>>,
+    /// Whether the selectors and the elements the data comes from are recorded into `__provenance`, for `#![provenance]`.
+    /// The key, value and zip extractors don't record them.
+    record: bool,
 }
 impl Extractor {
-    fn parse(ts: &mut TokenStreamIter) -> Self {
+    fn parse(ts: &mut TokenStreamIter, defs: &Definitions) -> Self {
         let extractor_tt = ts.next_ex("`(..)`");
         let mut extractor_ts: TokenStreamIter = match &extractor_tt {
             Group(g) if g.delimiter() == Delimiter::Parenthesis => {
@@ -359,18 +1600,48 @@ impl Extractor {
         };
 
         let mut target = None;
+        let mut alternatives = Vec::new();
+        let mut transforms = Vec::new();
         let mut capture = None;
+        let mut capture_by_name = false;
         let mut collector = ExtractCollector::First;
         let mut parser = None;
+        let mut mapper = None;
+        let mut filter = None;
+        let mut key = None;
+        let mut value = None;
+        let mut group = false;
+        let mut zip = None;
+        let mut fold = None;
+        let mut join = None;
+        let mut validator = None;
+        let mut page = None;
+        let mut trim = true;
 
         while !extractor_ts.is_finished() {
+            let specifier = extractor_ts.peek_ex_str("specifier");
             match &*extractor_ts.next_ex_str(
-                "`elem`, `attr`, `text`, `inner_html`, `presence`, `capture`, `collect`, `optional` or `parse`",
+                "`elem`, `shadow`, `attr`, `attrs`, `dataset`, `style`, `text`, `own_text`, `text_all`, `texts`, `next_text`, `inner_html`, `inner_markdown`, `sanitized_html`, `rendered_text`, `position`, `presence`, `replace`, `normalize_whitespace`, `no_trim`, `lowercase`, `uppercase`, `decode_entities`, `capture`, `collect`, `optional`, `or`, `default`, `unique`, `parse`, `validate` or `from`",
             ) {
                 "elem" => {
                     extractor_ts.expect("of");
-                    let selector = extractor_ts.next_ex("literal string").clone();
-                    target = Some(ExtractTarget::Element { selector });
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::Element {
+                        selector,
+                        position,
+                        navigation,
+                        shadow: false,
+                    });
+                }
+                "shadow" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::Element {
+                        selector,
+                        position,
+                        navigation,
+                        shadow: true,
+                    });
                 }
                 "attr" => {
                     let attribute = match extractor_ts.next_ex("`[..]`") {
@@ -380,10 +1651,75 @@ impl Extractor {
                         tt => abort!(tt, "expected `[..]`, found {}", tt),
                     };
                     extractor_ts.expect("of");
-                    let selector = extractor_ts.next_ex("literal string").clone();
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
                     target = Some(ExtractTarget::Attribute {
                         attribute,
                         selector,
+                        position,
+                        navigation,
+                    });
+                }
+                "attrs" => {
+                    // `attrs["a", "b"] of ..` extracts the attributes into a tuple
+                    let attributes = match extractor_ts.next_ex("`[..]` or `of`") {
+                        Group(g) if g.delimiter() == Delimiter::Bracket => {
+                            extractor_ts.expect("of");
+                            let mut list = g.stream().into_iter().peekable();
+                            let mut attributes = Vec::new();
+                            while !list.is_finished() {
+                                let attribute = list.next_ex("literal string");
+                                get_literal_str_value(&attribute);
+                                attributes.push(attribute);
+                                list.expect_or_none(",");
+                            }
+                            if attributes.is_empty() {
+                                abort!(g, "expected attribute names in `[..]`");
+                            }
+                            Some(attributes)
+                        }
+                        tt if tt.to_string() == "of" => None,
+                        tt => abort!(tt, "expected `[..]` or `of`, found {}", tt),
+                    };
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(match attributes {
+                        Some(attributes) => ExtractTarget::AttributeTuple {
+                            attributes,
+                            selector,
+                            position,
+                            navigation,
+                        },
+                        None => ExtractTarget::AllAttributes {
+                            selector,
+                            position,
+                            navigation,
+                            dataset: false,
+                        },
+                    });
+                }
+                "dataset" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::AllAttributes {
+                        selector,
+                        position,
+                        navigation,
+                        dataset: true,
+                    });
+                }
+                "style" => {
+                    let property = match extractor_ts.next_ex("`[..]`") {
+                        Group(g) if g.delimiter() == Delimiter::Bracket => {
+                            g.stream().into_iter().peekable().next_ex("literal string")
+                        }
+                        tt => abort!(tt, "expected `[..]`, found {}", tt),
+                    };
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::Style {
+                        property,
+                        selector,
+                        position,
+                        navigation,
                     });
                 }
                 "text" => {
@@ -396,44 +1732,302 @@ impl Extractor {
                         tt => abort!(tt, "expected `[..]` or `of`, found {}", tt),
                     };
 
-                    let selector = extractor_ts.next_ex("literal string").clone();
-                    target = Some(ExtractTarget::TextNode { nth, selector });
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::TextNode {
+                        nth,
+                        selector,
+                        position,
+                        navigation,
+                    });
+                }
+                "own_text" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::OwnText { selector, position, navigation });
+                }
+                "text_all" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::TextAll { selector, position, navigation });
+                }
+                "texts" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) =
+                        defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::TextNodes {
+                        selector,
+                        position,
+                        navigation,
+                    });
+                }
+                "next_text" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) =
+                        defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::NextText {
+                        selector,
+                        position,
+                        navigation,
+                    });
                 }
                 "inner_html" => {
                     extractor_ts.expect("of");
-                    let selector = extractor_ts.next_ex("literal string").clone();
-                    target = Some(ExtractTarget::InnerHTML { selector });
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::InnerHTML { selector, position, navigation });
+                }
+                "inner_markdown" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) =
+                        defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::InnerMarkdown {
+                        selector,
+                        position,
+                        navigation,
+                    });
+                }
+                "sanitized_html" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) =
+                        defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::SanitizedHTML {
+                        selector,
+                        position,
+                        navigation,
+                    });
+                }
+                "rendered_text" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) =
+                        defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::RenderedText {
+                        selector,
+                        position,
+                        navigation,
+                    });
+                }
+                "position" => {
+                    extractor_ts.expect("of");
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::SiblingIndex { selector, position, navigation });
                 }
                 "presence" => {
                     extractor_ts.expect("of");
-                    let selector = extractor_ts.next_ex("literal string").clone();
-                    target = Some(ExtractTarget::PresenceOf { selector });
+                    let (selector, position, navigation) = defs.parse_target_selector(&mut extractor_ts);
+                    target = Some(ExtractTarget::PresenceOf { selector, position, navigation });
+                }
+                "replace" => {
+                    let from = extractor_ts.next_ex("literal string");
+                    get_literal_str_value(&from);
+                    extractor_ts.expect("with");
+                    let to = extractor_ts.next_ex("literal string");
+                    get_literal_str_value(&to);
+                    transforms.push(Transform::Replace { from, to });
+                }
+                "normalize_whitespace" => {
+                    transforms.push(Transform::NormalizeWhitespace);
+                }
+                "no_trim" => {
+                    trim = false;
+                }
+                "lowercase" => {
+                    transforms.push(Transform::Lowercase);
+                }
+                "decode_entities" => {
+                    transforms.push(Transform::DecodeEntities);
+                }
+                "uppercase" => {
+                    transforms.push(Transform::Uppercase);
                 }
                 "capture" => {
+                    if extractor_ts.peek().map(|tt| tt.to_string()).as_deref() == Some("by") {
+                        extractor_ts.next();
+                        extractor_ts.expect("name");
+                        capture_by_name = true;
+                    }
                     extractor_ts.expect("with");
                     let regex = extractor_ts.next_ex("literal string").clone();
                     capture = Some(regex);
                 }
                 "collect" => {
-                    collector = ExtractCollector::IntoIterator;
+                    let mut modifiers = CollectModifiers::default();
+                    while let Some(modifier @ ("skip" | "limit" | "enumerate" | "at")) =
+                        extractor_ts.peek().map(|tt| tt.to_string()).as_deref()
+                    {
+                        let mut modifier = modifier.to_owned();
+                        extractor_ts.next();
+                        if modifier == "enumerate" {
+                            modifiers.enumerate = true;
+                            continue;
+                        }
+                        if modifier == "at" {
+                            modifier = match extractor_ts.next_ex("`least` or `most`") {
+                                tt if tt.to_string() == "least" || tt.to_string() == "most" => {
+                                    tt.to_string()
+                                }
+                                tt => abort!(tt, "expected `least` or `most`, found `{}`", tt),
+                            };
+                        }
+                        let count = extractor_ts.next_ex("count");
+                        if let TokenTree::Literal(lit) = &count {
+                            if lit.to_string().parse::<usize>().is_err() {
+                                abort!(count, "expected an integer");
+                            }
+                        }
+                        match &*modifier {
+                            "skip" => modifiers.skip = Some(count),
+                            "limit" => modifiers.limit = Some(count),
+                            "least" => modifiers.at_least = Some(count),
+                            _ => modifiers.at_most = Some(count),
+                        }
+                    }
+                    collector = ExtractCollector::IntoIterator(modifiers);
                 }
                 "optional" => {
                     collector = ExtractCollector::Option;
                 }
-                "parse" => {
+                "unique" => {
+                    collector = ExtractCollector::Unique;
+                }
+                "or" => {
+                    extractor_ts.expect("default");
+                    collector = ExtractCollector::Default(None);
+                }
+                "default" => {
                     extractor_ts.expect("with");
-                    let mut parser_vec = Vec::new();
-                    while !extractor_ts.is_finished() && extractor_ts.peek_ex_str(",") != "," {
-                        parser_vec.push(extractor_ts.next_ex(","));
+                    let default_vec = parse_expression(&mut extractor_ts);
+                    if default_vec.is_empty() {
+                        abort!(extractor_tt, "expected an expression after `default with`");
                     }
+                    collector = ExtractCollector::Default(Some(default_vec));
+                }
+                "parse" => {
+                    extractor_ts.expect("with");
+                    let parser_vec = parse_expression(&mut extractor_ts);
                     parser = Some(parser_vec)
                 }
+                "map" => {
+                    extractor_ts.expect("with");
+                    let mapper_vec = parse_expression(&mut extractor_ts);
+                    if mapper_vec.is_empty() {
+                        abort!(extractor_tt, "expected a function after `map with`");
+                    }
+                    mapper = Some(mapper_vec);
+                }
+                "filter" => {
+                    extractor_ts.expect("with");
+                    let filter_vec = parse_expression(&mut extractor_ts);
+                    if filter_vec.is_empty() {
+                        abort!(extractor_tt, "expected a predicate after `filter with`");
+                    }
+                    filter = Some(filter_vec);
+                }
+                "validate" => {
+                    extractor_ts.expect("with");
+                    let validator_vec = parse_expression(&mut extractor_ts);
+                    if validator_vec.is_empty() {
+                        abort!(extractor_tt, "expected a predicate after `validate with`");
+                    }
+                    validator = Some(validator_vec);
+                }
+                "key" | "group" => {
+                    extractor_ts.expect(if specifier == "key" { "with" } else { "by" });
+                    if key.is_some() {
+                        abort!(
+                            extractor_tt,
+                            "`key with ..` and `group by ..` cannot be used together"
+                        );
+                    }
+                    key = Some(Box::new(Extractor::parse(&mut extractor_ts, defs)));
+                    group = specifier == "group";
+                }
+                "join" => {
+                    extractor_ts.expect("with");
+                    let separator = extractor_ts.next_ex("literal string");
+                    get_literal_str_value(&separator);
+                    join = Some(separator);
+                    if !matches!(collector, ExtractCollector::IntoIterator(_)) {
+                        collector = ExtractCollector::IntoIterator(CollectModifiers::default());
+                    }
+                }
+                "fold" => {
+                    let init = if extractor_ts.peek_ex_str("`from` or `with`") == "from" {
+                        extractor_ts.next();
+                        let mut init_vec = Vec::new();
+                        while !extractor_ts.is_finished() && extractor_ts.peek_ex_str("with") != "with" {
+                            init_vec.push(extractor_ts.next_ex("with"));
+                        }
+                        if init_vec.is_empty() {
+                            abort!(extractor_tt, "expected an expression after `fold from`");
+                        }
+                        Some(init_vec)
+                    } else {
+                        None
+                    };
+                    extractor_ts.expect("with");
+                    let fold_vec = parse_expression(&mut extractor_ts);
+                    if fold_vec.is_empty() {
+                        abort!(extractor_tt, "expected a function after `fold with`");
+                    }
+                    fold = Some((init, fold_vec));
+                }
+                "zip" => {
+                    extractor_ts.expect("with");
+                    let mut extractor = Extractor::parse(&mut extractor_ts, defs);
+                    match extractor.collector {
+                        ExtractCollector::First => {
+                            extractor.collector =
+                                ExtractCollector::IntoIterator(CollectModifiers::default())
+                        }
+                        ExtractCollector::IntoIterator(_) => {}
+                        _ => abort!(
+                            extractor_tt,
+                            "the extractor of `zip with ..` can only use the collector `collect`"
+                        ),
+                    }
+                    zip = Some(Box::new(extractor));
+                }
+                "value" => {
+                    extractor_ts.expect("with");
+                    value = Some(Box::new(Extractor::parse(&mut extractor_ts, defs)));
+                }
+                "from" => {
+                    extractor_ts.expect("page");
+                    let name = extractor_ts.next_ex("literal string");
+                    get_literal_str_value(&name);
+                    page = Some(name);
+                }
                 tt => abort!(
                     tt,
-                    "expected `elem`, `attr`, `text`, `capture` or `collect`, found `{}`",
+                    "expected `elem`, `shadow`, `attr`, `attrs`, `dataset`, `style`, `text`, `own_text`, `text_all`, `texts`, `next_text`, `inner_html`, `inner_markdown`, `sanitized_html`, `rendered_text`, `position`, `presence`, `replace`, `normalize_whitespace`, `no_trim`, `lowercase`, `uppercase`, `decode_entities`, `capture`, `collect`, `optional`, `or`, `default`, `unique`, `parse`, `map`, `filter`, `key`, `group`, `value`, `zip`, `fold`, `join`, `validate` or `from`, found `{}`",
                     tt
                 ),
             }
+            let is_target = [
+                "elem",
+                "shadow",
+                "attr",
+                "attrs",
+                "dataset",
+                "style",
+                "text",
+                "own_text",
+                "text_all",
+                "texts",
+                "next_text",
+                "inner_html",
+                "inner_markdown",
+                "sanitized_html",
+                "rendered_text",
+                "position",
+                "presence",
+            ]
+            .contains(&&*specifier);
+            if is_target && extractor_ts.peek().map(|tt| tt.to_string()).as_deref() == Some("else")
+            {
+                extractor_ts.next();
+                alternatives.extend(target.take());
+                continue;
+            }
             extractor_ts.expect_or_none(",");
         }
 
@@ -441,65 +2035,461 @@ impl Extractor {
             Some(t) => t,
             None => abort!(extractor_tt, "target is not specified"),
         };
-
-        if let ExtractTarget::Element { .. } = &target {
-            if capture.is_some() {
+        if filter.is_some() && !matches!(collector, ExtractCollector::IntoIterator(_)) {
+            abort!(
+                extractor_tt,
+                "`filter with ..` can only be used with `collect`"
+            );
+        }
+        if (key.is_some() || value.is_some() || zip.is_some() || fold.is_some())
+            && !matches!(collector, ExtractCollector::IntoIterator(_))
+        {
+            abort!(
+                extractor_tt,
+                "`key with ..`, `group by ..`, `value with ..`, `zip with ..` and `fold with ..` can only be used with `collect`"
+            );
+        }
+        if join.is_some() {
+            if !matches!(&collector, ExtractCollector::IntoIterator(m) if !m.enumerate) {
                 abort!(
                     extractor_tt,
-                    "`elem of ..` and `capture with ..` cannot be used for the same field"
+                    "`join with ..` can only be used with `collect` without `enumerate`"
                 );
             }
-        } else if let ExtractTarget::PresenceOf { .. } = &target {
-            if capture.is_some() || collector != ExtractCollector::First || parser.is_some() {
+            if key.is_some() || value.is_some() || zip.is_some() || fold.is_some() {
                 abort!(
                     extractor_tt,
-                    "`presence of ..` cannot be used with any other specifier"
+                    "`key with ..`, `group by ..`, `value with ..`, `zip with ..` and `fold with ..` cannot be used with `join with ..`"
                 );
             }
+            if !alternatives
+                .iter()
+                .chain(Some(&target))
+                .all(|t| !matches!(t, ExtractTarget::Element { .. }))
+            {
+                abort!(
+                    extractor_tt,
+                    "`join with ..` cannot be used with `elem of ..`"
+                );
+            }
+        }
+        if capture_by_name && matches!(collector, ExtractCollector::IntoIterator(_)) {
+            abort!(
+                extractor_tt,
+                "`capture by name with ..` cannot be used with `collect`"
+            );
+        }
+        if group && fold.is_some() {
+            abort!(
+                extractor_tt,
+                "`group by ..` and `fold with ..` cannot be used together"
+            );
+        }
+        if group && matches!(&collector, ExtractCollector::IntoIterator(m) if m.enumerate) {
+            abort!(
+                extractor_tt,
+                "`group by ..` cannot be used with `collect enumerate`"
+            );
+        }
+        if value.is_some() {
+            if key.is_none() {
+                abort!(
+                    extractor_tt,
+                    "`value with ..` requires `key with ..` or `group by ..`"
+                );
+            }
+            if !alternatives
+                .iter()
+                .chain(Some(&target))
+                .all(|t| matches!(t, ExtractTarget::Element { shadow: false, .. }))
+            {
+                abort!(
+                    extractor_tt,
+                    "`value with ..` can only be used with `elem of ..`"
+                );
+            }
+            if capture.is_some() || parser.is_some() || mapper.is_some() {
+                abort!(
+                    extractor_tt,
+                    "`capture`, `parse` and `map` cannot be used with `value with ..`, write them in the value extractor"
+                );
+            }
+        }
+        alternatives.push(target);
+        let target = alternatives.remove(0);
+        let fallbacks = alternatives;
+
+        let targets = std::iter::once(&target)
+            .chain(&fallbacks)
+            .collect::<Vec<_>>();
+        for target in &targets {
+            if let ExtractTarget::Element { shadow, .. } = target {
+                let name = if *shadow { "shadow" } else { "elem" };
+                if capture.is_some() {
+                    abort!(
+                        extractor_tt,
+                        "`{} of ..` and `capture with ..` cannot be used for the same field",
+                        name
+                    );
+                }
+                if !transforms.is_empty() {
+                    abort!(
+                        extractor_tt,
+                        "`{} of ..` and transforms cannot be used for the same field",
+                        name
+                    );
+                }
+            } else if let ExtractTarget::TextNodes { .. } = target {
+                if capture.is_some() || !transforms.is_empty() || join.is_some() {
+                    abort!(
+                        extractor_tt,
+                        "`texts of ..` cannot be used with `capture with ..`, transforms or `join with ..`"
+                    );
+                }
+            } else if let ExtractTarget::AttributeTuple { .. } = target {
+                if capture.is_some() || !transforms.is_empty() || join.is_some() {
+                    abort!(
+                        extractor_tt,
+                        "`attrs[..] of ..` cannot be used with `capture with ..`, transforms or `join with ..`"
+                    );
+                }
+            } else if let ExtractTarget::AllAttributes { .. } | ExtractTarget::SiblingIndex { .. } =
+                target
+            {
+                let name = match target {
+                    ExtractTarget::AllAttributes { dataset: false, .. } => "attrs",
+                    ExtractTarget::AllAttributes { dataset: true, .. } => "dataset",
+                    _ => "position",
+                };
+                if capture.is_some() || !transforms.is_empty() || parser.is_some() || join.is_some()
+                {
+                    abort!(
+                        extractor_tt,
+                        "`{} of ..` cannot be used with `capture with ..`, transforms, `parse with ..` or `join with ..`",
+                        name
+                    );
+                }
+            } else if let ExtractTarget::PresenceOf { .. } = target {
+                if capture.is_some()
+                    || !matches!(collector, ExtractCollector::First)
+                    || parser.is_some()
+                    || mapper.is_some()
+                    || filter.is_some()
+                    || key.is_some()
+                    || zip.is_some()
+                    || fold.is_some()
+                    || validator.is_some()
+                    || !transforms.is_empty()
+                    || !targets
+                        .iter()
+                        .all(|t| matches!(t, ExtractTarget::PresenceOf { .. }))
+                {
+                    abort!(
+                        extractor_tt,
+                        "`presence of ..` cannot be used with any other specifier"
+                    );
+                }
+            }
         }
 
         Extractor {
             target,
+            fallbacks,
+            scopes: Vec::new(),
+            transforms,
             capture,
+            capture_by_name,
             collector,
-            parser: parser
-                .unwrap_or_else(|| quote!(::std::str::FromStr::from_str).into_iter().collect()),
+            parser,
+            mapper,
+            filter,
+            key,
+            value,
+            group,
+            zip,
+            fold,
+            join,
+            validator,
+            page,
+            trim,
+            context: None,
+            record: false,
+        }
+    }
+    /// Gives the context of type `ty` to the nested structures, including in the key, value and zip extractors.
+    fn context(&mut self, ty: &[TokenTree]) {
+        self.context = Some(ty.to_vec());
+        let sub_extractors = self
+            .key
+            .iter_mut()
+            .chain(&mut self.value)
+            .chain(&mut self.zip);
+        for extractor in sub_extractors {
+            extractor.context(ty);
+        }
+    }
+    /// Keeps the whitespace of the text for `#![no_trim]`, including in the key, value and zip extractors.
+    fn no_trim(&mut self) {
+        self.trim = false;
+        let sub_extractors = self
+            .key
+            .iter_mut()
+            .chain(&mut self.value)
+            .chain(&mut self.zip);
+        for extractor in sub_extractors {
+            extractor.no_trim();
+        }
+    }
+    /// Generates `call` on the provenance being recorded, like `__node(1, &target_elem)`, if the extractor records it.
+    fn record_tokens(&self, call: TokenStream) -> TokenStream {
+        if !self.record {
+            return TokenStream::new();
+        }
+        quote! {
+            if let ::std::option::Option::Some(__provenance) = __provenance.as_deref_mut() {
+                __provenance.#call;
+            }
         }
     }
-    fn to_tokens(&self, struct_name: &TokenTree, field_name: &TokenTree) -> TokenStream {
+    /// The `.skip(..)` and `.take(..)` calls on the iterator of the matched elements of a collected field.
+    fn collect_range_tokens(&self) -> TokenStream {
+        match &self.collector {
+            ExtractCollector::IntoIterator(modifiers) => {
+                let skip = modifiers.skip.iter();
+                let limit = modifiers.limit.iter();
+                quote!(#(.skip(#skip))* #(.take(#limit))*)
+            }
+            _ => TokenStream::new(),
+        }
+    }
+    /// The parser given by the parser specifier, or the default one.
+    fn parser(&self) -> TokenStream {
         let _crate = CRATE.parse::<TokenStream>().unwrap();
+        match &self.parser {
+            Some(parser) => quote!(#(#parser)*),
+            // chooses `FromStr` or the conversion into a smart pointer from the type of the value
+            None => quote!(#_crate::convert::FromText::<_>::from_text),
+        }
+    }
+    /// Emits the expression of the value, which is the tuple of the values of `fields` if `tuple` is true.
+    fn to_tokens(
+        &self,
+        struct_name: &TokenTree,
+        fields: &[&SingleField],
+        tuple: bool,
+    ) -> TokenStream {
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let field_name = fields[0].member();
 
-        let selector = self.target.selector();
-        if let Err(err) = scraper::Selector::parse(&get_literal_str_value(selector)) {
-            abort!(selector, "cannot parse the selector: {:?}", err);
+        for target in self.targets().filter(|t| !t.is_runtime()) {
+            let selector = target.selector();
+            if let Err(err) = scraper::Selector::parse(&get_literal_str_value(selector)) {
+                abort!(selector, "cannot parse the selector: {:?}", err);
+            }
         }
 
+        let page_ts = self.page.as_ref().map(|page| {
+            quote! {
+                let __elem = &__pages.iter().find(|(name, _)| *name == #page).map(|(_, elem)| *elem).ok_or(
+                    #_crate::error::Error::InvalidInput(
+                        ::std::borrow::Cow::Borrowed(::std::concat!(
+                            "extracting the data of field `",
+                            ::std::stringify!(#field_name),
+                            "` in struct `",
+                            ::std::stringify!(#struct_name),
+                            "`, page `",
+                            #page,
+                            "` is not given"
+                        ))
+                    )
+                )?;
+            }
+        });
+        let extract_ts = self
+            .alternatives_tokens(|target| self.target_tokens(target, struct_name, fields, tuple));
+        // the entries start with the first target, and the fields are not recorded if a scope is not found
+        let names = fields.iter().map(|f| f.member());
+        let first_selector = self.target.selector();
+        let begin_ts = self.record_tokens(quote!(__begin(
+            &[#(::std::stringify!(#names)),*],
+            #first_selector,
+        )));
+        let extract_ts = quote! {{
+            #begin_ts
+            #extract_ts
+        }};
+        // a missing scope is the same as a missing element for the collectors that allow it
+        let missing_scope_ts = match &self.collector {
+            ExtractCollector::Option => quote!(None),
+            ExtractCollector::Default(Some(expr)) => quote!(#(#expr)*),
+            ExtractCollector::Default(None) => quote!(::std::default::Default::default()),
+            _ => quote! {
+                return ::std::result::Result::Err(#_crate::error::element_not_found(
+                    ::std::concat!(
+                        "extracting the data of field `",
+                        ::std::stringify!(#field_name),
+                        "` in struct `",
+                        ::std::stringify!(#struct_name),
+                        "`, no element matched the scope selector"
+                    ),
+                    __elem,
+                    __scope_selector,
+                    __options,
+                ))
+            },
+        };
+        let extract_ts = self.scope_tokens(extract_ts, missing_scope_ts);
+        let extract_ts = match &self.validator {
+            Some(validator) => {
+                // the type and the signature are given so that the predicate can be a closure without annotations
+                let ty = fields.iter().map(|f| {
+                    let ty = &f.ty;
+                    quote!(#(#ty)*)
+                });
+                let ty = if tuple {
+                    quote!((#(#ty,)*))
+                } else {
+                    quote!(#(#ty)*)
+                };
+                quote! {{
+                    let __value: #ty = #extract_ts;
+                    fn __validate<T>(value: &T, predicate: impl FnOnce(&T) -> bool) -> bool {
+                        predicate(value)
+                    }
+                    if !__validate(&__value, #(#validator)*) {
+                        return ::std::result::Result::Err(#_crate::error::Error::InvalidInput(
+                            ::std::borrow::Cow::Borrowed(::std::concat!(
+                                "extracting the data of field `",
+                                ::std::stringify!(#field_name),
+                                "` in struct `",
+                                ::std::stringify!(#struct_name),
+                                "`, the validation failed"
+                            ))
+                        ));
+                    }
+                    __value
+                }}
+            }
+            None => extract_ts,
+        };
+
+        quote! {{
+            __options.check()?;
+            #page_ts
+            #extract_ts
+        }}
+    }
+    /// Generates `body` with `__elem` rebound to the element of each scope in turn, or `missing` if a scope is not found,
+    /// where `__scope_selector` is the selector of the scope.
+    fn scope_tokens(&self, body: TokenStream, missing: TokenStream) -> TokenStream {
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        self.scopes.iter().rev().fold(body, |body, scope| {
+            quote! {{
+                #_crate::lazy_static::lazy_static! {
+                    static ref SCOPE: #_crate::scraper::Selector = #_crate::scraper::Selector::parse(#scope).unwrap();
+                }
+                match __elem.select(&*SCOPE).next() {
+                    ::std::option::Option::Some(__scope) => {
+                        let __elem = &__scope;
+                        #body
+                    }
+                    ::std::option::Option::None => {
+                        #[allow(unused_variables)]
+                        let __scope_selector = #scope;
+                        #missing
+                    }
+                }
+            }}
+        })
+    }
+    /// The targets, the first one and then the fallbacks given with `else`.
+    fn targets(&self) -> impl Iterator<Item = &ExtractTarget> {
+        std::iter::once(&self.target).chain(&self.fallbacks)
+    }
+    /// Generates `f(target)` for the first target whose selector matches any element, or the last target.
+    fn alternatives_tokens(&self, f: impl Fn(&ExtractTarget) -> TokenStream) -> TokenStream {
+        let (last, rest) = match self.fallbacks.split_last() {
+            Some((last, rest)) => (last, rest),
+            None => return f(&self.target),
+        };
+        let tried = std::iter::once(&self.target).chain(rest).map(|target| {
+            let selector_static = target.selector_static();
+            let select = target.select_tokens();
+            let ts = f(target);
+            quote! {{
+                #selector_static
+                if #select.next().is_some() {
+                    break '__alternative #ts;
+                }
+            }}
+        });
+        let last_ts = f(last);
+        quote! {
+            '__alternative: {
+                #(#tried)*
+                #last_ts
+            }
+        }
+    }
+    /// Extracts the data of the field from `__elem` with the target.
+    fn target_tokens(
+        &self,
+        target: &ExtractTarget,
+        struct_name: &TokenTree,
+        fields: &[&SingleField],
+        tuple: bool,
+    ) -> TokenStream {
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let field_name = fields[0].member();
+        let selector = target.selector();
+
+        let selector_static = target.selector_static();
+        let select = target.select_tokens();
         let mut regex_captures_len = None;
+        let mut regex_capture_names = Vec::new();
 
         let lazy_static_ts = match &self.capture {
             Some(regex) => {
                 match regex::Regex::new(&get_literal_str_value(regex)) {
-                    Ok(regex) => regex_captures_len = Some(regex.captures_len()),
+                    Ok(regex) => {
+                        regex_captures_len = Some(regex.captures_len());
+                        regex_capture_names =
+                            regex.capture_names().flatten().map(str::to_owned).collect();
+                    }
                     Err(err) => abort!(regex, "cannot parse the regex: {:?}", err),
                 };
                 quote! {
+                    #selector_static
                     #_crate::lazy_static::lazy_static! {
-                        static ref SELECTOR: #_crate::scraper::Selector = #_crate::scraper::Selector::parse(#selector).unwrap();
                         static ref REGEX: #_crate::regex::Regex = #_crate::regex::Regex::new(#regex).unwrap();
                     }
                 }
             }
-            None => quote! {
-                #_crate::lazy_static::lazy_static! {
-                    static ref SELECTOR: #_crate::scraper::Selector = #_crate::scraper::Selector::parse(#selector).unwrap();
-                }
-            },
+            None => selector_static,
         };
 
-        let extract_data_from_elem_ts = match &self.target {
-            ExtractTarget::Element { .. } => quote! {
+        let trim_ts = if self.trim {
+            quote!(data_whitespace.trim())
+        } else {
+            quote!(&*data_whitespace)
+        };
+        let extract_data_from_elem_ts = match &target {
+            ExtractTarget::Element { shadow: false, .. } => quote! {
                 let data = target_elem;
             },
+            ExtractTarget::Element { shadow: true, .. } => quote! {
+                let data = #_crate::shadow_root(&target_elem).ok_or(
+                    #_crate::error::Error::InvalidInput(
+                        ::std::borrow::Cow::Borrowed(::std::concat!(
+                            "extracting the data of field `",
+                            ::std::stringify!(#field_name),
+                            "` in struct `",
+                            ::std::stringify!(#struct_name),
+                            "`, the element has no declarative shadow root"
+                        ))
+                    )
+                )?;
+            },
             ExtractTarget::Attribute { attribute, .. } => quote! {
                 let data = target_elem.value().attr(#attribute).ok_or(
                     #_crate::error::Error::InvalidInput(
@@ -515,6 +2505,55 @@ impl Extractor {
                     )
                 )?;
             },
+            ExtractTarget::AttributeTuple { attributes, .. } => quote! {
+                let data = (#(
+                    target_elem.value().attr(#attributes).ok_or(
+                        #_crate::error::Error::InvalidInput(
+                            ::std::borrow::Cow::Borrowed(::std::concat!(
+                                "extracting the data of field `",
+                                ::std::stringify!(#field_name),
+                                "` in struct `",
+                                ::std::stringify!(#struct_name),
+                                "`, attribute `",
+                                #attributes,
+                                "` is not found"
+                            ))
+                        )
+                    )?,
+                )*);
+            },
+            ExtractTarget::AllAttributes { dataset: false, .. } => quote! {
+                let data = target_elem.value().attrs().map(|(name, value)| {
+                    (
+                        ::std::borrow::ToOwned::to_owned(name),
+                        ::std::borrow::ToOwned::to_owned(value),
+                    )
+                });
+            },
+            ExtractTarget::AllAttributes { dataset: true, .. } => quote! {
+                let data = target_elem.value().attrs().filter_map(|(name, value)| {
+                    ::std::option::Option::Some((
+                        ::std::borrow::ToOwned::to_owned(name.strip_prefix("data-")?),
+                        ::std::borrow::ToOwned::to_owned(value),
+                    ))
+                });
+            },
+            ExtractTarget::Style { property, .. } => quote! {
+                let data_owned = #_crate::style::inline_style(&target_elem, #property).ok_or(
+                    #_crate::error::Error::InvalidInput(
+                        ::std::borrow::Cow::Borrowed(::std::concat!(
+                            "extracting the data of field `",
+                            ::std::stringify!(#field_name),
+                            "` in struct `",
+                            ::std::stringify!(#struct_name),
+                            "`, style property `",
+                            #property,
+                            "` is not found"
+                        ))
+                    )
+                )?;
+                let data = &*data_owned;
+            },
             ExtractTarget::TextNode { nth, .. } => quote! {
                 let data_whitespace = target_elem.text().nth(#nth).ok_or(
                     #_crate::error::Error::InvalidInput(
@@ -529,24 +2568,180 @@ impl Extractor {
                         ))
                     )
                 )?;
-                let data = data_whitespace.trim();
+                let data = #trim_ts;
+            },
+            ExtractTarget::OwnText { .. } => quote! {
+                let data_whitespace = target_elem
+                    .children()
+                    .filter_map(|node| node.value().as_text())
+                    .map(|text| &**text)
+                    .collect::<::std::string::String>();
+                let data = #trim_ts;
+            },
+            ExtractTarget::TextAll { .. } => quote! {
+                let data_owned = target_elem
+                    .text()
+                    .collect::<::std::string::String>()
+                    .split_whitespace()
+                    .collect::<::std::vec::Vec<_>>()
+                    .join(" ");
+                let data = &*data_owned;
+            },
+            ExtractTarget::TextNodes { .. } if !self.trim => quote! {
+                let data = target_elem.text();
+            },
+            ExtractTarget::TextNodes { .. } => quote! {
+                let data = target_elem
+                    .text()
+                    .map(str::trim)
+                    .filter(|text| !text.is_empty());
+            },
+            ExtractTarget::NextText { .. } => quote! {
+                let data_whitespace = target_elem
+                    .next_siblings()
+                    .take_while(|node| !node.value().is_element())
+                    .filter_map(|node| node.value().as_text())
+                    .map(|text| &**text)
+                    .collect::<::std::string::String>();
+                let data = #trim_ts;
             },
             ExtractTarget::InnerHTML { .. } => quote! {
                 let data_whitespace = target_elem.inner_html();
-                let data = data_whitespace.trim();
+                let data = #trim_ts;
+            },
+            ExtractTarget::InnerMarkdown { .. } => quote! {
+                let data_owned = #_crate::render::html_to_markdown(&target_elem);
+                let data = &*data_owned;
+            },
+            ExtractTarget::SanitizedHTML { .. } => quote! {
+                let data_whitespace = #_crate::render::sanitized_html(&target_elem);
+                let data = #trim_ts;
+            },
+            ExtractTarget::RenderedText { .. } => quote! {
+                let data_owned = #_crate::render::html_to_text(&target_elem);
+                let data = &*data_owned;
+            },
+            ExtractTarget::SiblingIndex { .. } => quote! {
+                let data = target_elem
+                    .prev_siblings()
+                    .filter(|node| node.value().is_element())
+                    .count();
             },
             ExtractTarget::PresenceOf { .. } => quote! {
                 let data = presence;
             },
         };
 
-        let parser = &self.parser;
+        let transform_data_ts = self.transforms.iter().map(|transform| match transform {
+            Transform::Replace { from, to } => quote! {
+                let data = data.replace(#from, #to);
+                let data = &*data;
+            },
+            Transform::NormalizeWhitespace => quote! {
+                let data = data.split_whitespace().collect::<::std::vec::Vec<_>>().join(" ");
+                let data = &*data;
+            },
+            Transform::Lowercase => quote! {
+                let data = data.to_lowercase();
+                let data = &*data;
+            },
+            Transform::Uppercase => quote! {
+                let data = data.to_uppercase();
+                let data = &*data;
+            },
+            Transform::DecodeEntities => quote! {
+                let data = #_crate::entities::decode(data);
+                let data = &*data;
+            },
+        });
+        let extract_data_from_elem_ts = quote! {
+            #extract_data_from_elem_ts
+            #(#transform_data_ts)*
+        };
+
+        let field_names = fields
+            .iter()
+            .map(|f| f.name.to_string().trim_start_matches("r#").to_owned())
+            .collect::<Vec<_>>();
         let parse_data_ts = match &self.capture {
+            Some(regex) if self.capture_by_name => {
+                let captures = fields.iter().zip(&field_names).map(|(field, name)| {
+                    if !regex_capture_names.contains(name) {
+                        abort!(
+                            field.name,
+                            "the regex {} has no capture group named `{}`, which is required by `capture by name with ..`",
+                            regex,
+                            name
+                        );
+                    }
+                    let parser = self.parser();
+                    quote! {
+                        (#parser)(caps.name(#name).ok_or(#_crate::error::Error::InvalidInput(
+                            ::std::borrow::Cow::Borrowed(::std::concat!(
+                                "extracting the data of field `",
+                                ::std::stringify!(#field_name),
+                                "` in struct `",
+                                ::std::stringify!(#struct_name),
+                                "`, capture group `",
+                                #name,
+                                "` did not match"
+                            ))
+                        ))?.as_str()).or_else(|e| ::std::result::Result::Err(
+                            #_crate::error::Error::InvalidInput(
+                                ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
+                                    "extracting the data of field `",
+                                    ::std::stringify!(#field_name),
+                                    "` in struct `",
+                                    ::std::stringify!(#struct_name),
+                                    "`, cannot parse capture group `",
+                                    #name,
+                                    "`: {:#?}"
+                                ), e))
+                            )
+                        ))?
+                    }
+                });
+                let value_ts = if tuple {
+                    quote!((#(#captures,)*))
+                } else {
+                    quote!(#(#captures)*)
+                };
+                quote! {
+                    let caps = REGEX.captures(data).ok_or(
+                        #_crate::error::Error::InvalidInput(
+                            ::std::borrow::Cow::Borrowed(::std::concat!(
+                                "extracting the data of field `",
+                                ::std::stringify!(#field_name),
+                                "` in struct `",
+                                ::std::stringify!(#struct_name),
+                                "`, nothing is captured with regex"
+                            ))
+                        )
+                    )?;
+                    #value_ts
+                }
+            }
             Some(_) => {
                 let mut captures = Vec::new();
                 for i in 1..regex_captures_len.unwrap() {
+                    let parser = self.parser();
+                    let nth = proc_macro2::Literal::usize_unsuffixed(i);
+                    // optional groups may not participate in the match
+                    let group_ts = quote! {
+                        caps.get(#i).ok_or(#_crate::error::Error::InvalidInput(
+                            ::std::borrow::Cow::Borrowed(::std::concat!(
+                                "extracting the data of field `",
+                                ::std::stringify!(#field_name),
+                                "` in struct `",
+                                ::std::stringify!(#struct_name),
+                                "`, capture group ",
+                                #nth,
+                                " did not match"
+                            ))
+                        ))?.as_str()
+                    };
                     captures.push(quote! {
-                        (#(#parser)*)(caps.get(#i).unwrap().as_str()).or_else(|e| ::std::result::Result::Err(
+                        (#parser)(#group_ts).or_else(|e| ::std::result::Result::Err(
                             #_crate::error::Error::InvalidInput(
                                 ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
                                     "extracting the data of field `",
@@ -578,12 +2773,75 @@ impl Extractor {
                     )
                 }
             }
-            None => match &self.target {
-                ExtractTarget::Element { .. } => quote! {
-                    #_crate::HtmlExtractor::extract(&data)?
+            None => match &target {
+                ExtractTarget::Element { .. } => match &self.context {
+                    Some(ty) => quote! {
+                        #_crate::convert::FromElement::<#(#ty)*, _>::from_element(&data, __context, __options)?
+                    },
+                    None => quote! {
+                        #_crate::convert::FromElement::<(), _>::from_element(&data, &(), __options)?
+                    },
+                },
+                ExtractTarget::AllAttributes { .. } => quote! {
+                    ::std::iter::Iterator::collect(data)
+                },
+                ExtractTarget::TextNodes { .. } => {
+                    let parser = self.parser();
+                    quote! {
+                        ::std::iter::Iterator::collect::<::std::result::Result<_, #_crate::error::Error>>(
+                            data.map(|data| (#parser)(data).or_else(|e| ::std::result::Result::Err(
+                                #_crate::error::Error::InvalidInput(
+                                    ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
+                                        "extracting the data of field `",
+                                        ::std::stringify!(#field_name),
+                                        "` in struct `",
+                                        ::std::stringify!(#struct_name),
+                                        "`, cannot parse `{}`: {:#?}",
+                                    ), data, e))
+                                )
+                            )))
+                        )?
+                    }
+                }
+                ExtractTarget::AttributeTuple { attributes, .. } => {
+                    if fields.len() > 1 && fields.len() != attributes.len() {
+                        abort!(
+                            attributes[0],
+                            "{} attributes are extracted, but {} fields are given",
+                            attributes.len(),
+                            fields.len()
+                        );
+                    }
+                    let parsed = attributes.iter().enumerate().map(|(i, attribute)| {
+                        let parser = self.parser();
+                        let i = proc_macro2::Literal::usize_unsuffixed(i);
+                        quote! {
+                            (#parser)(data.#i).or_else(|e| ::std::result::Result::Err(
+                                #_crate::error::Error::InvalidInput(
+                                    ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
+                                        "extracting the data of field `",
+                                        ::std::stringify!(#field_name),
+                                        "` in struct `",
+                                        ::std::stringify!(#struct_name),
+                                        "`, cannot parse attribute `",
+                                        #attribute,
+                                        "` `{}`: {:#?}"
+                                    ), data.#i, e))
+                                )
+                            ))?
+                        }
+                    });
+                    quote! {
+                        (#(#parsed,)*)
+                    }
+                }
+                ExtractTarget::SiblingIndex { .. } => quote! {
+                    data
                 },
-                _ => quote! {
-                    (#(#parser)*)(data).or_else(|e| ::std::result::Result::Err(#_crate::error::Error::InvalidInput(
+                _ => {
+                    let parser = self.parser();
+                    quote! {
+                        (#parser)(data).or_else(|e| ::std::result::Result::Err(#_crate::error::Error::InvalidInput(
                             ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
                                 "extracting the data of field `",
                                 ::std::stringify!(#field_name),
@@ -591,53 +2849,327 @@ impl Extractor {
                                 ::std::stringify!(#struct_name),
                                 "`, cannot parse `{}`: {:#?}",
                             ), data, e))
-                        )
-                    ))?
-                },
+                        )))?
+                    }
+                }
             },
         };
 
+        let parse_data_ts = match &self.mapper {
+            Some(mapper) => quote!((#(#mapper)*)({ #parse_data_ts })),
+            None => parse_data_ts,
+        };
+
+        let count = fields.len();
+        let select_ts = if self.fallbacks.is_empty() {
+            TokenStream::new()
+        } else {
+            self.record_tokens(quote!(__select(#count, #selector)))
+        };
+        let node_ts = self.record_tokens(quote!(__node(#count, &target_elem)));
+
         let collector_ts = match &self.collector {
             ExtractCollector::First => {
-                if let ExtractTarget::PresenceOf { .. } = &self.target {
-                    quote! {
-                        __elem.select(&*SELECTOR).next().is_some()
+                if let ExtractTarget::PresenceOf { .. } = &target {
+                    if self.record {
+                        quote! {
+                            let __found = #select.next();
+                            if let ::std::option::Option::Some(target_elem) = __found {
+                                #node_ts
+                            }
+                            __found.is_some()
+                        }
+                    } else {
+                        quote! {
+                            #select.next().is_some()
+                        }
                     }
                 } else {
                     quote! {
-                        let target_elem = __elem.select(&*SELECTOR).next().ok_or(
-                            #_crate::error::Error::InvalidInput(
-                                ::std::borrow::Cow::Borrowed(::std::concat!(
+                        let target_elem = #select.next().ok_or_else(||
+                            #_crate::error::element_not_found(
+                                ::std::concat!(
                                     "extracting the data of field `",
                                     ::std::stringify!(#field_name),
                                     "` in struct `",
                                     ::std::stringify!(#struct_name),
                                     "`, no element matched the selector"
-                                ))
+                                ),
+                                __elem,
+                                #selector,
+                                __options,
                             )
                         )?;
+                        #node_ts
                         #extract_data_from_elem_ts
                         #parse_data_ts
                     }
                 }
             }
-            ExtractCollector::IntoIterator => {
-                quote! {
-                    let mut items = ::std::vec::Vec::new();
-                    for target_elem in __elem.select(&*SELECTOR) {
-                        let item = {
-                            #extract_data_from_elem_ts
+            ExtractCollector::IntoIterator(modifiers) => {
+                let range_ts = self.collect_range_tokens();
+                // the key and value extractors see the matched element as `__elem`
+                let sub_field = SingleField {
+                    attr: Attributes { tokens: Vec::new() },
+                    vis: Visibility::Private,
+                    name: field_name.clone(),
+                    ty: quote!(_).into_iter().collect(),
+                    member: None,
+                };
+                let value_ts = match &self.value {
+                    Some(value) => {
+                        let value_ts = value.to_tokens(struct_name, &[&sub_field], false);
+                        quote!({
+                            let __elem = &target_elem;
+                            #value_ts
+                        })
+                    }
+                    // the strings are parsed after they are joined
+                    None if self.join.is_some() => quote!({
+                        #extract_data_from_elem_ts
+                        ::std::borrow::ToOwned::to_owned(data)
+                    }),
+                    None => quote!({
+                        #extract_data_from_elem_ts
+                        #parse_data_ts
+                    }),
+                };
+                // the lengths are compared before extracting the items, to report both of them
+                let (zip_ts, zip_item_ts) = match &self.zip {
+                    Some(zip) => {
+                        let zip_ts = zip.to_tokens(struct_name, &[&sub_field], false);
+                        (
+                            quote! {
+                                let __zipped: ::std::vec::Vec<_> = #zip_ts;
+                                let count = #select #range_ts.count();
+                                if count != __zipped.len() {
+                                    return ::std::result::Result::Err(#_crate::error::Error::InvalidInput(
+                                        ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
+                                            "extracting the data of field `",
+                                            ::std::stringify!(#field_name),
+                                            "` in struct `",
+                                            ::std::stringify!(#struct_name),
+                                            "`, {} elements matched the selector, but {} items are extracted to zip with"
+                                        ), count, __zipped.len()))
+                                    ));
+                                }
+                            },
+                            Some(quote!(let item = (item, zipped);)),
+                        )
+                    }
+                    None => (quote!(), None),
+                };
+                let (range_ts, target_elem_ts) = if self.zip.is_some() {
+                    (
+                        quote!(#range_ts.zip(__zipped)),
+                        quote!((target_elem, zipped)),
+                    )
+                } else {
+                    (range_ts, quote!(target_elem))
+                };
+                let key_ts = self.key.as_ref().map(|key| {
+                    let key_ts = key.to_tokens(struct_name, &[&sub_field], false);
+                    quote! {
+                        let item = (
+                            {
+                                let __elem = &target_elem;
+                                #key_ts
+                            },
+                            item,
+                        );
+                    }
+                });
+                // the index counts the elements after `skip`, and is kept for the items that pass `filter`
+                let (range_ts, target_elem_ts, item_ts) = if modifiers.enumerate {
+                    (
+                        quote!(#range_ts.enumerate()),
+                        quote!((index, #target_elem_ts)),
+                        quote!((index, item)),
+                    )
+                } else {
+                    (range_ts, target_elem_ts, quote!(item))
+                };
+                // the element is recorded with the item, so that the paths of the dropped items are not recorded
+                let push_ts = match &self.fold {
+                    Some(_) => quote!(#node_ts __accumulator = __fold(__accumulator, item);),
+                    None => quote!(#node_ts items.push(item);),
+                };
+                let (filter_ts, push_ts) = match &self.filter {
+                    Some(filter) => (
+                        quote!(let __filter = #(#filter)*;),
+                        quote! {
+                            if __filter(&item) {
+                                #push_ts
+                            }
+                        },
+                    ),
+                    None => (quote!(), push_ts),
+                };
+                // arrays cannot be collected from an iterator, but converted from a `Vec` of the same length
+                let collect_ts = match (fields, fields[0].ty.first()) {
+                    _ if self.join.is_some() => {
+                        let separator = &self.join;
+                        quote! {
+                            let data_owned = items.join(#separator);
+                            let data: &str = &data_owned;
                             #parse_data_ts
+                        }
+                    }
+                    // the map is built with `entry`, and the values are added to the collections with `Extend`,
+                    // whose item type is fixed to the one of `IntoIterator` for type inference
+                    _ if self.group => {
+                        let ty = &fields[0].ty;
+                        quote! {
+                            fn __push<C>(collection: &mut C, value: <C as ::std::iter::IntoIterator>::Item)
+                            where
+                                C: ::std::iter::IntoIterator + ::std::iter::Extend<<C as ::std::iter::IntoIterator>::Item>,
+                            {
+                                collection.extend(::std::iter::once(value));
+                            }
+                            let mut groups: #(#ty)* = ::std::default::Default::default();
+                            for (key, value) in items {
+                                __push(
+                                    groups.entry(key).or_insert_with(::std::default::Default::default),
+                                    value,
+                                );
+                            }
+                            groups
+                        }
+                    }
+                    ([_], Some(Group(g))) if g.delimiter() == Delimiter::Bracket => {
+                        let ty = &fields[0].ty;
+                        let len = g
+                            .stream()
+                            .into_iter()
+                            .skip_while(|tt| tt.to_string() != ";")
+                            .skip(1)
+                            .collect::<TokenStream>();
+                        quote! {
+                            let len = items.len();
+                            <#(#ty)* as ::std::convert::TryFrom<::std::vec::Vec<_>>>::try_from(items).map_err(|_|
+                                #_crate::error::Error::InvalidInput(
+                                    ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
+                                        "extracting the data of field `",
+                                        ::std::stringify!(#field_name),
+                                        "` in struct `",
+                                        ::std::stringify!(#struct_name),
+                                        "`, {} items are extracted, but {} are expected"
+                                    ), len, #len))
+                                )
+                            )?
+                        }
+                    }
+                    _ => quote!(items.into_iter().collect()),
+                };
+                // the items are accumulated without the intermediate `Vec`,
+                // and the type of the accumulator is given so that the function can be a closure without annotations
+                let (init_ts, collect_ts) = match &self.fold {
+                    Some((init, fold)) => {
+                        let ty = &fields[0].ty;
+                        let init = match init {
+                            Some(init) => quote!(#(#init)*),
+                            None => quote!(::std::default::Default::default()),
                         };
-                        items.push(item);
+                        (
+                            quote! {
+                                fn __fold_fn<A, T, F: ::std::ops::FnMut(A, T) -> A>(f: F) -> F {
+                                    f
+                                }
+                                #[allow(unused_mut)]
+                                let mut __fold = __fold_fn::<#(#ty)*, _, _>(#(#fold)*);
+                                let mut __accumulator: #(#ty)* = #init;
+                            },
+                            quote!(__accumulator),
+                        )
+                    }
+                    None => (quote!(let mut items = ::std::vec::Vec::new();), collect_ts),
+                };
+                // the matched elements are counted before they are skipped, limited or filtered
+                let bounds = [
+                    (&modifiers.at_least, quote!(<), "at least"),
+                    (&modifiers.at_most, quote!(>), "at most"),
+                ];
+                let bounds_ts = if bounds.iter().any(|(bound, ..)| bound.is_some()) {
+                    let checks = bounds.iter().filter_map(|(bound, op, text)| {
+                        let bound = bound.as_ref()?;
+                        Some(quote! {
+                            if count #op #bound {
+                                return ::std::result::Result::Err(#_crate::error::Error::InvalidInput(
+                                    ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
+                                        "extracting the data of field `",
+                                        ::std::stringify!(#field_name),
+                                        "` in struct `",
+                                        ::std::stringify!(#struct_name),
+                                        "`, {} elements matched the selector, but ",
+                                        #text,
+                                        " {} are expected"
+                                    ), count, #bound))
+                                ));
+                            }
+                        })
+                    });
+                    quote! {
+                        let count = #select.count();
+                        #(#checks)*
+                    }
+                } else {
+                    quote!()
+                };
+                quote! {
+                    #filter_ts
+                    #bounds_ts
+                    #zip_ts
+                    #init_ts
+                    for #target_elem_ts in #select #range_ts {
+                        __options.check()?;
+                        let item = #value_ts;
+                        #zip_item_ts
+                        #key_ts
+                        let item = #item_ts;
+                        #push_ts
+                    }
+                    #collect_ts
+                }
+            }
+            ExtractCollector::Unique => {
+                quote! {
+                    let mut target_elems = #select;
+                    let target_elem = target_elems.next().ok_or_else(||
+                        #_crate::error::element_not_found(
+                            ::std::concat!(
+                                "extracting the data of field `",
+                                ::std::stringify!(#field_name),
+                                "` in struct `",
+                                ::std::stringify!(#struct_name),
+                                "`, no element matched the selector"
+                            ),
+                            __elem,
+                            #selector,
+                            __options,
+                        )
+                    )?;
+                    let rest_count = target_elems.count();
+                    if rest_count > 0 {
+                        return ::std::result::Result::Err(#_crate::error::Error::InvalidInput(
+                            ::std::borrow::Cow::Owned(::std::format!(::std::concat!(
+                                "extracting the data of field `",
+                                ::std::stringify!(#field_name),
+                                "` in struct `",
+                                ::std::stringify!(#struct_name),
+                                "`, {} elements matched the selector, but exactly one is expected"
+                            ), rest_count + 1))
+                        ));
                     }
-                    items.into_iter().collect()
+                    #node_ts
+                    #extract_data_from_elem_ts
+                    #parse_data_ts
                 }
             }
             ExtractCollector::Option => {
                 quote! {
-                    match __elem.select(&*SELECTOR).next() {
+                    match #select.next() {
                         Some(target_elem) => Some({
+                            #node_ts
                             #extract_data_from_elem_ts
                             #parse_data_ts
                         }),
@@ -645,53 +3177,438 @@ impl Extractor {
                     }
                 }
             }
+            ExtractCollector::Default(default) => {
+                let default = match default {
+                    Some(expr) => quote!(#(#expr)*),
+                    None => quote!(::std::default::Default::default()),
+                };
+                quote! {
+                    match #select.next() {
+                        Some(target_elem) => {
+                            #node_ts
+                            #extract_data_from_elem_ts
+                            #parse_data_ts
+                        }
+                        None => #default,
+                    }
+                }
+            }
         };
 
         quote! {{
             #lazy_static_ts
+            #select_ts
             #collector_ts
         }}
     }
 }
+/// A move from the matched element to another element.
+enum Navigation {
+    /// `parent of ..` or `parent[n] of ..`, the ancestor element `levels` levels up.
+    Parent { levels: usize },
+    /// `closest ".." of ..`, the nearest element matching the selector, starting from the element itself, like `Element.closest()` of DOM.
+    Closest { selector: TokenTree },
+    /// `next of ..`, the next sibling element.
+    Next,
+}
+/// The targets, whose `position` is the index or `last` in `[..]` after the selector, which picks one of the matched elements,
+/// and `navigation` moves from the picked elements to other elements.
 enum ExtractTarget {
     Element {
         selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+        shadow: bool,
     },
     Attribute {
         attribute: TokenTree,
         selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// The attributes of the element, as a tuple.
+    AttributeTuple {
+        attributes: Vec<TokenTree>,
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// All the attributes of the element, as the pairs of the name and the value,
+    /// or only the `data-*` attributes without the prefix if `dataset` is true.
+    AllAttributes {
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+        dataset: bool,
+    },
+    Style {
+        property: TokenTree,
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
     },
     TextNode {
         nth: TokenStream,
         selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// The text nodes that are the direct children of the element, concatenated.
+    OwnText {
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// All the text nodes in the element, concatenated with the whitespace normalized.
+    TextAll {
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// The text nodes in the element, each trimmed, without empty ones.
+    TextNodes {
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// The text nodes following the element up to the next element, concatenated.
+    NextText {
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
     },
     InnerHTML {
         selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// The contents of the element converted into Markdown.
+    InnerMarkdown {
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// Sanitized inner HTML of the element.
+    SanitizedHTML {
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// The contents of the element rendered into plain text.
+    RenderedText {
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
+    },
+    /// The index of the element among the sibling elements.
+    SiblingIndex {
+        selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
     },
     PresenceOf {
         selector: TokenTree,
+        position: Option<TokenStream>,
+        navigation: Vec<Navigation>,
     },
 }
 impl ExtractTarget {
+    /// Whether the selector is built at extraction time, like `format!(..)`.
+    fn is_runtime(&self) -> bool {
+        matches!(self.selector(), Group(_))
+    }
+    /// The lookup specialized for the selector, if it is `:scope`, a single id or a single class.
+    fn fast_select(&self) -> Option<TokenStream> {
+        if self.is_runtime() {
+            return None;
+        }
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let selector = get_literal_str_value(self.selector());
+        if selector == ":scope" {
+            return Some(quote!(::std::iter::once(*__elem)));
+        }
+        let name = selector.get(1..).unwrap_or("");
+        let mut chars = name.chars();
+        let is_ident = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !is_ident {
+            return None;
+        }
+        match selector.chars().next() {
+            Some('#') => Some(quote!(#_crate::select::by_id(__elem, #name))),
+            Some('.') => Some(quote!(#_crate::select::by_class(__elem, #name))),
+            _ => None,
+        }
+    }
+    /// The `SELECTOR` static, unless the lookup is specialized.
+    /// Selectors built at extraction time are parsed into a local variable, which fails the extraction if invalid.
+    fn selector_static(&self) -> TokenStream {
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let selector = self.selector();
+        if self.is_runtime() {
+            return quote! {
+                let __runtime_selector = #_crate::select::parse(&#selector)?;
+            };
+        }
+        if self.fast_select().is_some() {
+            return quote!();
+        }
+        quote! {
+            #_crate::lazy_static::lazy_static! {
+                static ref SELECTOR: #_crate::scraper::Selector = #_crate::scraper::Selector::parse(#selector).unwrap();
+            }
+        }
+    }
+    /// An iterator over the elements in `__elem` that match the selector,
+    /// or over the element at the position if it is given, moved with the navigations.
+    fn select_tokens(&self) -> TokenStream {
+        let _crate = CRATE.parse::<TokenStream>().unwrap();
+        let select = if self.is_runtime() {
+            quote!(__elem.select(&__runtime_selector))
+        } else {
+            self.fast_select()
+                .unwrap_or_else(|| quote!(__elem.select(&*SELECTOR)))
+        };
+        let select = match self.position() {
+            Some(position) if position.to_string() == "last" => {
+                quote!(#select.last().into_iter())
+            }
+            Some(position) => quote!(#select.nth(#position).into_iter()),
+            None => select,
+        };
+        let navigation = self.navigation().iter().map(|step| match step {
+            Navigation::Parent { levels } => {
+                let nth = proc_macro2::Literal::usize_unsuffixed(levels - 1);
+                quote! {
+                    .filter_map(|elem| {
+                        elem.ancestors()
+                            .filter_map(#_crate::scraper::ElementRef::wrap)
+                            .nth(#nth)
+                    })
+                }
+            }
+            Navigation::Closest { selector } => quote! {
+                .filter_map(|elem| {
+                    #_crate::lazy_static::lazy_static! {
+                        static ref CLOSEST: #_crate::scraper::Selector = #_crate::scraper::Selector::parse(#selector).unwrap();
+                    }
+                    ::std::iter::once(elem)
+                        .chain(elem.ancestors().filter_map(#_crate::scraper::ElementRef::wrap))
+                        .find(|elem| CLOSEST.matches(elem))
+                })
+            },
+            Navigation::Next => quote! {
+                .filter_map(|elem| elem.next_siblings().find_map(#_crate::scraper::ElementRef::wrap))
+            },
+        });
+        quote!(#select #(#navigation)*)
+    }
     fn selector(&self) -> &TokenTree {
         match self {
-            ExtractTarget::Element { selector } => selector,
+            ExtractTarget::Element { selector, .. } => selector,
             ExtractTarget::Attribute { selector, .. } => selector,
+            ExtractTarget::AttributeTuple { selector, .. } => selector,
+            ExtractTarget::AllAttributes { selector, .. } => selector,
+            ExtractTarget::Style { selector, .. } => selector,
             ExtractTarget::TextNode { selector, .. } => selector,
-            ExtractTarget::InnerHTML { selector } => selector,
-            ExtractTarget::PresenceOf { selector } => selector,
+            ExtractTarget::OwnText { selector, .. } => selector,
+            ExtractTarget::TextAll { selector, .. } => selector,
+            ExtractTarget::TextNodes { selector, .. } => selector,
+            ExtractTarget::NextText { selector, .. } => selector,
+            ExtractTarget::InnerHTML { selector, .. } => selector,
+            ExtractTarget::InnerMarkdown { selector, .. } => selector,
+            ExtractTarget::SanitizedHTML { selector, .. } => selector,
+            ExtractTarget::RenderedText { selector, .. } => selector,
+            ExtractTarget::SiblingIndex { selector, .. } => selector,
+            ExtractTarget::PresenceOf { selector, .. } => selector,
+        }
+    }
+    fn position(&self) -> &Option<TokenStream> {
+        match self {
+            ExtractTarget::Element { position, .. } => position,
+            ExtractTarget::Attribute { position, .. } => position,
+            ExtractTarget::AttributeTuple { position, .. } => position,
+            ExtractTarget::AllAttributes { position, .. } => position,
+            ExtractTarget::Style { position, .. } => position,
+            ExtractTarget::TextNode { position, .. } => position,
+            ExtractTarget::OwnText { position, .. } => position,
+            ExtractTarget::TextAll { position, .. } => position,
+            ExtractTarget::TextNodes { position, .. } => position,
+            ExtractTarget::NextText { position, .. } => position,
+            ExtractTarget::InnerHTML { position, .. } => position,
+            ExtractTarget::InnerMarkdown { position, .. } => position,
+            ExtractTarget::SanitizedHTML { position, .. } => position,
+            ExtractTarget::RenderedText { position, .. } => position,
+            ExtractTarget::SiblingIndex { position, .. } => position,
+            ExtractTarget::PresenceOf { position, .. } => position,
+        }
+    }
+    fn navigation(&self) -> &[Navigation] {
+        match self {
+            ExtractTarget::Element { navigation, .. } => navigation,
+            ExtractTarget::Attribute { navigation, .. } => navigation,
+            ExtractTarget::AttributeTuple { navigation, .. } => navigation,
+            ExtractTarget::AllAttributes { navigation, .. } => navigation,
+            ExtractTarget::Style { navigation, .. } => navigation,
+            ExtractTarget::TextNode { navigation, .. } => navigation,
+            ExtractTarget::OwnText { navigation, .. } => navigation,
+            ExtractTarget::TextAll { navigation, .. } => navigation,
+            ExtractTarget::TextNodes { navigation, .. } => navigation,
+            ExtractTarget::NextText { navigation, .. } => navigation,
+            ExtractTarget::InnerHTML { navigation, .. } => navigation,
+            ExtractTarget::InnerMarkdown { navigation, .. } => navigation,
+            ExtractTarget::SanitizedHTML { navigation, .. } => navigation,
+            ExtractTarget::RenderedText { navigation, .. } => navigation,
+            ExtractTarget::SiblingIndex { navigation, .. } => navigation,
+            ExtractTarget::PresenceOf { navigation, .. } => navigation,
         }
     }
 }
 
-#[derive(PartialEq)]
+enum Transform {
+    Replace { from: TokenTree, to: TokenTree },
+    NormalizeWhitespace,
+    Lowercase,
+    Uppercase,
+    DecodeEntities,
+}
+
+/// The modifiers written after `collect`.
+#[derive(Default)]
+struct CollectModifiers {
+    /// The numbers of the elements to skip and to take, given by `skip` and `limit`.
+    skip: Option<TokenTree>,
+    limit: Option<TokenTree>,
+    /// Whether each data is paired with its index.
+    enumerate: bool,
+    /// The bounds of the number of the matched elements, given by `at least` and `at most`.
+    at_least: Option<TokenTree>,
+    at_most: Option<TokenTree>,
+}
+
 enum ExtractCollector {
     //extracts only the first data
     First,
-    //extracts all the data and collects into the type that implements IntoIterator,
-    IntoIterator,
+    //extracts all the data and collects into the type that implements IntoIterator
+    IntoIterator(CollectModifiers),
     //emits Some(..) if the data exist, None if not
     Option,
+    //extracts the data of the only element, fails if no or more than one element matches
+    Unique,
+    //extracts only the first data, falls back to the expression or `Default::default()` if no element matches
+    Default(Option<Vec<TokenTree>>),
+}
+
+/// Takes the tokens of an expression until a comma, except the commas between the parameters of closures.
+fn parse_expression(ts: &mut TokenStreamIter) -> Vec<TokenTree> {
+    let mut expr = Vec::new();
+    while !ts.is_finished() && ts.peek_ex_str(",") != "," {
+        let tt = ts.next_ex(",");
+        let is_closure_start = tt.to_string() == "|"
+            && !matches!(expr.last(), Some(Ident(i)) if i != "move")
+            && !matches!(expr.last(), Some(Literal(_)) | Some(Group(_)));
+        expr.push(tt);
+        if is_closure_start {
+            for tt in ts.by_ref() {
+                let is_end = tt.to_string() == "|";
+                expr.push(tt);
+                if is_end {
+                    break;
+                }
+            }
+        }
+    }
+    expr
+}
+
+/// Takes `(skip)` or `(skip, init with ..)` from the input, and returns the initializer.
+fn parse_skip(ts: &mut TokenStreamIter) -> Option<Option<Vec<TokenTree>>> {
+    let mut skip_ts: TokenStreamIter = match ts.peek() {
+        Some(Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+            g.stream().into_iter().peekable()
+        }
+        _ => return None,
+    };
+    if skip_ts.peek().map(|tt| tt.to_string()).as_deref() != Some("skip") {
+        return None;
+    }
+    let skip_tt = ts.next().unwrap();
+    skip_ts.next();
+    skip_ts.expect_or_none(",");
+    if skip_ts.is_finished() {
+        return Some(None);
+    }
+    skip_ts.expect("init");
+    skip_ts.expect("with");
+    let init = skip_ts.collect::<Vec<_>>();
+    if init.is_empty() {
+        abort!(skip_tt, "expected an expression after `init with`");
+    }
+    Some(Some(init))
+}
+
+/// Takes the tokens of a type, which may contain commas like `HashMap<K, V>`, from the input.
+fn parse_type(ts: &mut TokenStreamIter) -> Vec<TokenTree> {
+    use syn::parse::{ParseStream, Parser};
+    let rest = ts.clone().collect::<TokenStream>();
+    let rest_len = rest.clone().into_iter().count();
+    let parser = |input: ParseStream| {
+        input.parse::<syn::Type>()?;
+        input.parse::<TokenStream>()
+    };
+    let after = match parser.parse2(rest) {
+        Ok(after) => after,
+        Err(err) => match ts.peek() {
+            Some(tt) => abort!(tt, "expected type: {}", err),
+            None => abort_call_site!("expected type"),
+        },
+    };
+    // the type consists of the token trees before the rest
+    let len = rest_len - after.into_iter().count();
+    (0..len).map(|_| ts.next().unwrap()).collect()
+}
+
+/// Parses the `where` clause until the token for which `is_end` returns `true`.
+fn parse_where_clause(
+    ts: &mut TokenStreamIter,
+    is_end: impl Fn(&TokenTree) -> bool,
+) -> syn::WhereClause {
+    let mut where_ts = TokenStream::new();
+    while !is_end(ts.peek_ex("the end of `where` clause")) {
+        where_ts.extend(ts.next());
+    }
+    syn::parse2(where_ts).unwrap_or_else(|err| abort!(err.span(), "{}", err))
+}
+
+/// Parses the generic parameters `<..>` after the name of a structure, if any.
+fn parse_generics(ts: &mut TokenStreamIter) -> syn::Generics {
+    if ts.peek().map(|tt| tt.to_string()).as_deref() != Some("<") {
+        return syn::Generics::default();
+    }
+    let mut generics_ts = TokenStream::new();
+    let mut depth = 0usize;
+    let mut arrow = false;
+    loop {
+        let tt = ts.next_ex("`>`");
+        if let Punct(p) = &tt {
+            match p.as_char() {
+                '<' => depth += 1,
+                // `->` in the bounds of `Fn`
+                '>' if !arrow => depth -= 1,
+                _ => {}
+            }
+            arrow = p.as_char() == '-' && p.spacing() == proc_macro2::Spacing::Joint;
+        } else {
+            arrow = false;
+        }
+        generics_ts.extend(Some(tt));
+        if depth == 0 {
+            break;
+        }
+    }
+    syn::parse2(generics_ts).unwrap_or_else(|err| abort!(err.span(), "{}", err))
 }
 
 fn get_literal_str_value(tt: &TokenTree) -> String {
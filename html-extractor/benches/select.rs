@@ -0,0 +1,68 @@
+//! Compares the specialized lookups for `#id` and `.class` selectors with the general selector matching.
+//!
+//! Run with `cargo bench --bench select`.
+use html_extractor::{html_extractor, HtmlExtractor};
+use scraper::{Html, Selector};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+html_extractor! {
+    Page {
+        // specialized
+        title: String = (text of "#title"),
+        prices: Vec<usize> = (text of ".price", collect),
+        // general
+        title_general: String = (text of "body #title"),
+        prices_general: Vec<usize> = (text of "body .price", collect),
+    }
+}
+
+/// A document with `rows` rows, and the element with the id `title` at the end.
+fn document(rows: usize) -> String {
+    let mut html = String::from("<table>");
+    for i in 0..rows {
+        html += &format!(
+            r#"<tr class="row"><td class="name">item {}</td><td class="price">{}</td><td><a href="/{}">link</a></td></tr>"#,
+            i, i, i
+        );
+    }
+    html + r#"</table><h1 id="title">Title</h1>"#
+}
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    // warm up
+    f();
+    let start = Instant::now();
+    let mut iterations = 0u32;
+    while start.elapsed() < Duration::from_secs(1) {
+        f();
+        iterations += 1;
+    }
+    println!("{:<40} {:>12.3?}/iter", name, start.elapsed() / iterations);
+}
+
+fn main() {
+    for &rows in &[1_000, 10_000] {
+        let html = Html::parse_document(&document(rows));
+        let root = html.root_element();
+        println!("{} rows", rows);
+
+        let id = Selector::parse("#title").unwrap();
+        bench("  #title: ElementRef::select", || {
+            black_box(root.select(&id).next());
+        });
+        bench("  #title: select::by_id", || {
+            black_box(html_extractor::select::by_id(&root, "title").next());
+        });
+        let class = Selector::parse(".price").unwrap();
+        bench("  .price: ElementRef::select", || {
+            black_box(root.select(&class).count());
+        });
+        bench("  .price: select::by_class", || {
+            black_box(html_extractor::select::by_class(&root, "price").count());
+        });
+        bench("  Page::extract", || {
+            black_box(Page::extract(&root).unwrap());
+        });
+    }
+}
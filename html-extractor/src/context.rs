@@ -0,0 +1,83 @@
+//! User data given to the parsers during an extraction, like the URL of the page or an exchange-rate table.
+//!
+//! See [`ExtractWithContext`].
+
+use crate::error::FieldError;
+use crate::{options, Error, HtmlExtractor, UpdatePolicy};
+use scraper::ElementRef;
+
+/// Extraction with a context of type `C`.
+///
+/// The structures that declare a context with [`#![context(name: Type)]`](macro.html_extractor.html#struct-options)
+/// implement this trait for `Type` instead of [`HtmlExtractor`], so they cannot be extracted without the context,
+/// and a context of another type is a compile error.
+/// Every [`HtmlExtractor`] implements it for any `C` and ignores the context,
+/// so the structures without a context can be nested in the ones with it.
+///
+/// The context is passed as an argument through the nested structures, so it works on any thread.
+/// ```
+/// use html_extractor::{html_extractor, ExtractWithContext, UpdatePolicy};
+/// html_extractor! {
+///     Counter {
+///         #![context(step: u32)]
+///         count: u32 = (text of "#count", parse with |s: &str| s.parse::<u32>().map(|n| n * step)),
+///     }
+/// }
+///
+/// fn main() {
+///     let mut counter = Counter::extract_from_str_with_context(r#"<p id="count">1</p>"#, &10).unwrap();
+///     assert_eq!(counter.count, 10);
+///     let html = scraper::Html::parse_document(r#"<p id="count">2</p>"#);
+///     counter.extract_into_with_context(&html.root_element(), UpdatePolicy::AllOrNothing, &10).unwrap();
+///     assert_eq!(counter.count, 20);
+/// }
+/// ```
+pub trait ExtractWithContext<C: ?Sized>: Sized {
+    /// Extracts data from [`scraper::element_ref::ElementRef`] with `context`.
+    fn extract_with_context(elem: &ElementRef, context: &C) -> Result<Self, Error> {
+        Self::extract_with_context_and_options(elem, context, &options::ExtractOptions::new())
+    }
+    /// Parses HTML string and extracts data from it with `context`.
+    fn extract_from_str_with_context(html_str: &str, context: &C) -> Result<Self, Error> {
+        let html = scraper::Html::parse_document(html_str);
+        Self::extract_with_context(&html.root_element(), context)
+    }
+    /// Extracts data with `context` within the limits of `options`,
+    /// as [`HtmlExtractor::extract_with_options`] does.
+    fn extract_with_context_and_options(
+        elem: &ElementRef,
+        context: &C,
+        options: &options::ExtractOptions,
+    ) -> Result<Self, Error>;
+    /// Extracts data with `context` into `self`, as [`HtmlExtractor::extract_into`] does.
+    ///
+    /// The default implementation ignores the policy and behaves as [`UpdatePolicy::AllOrNothing`]:
+    /// it replaces the whole `self` only if the extraction succeeds.
+    fn extract_into_with_context(
+        &mut self,
+        elem: &ElementRef,
+        _policy: UpdatePolicy,
+        context: &C,
+    ) -> Result<Vec<FieldError>, Error> {
+        *self = Self::extract_with_context(elem, context)?;
+        Ok(Vec::new())
+    }
+}
+
+impl<T: HtmlExtractor, C: ?Sized> ExtractWithContext<C> for T {
+    fn extract_with_context_and_options(
+        elem: &ElementRef,
+        _context: &C,
+        options: &options::ExtractOptions,
+    ) -> Result<Self, Error> {
+        T::extract_with_options(elem, options)
+    }
+    fn extract_into_with_context(
+        &mut self,
+        elem: &ElementRef,
+        policy: UpdatePolicy,
+        _context: &C,
+    ) -> Result<Vec<FieldError>, Error> {
+        self.extract_into(elem, policy)
+    }
+}
@@ -0,0 +1,86 @@
+//! Conversions of the extracted data into the types of the fields.
+//!
+//! [`html_extractor!`](crate::html_extractor) uses them instead of [`FromStr`] and [`HtmlExtractor`](crate::HtmlExtractor)
+//! so that the fields can also be smart pointers, like `Arc<str>` and `Arc<T>`.
+//! The conversion is chosen by the trait system from the type of each value, so it works through type aliases,
+//! collections and tuples. The markers keep the impls apart, and only one of them applies to a type.
+
+use crate::options::ExtractOptions;
+use crate::{Error, ExtractWithContext};
+use scraper::ElementRef;
+use std::convert::Infallible;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The marker of the conversions with [`FromStr`] and [`ExtractWithContext`].
+pub struct Direct;
+/// The marker of the conversions into smart pointers.
+pub struct Shared;
+
+/// Parses the extracted string into a value.
+pub trait FromText<M>: Sized {
+    /// The error of the parse.
+    type Err;
+    /// Parses `s` into a value.
+    fn from_text(s: &str) -> Result<Self, Self::Err>;
+}
+impl<T: FromStr> FromText<Direct> for T {
+    type Err = T::Err;
+    fn from_text(s: &str) -> Result<T, T::Err> {
+        T::from_str(s)
+    }
+}
+macro_rules! impl_from_text_for_str {
+    ($($ty:ty),*) => {$(
+        impl FromText<Shared> for $ty {
+            type Err = Infallible;
+            fn from_text(s: &str) -> Result<$ty, Infallible> {
+                Ok(<$ty>::from(s))
+            }
+        }
+    )*};
+}
+impl_from_text_for_str!(Arc<str>, Rc<str>, Box<str>);
+
+/// Extracts a value from the element with the context of type `C`.
+pub trait FromElement<C: ?Sized, M>: Sized {
+    /// Extracts a value from `elem`.
+    fn from_element(
+        elem: &ElementRef,
+        context: &C,
+        options: &ExtractOptions,
+    ) -> Result<Self, Error>;
+}
+impl<C: ?Sized, T: ExtractWithContext<C>> FromElement<C, Direct> for T {
+    fn from_element(elem: &ElementRef, context: &C, options: &ExtractOptions) -> Result<T, Error> {
+        T::extract_with_context_and_options(elem, context, options)
+    }
+}
+impl<C: ?Sized, T: ExtractWithContext<C>> FromElement<C, Shared> for Arc<T> {
+    fn from_element(
+        elem: &ElementRef,
+        context: &C,
+        options: &ExtractOptions,
+    ) -> Result<Self, Error> {
+        T::extract_with_context_and_options(elem, context, options).map(Arc::new)
+    }
+}
+impl<C: ?Sized, T: ExtractWithContext<C>> FromElement<C, Shared> for Rc<T> {
+    fn from_element(
+        elem: &ElementRef,
+        context: &C,
+        options: &ExtractOptions,
+    ) -> Result<Self, Error> {
+        T::extract_with_context_and_options(elem, context, options).map(Rc::new)
+    }
+}
+impl<C: ?Sized, T: ExtractWithContext<C>> FromElement<C, Shared> for Box<T> {
+    fn from_element(
+        elem: &ElementRef,
+        context: &C,
+        options: &ExtractOptions,
+    ) -> Result<Self, Error> {
+        T::extract_with_context_and_options(elem, context, options).map(Box::new)
+    }
+}
@@ -0,0 +1,174 @@
+use crate::error::Error;
+use scraper::{ElementRef, Html, Selector};
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// The maximum number of candidate selectors shown.
+const MAX_CANDIDATES: usize = 5;
+/// The number of lines shown before and after the line of the nearest candidate.
+const CONTEXT_LINES: usize = 2;
+/// Longer lines are clipped around the nearest candidate.
+const MAX_LINE_WIDTH: usize = 100;
+
+impl Error {
+    /// Renders the error for the terminal with ANSI colors, for interactive debugging.
+    ///
+    /// If no element matched the selector of a field, the selector, the nearest matching candidates in `html`
+    /// and a snippet of `html` around the element matched by the nearest one are shown.
+    /// `html` should be the document the extraction failed on. The candidates are looked for in the whole document,
+    /// regardless of [`ExtractOptions::suggestions`](crate::options::ExtractOptions::suggestions).
+    ///
+    /// Colors are disabled if the `NO_COLOR` environment variable is set.
+    pub fn render_pretty(&self, html: &str) -> String {
+        self.render(html, std::env::var_os("NO_COLOR").is_none())
+    }
+    /// Renders the error as [`render_pretty`](Error::render_pretty) does, but without colors.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor};
+    /// html_extractor! {
+    ///     #[derive(Debug)]
+    ///     Foo {
+    ///         foo: usize = (text of "div #foo"),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = "<div>\n<span id=\"Foo\">1</span>\n</div>";
+    ///     let err = Foo::extract_from_str(input).unwrap_err();
+    ///     let rendered = err.render_plain(input);
+    ///     assert!(rendered.contains("selector: div #foo"));
+    ///     assert!(rendered.contains("div #Foo"));
+    ///     assert!(rendered.contains("> 2 | <span id=\"Foo\">1</span>"));
+    /// }
+    /// ```
+    pub fn render_plain(&self, html: &str) -> String {
+        self.render(html, false)
+    }
+
+    fn render(&self, html: &str, color: bool) -> String {
+        let paint = |style: &str, text: &str| {
+            if color {
+                format!("{}{}{}", style, text, RESET)
+            } else {
+                text.to_owned()
+            }
+        };
+
+        let mut out = format!(
+            "{}: {}\n",
+            paint(RED, "error"),
+            paint(BOLD, &self.to_string())
+        );
+        let not_found = match self {
+            Error::ElementNotFound(not_found) => not_found,
+            _ => return out,
+        };
+        out += &format!("  {} {}\n", paint(CYAN, "selector:"), not_found.selector);
+
+        let document = Html::parse_document(html);
+        let candidates = crate::suggest::candidates(&document.root_element(), &not_found.selector);
+        if candidates.is_empty() {
+            out += &format!(
+                "  {}\n",
+                paint(DIM, "no similar selector matches the document")
+            );
+            return out;
+        }
+        out += &format!("  {}\n", paint(CYAN, "candidates:"));
+        for (candidate, note) in candidates.iter().take(MAX_CANDIDATES) {
+            out += &format!(
+                "    {} {}\n",
+                paint(YELLOW, candidate),
+                paint(DIM, &format!("({})", note))
+            );
+        }
+
+        let nearest = Selector::parse(&candidates[0].0)
+            .ok()
+            .and_then(|selector| document.select(&selector).next());
+        let (line, start, end) = match nearest.and_then(|elem| locate(html, &elem)) {
+            Some(location) => location,
+            None => return out,
+        };
+        out += &format!("  {}\n", paint(CYAN, "html:"));
+        let lines = html.lines().collect::<Vec<_>>();
+        let first = line.saturating_sub(CONTEXT_LINES);
+        let last = (line + CONTEXT_LINES).min(lines.len() - 1);
+        let width = (last + 1).to_string().len();
+        for (i, text) in lines.iter().enumerate().take(last + 1).skip(first) {
+            let number = format!("{:>width$} |", i + 1, width = width);
+            if i == line {
+                let (before, found, after) = clip(text, start, end);
+                out += &format!(
+                    "  {} {} {}{}{}\n",
+                    paint(YELLOW, ">"),
+                    paint(DIM, &number),
+                    before,
+                    paint(YELLOW, found),
+                    after
+                );
+            } else {
+                let (before, found, after) = clip(text, 0, 0);
+                out += &format!("    {} {}{}{}\n", paint(DIM, &number), before, found, after);
+            }
+        }
+        out
+    }
+}
+
+/// Finds the element in the source, returning the line index and the byte range of the found text in the line.
+///
+/// The parsed document doesn't keep the positions in the source, so its `id`, its classes or its tag are looked for.
+fn locate(html: &str, elem: &ElementRef) -> Option<(usize, usize, usize)> {
+    let mut needles = Vec::new();
+    if let Some(id) = elem.value().id() {
+        needles.push(format!("id=\"{}\"", id));
+        needles.push(format!("id='{}'", id));
+        needles.push(format!("id={}", id));
+    }
+    needles.extend(elem.value().classes().map(str::to_owned));
+    needles.push(format!("<{}", elem.value().name()));
+
+    needles.iter().find_map(|needle| {
+        html.lines().enumerate().find_map(|(i, line)| {
+            line.find(&**needle)
+                .map(|start| (i, start, start + needle.len()))
+        })
+    })
+}
+
+/// Splits the line into the parts before, in and after `start..end`, clipping long lines around it.
+fn clip(line: &str, start: usize, end: usize) -> (String, &str, String) {
+    let line = line.trim_end();
+    if line.chars().count() <= MAX_LINE_WIDTH {
+        return (
+            line[..start].to_owned(),
+            &line[start..end],
+            line[end..].to_owned(),
+        );
+    }
+    let floor = |mut i: usize| {
+        while !line.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    };
+    let clip_start = floor(start.saturating_sub(MAX_LINE_WIDTH / 3));
+    let clip_end = floor((clip_start + MAX_LINE_WIDTH).max(end).min(line.len()));
+    let before = if clip_start > 0 {
+        format!("…{}", &line[clip_start..start])
+    } else {
+        line[..start].to_owned()
+    };
+    let after = if clip_end < line.len() {
+        format!("{}…", &line[end..clip_end])
+    } else {
+        line[end..].to_owned()
+    };
+    (before, &line[start..end], after)
+}
@@ -0,0 +1,104 @@
+//! Decoding of the character references in HTML, like `&amp;` and `&#x2019;`.
+//!
+//! The text extracted with `text of ..` is already decoded by the parser, but `inner_html of ..` keeps the references
+//! as they are serialized. [`decode`] is what the [`decode_entities` transform](../macro.html_extractor.html#transform-specifier)
+//! applies to them.
+
+use markup5ever::data::{C1_REPLACEMENTS, NAMED_ENTITIES};
+use std::borrow::Cow;
+
+/// The length of the longest name of the named character references, `&CounterClockwiseContourIntegral;`.
+const MAX_NAME_LEN: usize = 32;
+
+/// Decodes the named and numeric character references in `s`, as the HTML parser does in text.
+///
+/// The legacy references without `;` like `&amp` are decoded, and the unknown ones are left as they are.
+/// Invalid code points are replaced with U+FFFD.
+/// ```
+/// use html_extractor::entities::decode;
+/// assert_eq!(decode("Tom &amp; Jerry&#x2019;s &lt;b&gt;"), "Tom & Jerry\u{2019}s <b>");
+/// assert_eq!(decode("&copy 2024 &unknown; &#0;"), "\u{a9} 2024 &unknown; \u{fffd}");
+/// ```
+pub fn decode(s: &str) -> Cow<'_, str> {
+    if !s.contains('&') {
+        return Cow::Borrowed(s);
+    }
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        decoded.push_str(&rest[..i]);
+        rest = &rest[i..];
+        match decode_reference(&rest[1..], &mut decoded) {
+            Some(len) => rest = &rest[1 + len..],
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+/// Decodes the reference at the beginning of `s`, which follows `&`, and returns the length of it.
+fn decode_reference(s: &str, decoded: &mut String) -> Option<usize> {
+    if let Some(numeric) = s.strip_prefix('#') {
+        let (digits, radix, prefix_len) = match numeric.strip_prefix(|c| c == 'x' || c == 'X') {
+            Some(hex) => (hex, 16, 2),
+            None => (numeric, 10, 1),
+        };
+        let len = digits
+            .find(|c: char| !c.is_digit(radix))
+            .unwrap_or(digits.len());
+        if len == 0 {
+            return None;
+        }
+        // too large numbers are invalid as well as the code points out of range
+        let code = u32::from_str_radix(&digits[..len], radix).unwrap_or(u32::MAX);
+        decoded.push(numeric_char(code));
+        let semicolon = digits[len..].starts_with(';') as usize;
+        return Some(prefix_len + len + semicolon);
+    }
+    let name_len = s
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(s.len())
+        .min(MAX_NAME_LEN);
+    if s[name_len..].starts_with(';') {
+        if let Some((first, second)) = named_entity(&s[..=name_len]) {
+            push_entity(decoded, first, second);
+            return Some(name_len + 1);
+        }
+    }
+    // the legacy references, whose names are the prefixes of the name
+    (2..=name_len).rev().find_map(|len| {
+        let (first, second) = named_entity(&s[..len])?;
+        push_entity(decoded, first, second);
+        Some(len)
+    })
+}
+
+/// The code points of the named reference, whose name includes `;` if it has.
+fn named_entity(name: &str) -> Option<(u32, u32)> {
+    // the map also has the prefixes of the names, which are mapped to zeros
+    NAMED_ENTITIES
+        .get(name)
+        .copied()
+        .filter(|&(first, _)| first != 0)
+}
+
+fn push_entity(decoded: &mut String, first: u32, second: u32) {
+    decoded.extend(std::char::from_u32(first));
+    if second != 0 {
+        decoded.extend(std::char::from_u32(second));
+    }
+}
+
+/// The character of a numeric reference, with the replacements of the parser.
+fn numeric_char(code: u32) -> char {
+    match code {
+        0x80..=0x9f => C1_REPLACEMENTS[(code - 0x80) as usize]
+            .unwrap_or_else(|| std::char::from_u32(code).unwrap()),
+        0 => '\u{fffd}',
+        _ => std::char::from_u32(code).unwrap_or('\u{fffd}'),
+    }
+}
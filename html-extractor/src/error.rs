@@ -1,8 +1,216 @@
 use std::borrow::Cow;
 use thiserror::Error;
 
+/// The errors of extraction.
+///
+/// New variants may be added in minor releases, so a `match` on it needs a wildcard arm.
+/// The variants don't depend on the enabled features.
+/// Use [`code`](Error::code) to tell the kinds of the errors apart without depending on the variants.
+///
+/// Since v2.0.0, the failures where no element matches the selector of a field
+/// are reported with [`Error::ElementNotFound`] instead of [`Error::InvalidInput`]. The message is the same as before.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
+    /// The input could not be extracted, for example a string that could not be parsed.
     #[error("invalid input: {0}")]
     InvalidInput(Cow<'static, str>),
+    /// No element matched the selector of a field.
+    #[error("invalid input: {0}")]
+    ElementNotFound(Box<ElementNotFound>),
+    /// The validator of a struct, given with `#![validate(..)]` or `#[extract(validate = ..)]`, rejected the extracted value.
+    ///
+    /// The error returned from the validator is kept as the [`source`](std::error::Error::source) and can be downcast back.
+    #[error("validating struct `{struct_name}`: {source}")]
+    Validation {
+        /// The name of the struct.
+        struct_name: &'static str,
+        /// The error returned from the validator.
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// The extraction was cancelled with [`ExtractOptions::cancellation_token`](crate::options::ExtractOptions::cancellation_token).
+    #[error("the extraction was cancelled")]
+    Cancelled,
+    /// The deadline of [`ExtractOptions`](crate::options::ExtractOptions) has passed.
+    #[error("the extraction timed out")]
+    TimedOut,
+    /// Fetching the document failed. It is reported only by the `fetch` feature.
+    #[error(transparent)]
+    Fetch(FetchFailure),
+    /// Reading the input failed.
+    #[error("reading the input failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    /// Returns a stable code that identifies the kind of the error, for aggregating failures by type.
+    ///
+    /// | Code                  | Error                                                            |
+    /// |-----------------------|------------------------------------------------------------------|
+    /// | `E_INVALID_INPUT`     | [`Error::InvalidInput`]                                          |
+    /// | `E_ELEMENT_NOT_FOUND` | [`Error::ElementNotFound`]                                       |
+    /// | `E_VALIDATION`        | [`Error::Validation`]                                            |
+    /// | `E_CANCELLED`         | [`Error::Cancelled`]                                             |
+    /// | `E_TIMED_OUT`         | [`Error::TimedOut`]                                              |
+    /// | `E_FETCH_CLIENT`      | [`FetchError::Client`](crate::fetch::FetchError::Client)         |
+    /// | `E_FETCH_REQUEST`     | [`FetchError::Request`](crate::fetch::FetchError::Request)       |
+    /// | `E_FETCH_STATUS`      | [`FetchError::Status`](crate::fetch::FetchError::Status)         |
+    /// | `E_FETCH_DISALLOWED`  | [`FetchError::Disallowed`](crate::fetch::FetchError::Disallowed) |
+    /// | `E_IO`                | [`Error::Io`]                                                    |
+    ///
+    /// With the `serde` feature, `Error` implements `Serialize` as a map of the code, the message and the details
+    /// (`selector` and `suggestions` for `E_ELEMENT_NOT_FOUND`, `struct` for `E_VALIDATION`, `url` and `status` for fetch errors).
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor};
+    /// html_extractor! {
+    ///     #[derive(Debug)]
+    ///     Foo {
+    ///         foo: usize = (text of "#foo"),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     assert_eq!(Foo::extract_from_str("").unwrap_err().code(), "E_ELEMENT_NOT_FOUND");
+    ///     assert_eq!(Foo::extract_from_str(r#"<p id="foo">x</p>"#).unwrap_err().code(), "E_INVALID_INPUT");
+    /// }
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::InvalidInput(_) => "E_INVALID_INPUT",
+            Error::ElementNotFound(_) => "E_ELEMENT_NOT_FOUND",
+            Error::Validation { .. } => "E_VALIDATION",
+            Error::Cancelled => "E_CANCELLED",
+            Error::TimedOut => "E_TIMED_OUT",
+            Error::Fetch(failure) => failure.code(),
+            Error::Io(_) => "E_IO",
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Error {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            Error::ElementNotFound(not_found) => {
+                map.serialize_entry("selector", &not_found.selector)?;
+                map.serialize_entry("suggestions", &not_found.suggestions)?;
+            }
+            Error::Validation { struct_name, .. } => {
+                map.serialize_entry("struct", struct_name)?;
+            }
+            Error::Fetch(failure) => {
+                if let Some(url) = failure.url() {
+                    map.serialize_entry("url", url)?;
+                }
+                if let Some(status) = failure.status() {
+                    map.serialize_entry("status", &status)?;
+                }
+            }
+            _ => {}
+        }
+        map.end()
+    }
+}
+
+/// A failure to extract a field in [`HtmlExtractor::extract_into`](crate::HtmlExtractor::extract_into).
+#[derive(Debug)]
+pub struct FieldError {
+    /// The name of the field. For tuple fields, the name of the first field is used.
+    pub field: &'static str,
+    /// The error.
+    pub error: Error,
+}
+
+/// The details of [`Error::ElementNotFound`], reported when no element matches the selector of a field.
+#[derive(Debug, Clone)]
+pub struct ElementNotFound {
+    /// The description of the failure, including the names of the field and the struct.
+    pub message: &'static str,
+    /// The selector that matched no element.
+    pub selector: Cow<'static, str>,
+    /// The selectors close to `selector` that match elements, with notes. Empty unless [`ExtractOptions::suggestions`](crate::options::ExtractOptions::suggestions) is enabled.
+    pub suggestions: Vec<String>,
+}
+impl std::fmt::Display for ElementNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if !self.suggestions.is_empty() {
+            write!(f, "; did you mean {}?", self.suggestions.join(" or "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "fetch")]
+type FetchFailureInner = crate::fetch::FetchError;
+#[cfg(not(feature = "fetch"))]
+type FetchFailureInner = std::convert::Infallible;
+
+/// The details of [`Error::Fetch`].
+///
+/// With the `fetch` feature, [`fetch_error`](FetchFailure::fetch_error) returns the [`FetchError`](crate::fetch::FetchError).
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct FetchFailure(FetchFailureInner);
+impl FetchFailure {
+    /// Returns a stable code that identifies the kind of the error. See [`Error::code`].
+    pub fn code(&self) -> &'static str {
+        #[cfg(feature = "fetch")]
+        return self.0.code();
+        #[cfg(not(feature = "fetch"))]
+        match self.0 {}
+    }
+    /// Returns the URL of the failed request, if any.
+    pub fn url(&self) -> Option<&str> {
+        #[cfg(feature = "fetch")]
+        return self.0.url();
+        #[cfg(not(feature = "fetch"))]
+        match self.0 {}
+    }
+    /// Returns the HTTP status code if the server responded with an unsuccessful status.
+    pub fn status(&self) -> Option<u16> {
+        #[cfg(feature = "fetch")]
+        return self.0.status();
+        #[cfg(not(feature = "fetch"))]
+        match self.0 {}
+    }
+    /// Returns the error of the fetcher.
+    ///
+    /// This method is available only when the `fetch` feature is enabled.
+    #[cfg(feature = "fetch")]
+    pub fn fetch_error(&self) -> &crate::fetch::FetchError {
+        &self.0
+    }
+}
+#[cfg(feature = "fetch")]
+impl From<crate::fetch::FetchError> for Error {
+    fn from(err: crate::fetch::FetchError) -> Error {
+        Error::Fetch(FetchFailure(err))
+    }
+}
+
+/// Creates the error reported when no element matches `selector`, adding suggestions if enabled.
+#[doc(hidden)]
+pub fn element_not_found(
+    message: &'static str,
+    elem: &scraper::ElementRef,
+    selector: impl Into<Cow<'static, str>>,
+    options: &crate::options::ExtractOptions,
+) -> Error {
+    let selector = selector.into();
+    let suggestions = if options.suggestions_enabled() {
+        crate::suggest::suggest(elem, &selector)
+    } else {
+        Vec::new()
+    };
+    Error::ElementNotFound(Box::new(ElementNotFound {
+        message,
+        selector,
+        suggestions,
+    }))
 }
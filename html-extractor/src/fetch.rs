@@ -0,0 +1,551 @@
+//! Fetching HTML documents over HTTP and extracting data from them.
+//!
+//! This module is available only when the `fetch` feature is enabled.
+//! ```no_run
+//! use html_extractor::{html_extractor, fetch};
+//! html_extractor! {
+//!     #[derive(Debug, PartialEq)]
+//!     Foo {
+//!         title: String = (text of "title"),
+//!     }
+//! }
+//!
+//! async fn print_title() -> Result<(), html_extractor::Error> {
+//!     let foo: Foo = fetch::extract_from_url("https://example.com/").await?;
+//!     println!("{}", foo.title);
+//!     Ok(())
+//! }
+//! # fn main() {}
+//! ```
+use crate::{Error, HtmlExtractor};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use thiserror::Error;
+
+mod crawler;
+mod rate_limit;
+mod retry;
+mod robots;
+mod sitemap;
+pub use crawler::{Crawler, MemoryUrlStore, UrlStore};
+pub use retry::RetryPolicy;
+pub use robots::RobotsTxt;
+pub use sitemap::Sitemap;
+
+/// An error occurred while fetching a document.
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("building the HTTP client failed: {0}")]
+    Client(#[source] reqwest::Error),
+    #[error("requesting `{url}` failed: {source}")]
+    Request {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("`{url}` responded with status {status}")]
+    Status {
+        url: String,
+        status: u16,
+        retry_after: Option<Duration>,
+    },
+    #[error("fetching `{url}` is disallowed by robots.txt")]
+    Disallowed { url: String },
+}
+impl FetchError {
+    /// Returns the HTTP status code if the server responded with an unsuccessful status.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            FetchError::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+    /// Returns the URL of the failed request, if any.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            FetchError::Client(_) => None,
+            FetchError::Request { url, .. }
+            | FetchError::Status { url, .. }
+            | FetchError::Disallowed { url } => Some(url),
+        }
+    }
+    /// Returns a stable code that identifies the kind of the error. See [`Error::code`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            FetchError::Client(_) => "E_FETCH_CLIENT",
+            FetchError::Request { .. } => "E_FETCH_REQUEST",
+            FetchError::Status { .. } => "E_FETCH_STATUS",
+            FetchError::Disallowed { .. } => "E_FETCH_DISALLOWED",
+        }
+    }
+}
+
+/// Fetches documents over HTTP and extracts data from them.
+///
+/// A `Fetcher` holds an HTTP client, so it should be created once and reused.
+#[derive(Debug, Clone)]
+pub struct Fetcher {
+    client: reqwest::Client,
+    retry: RetryPolicy,
+    robots: Option<Arc<RobotsCache>>,
+    rate_limiter: Arc<rate_limit::RateLimiter>,
+    bootstrap: Option<Arc<Bootstrap>>,
+}
+
+#[derive(Debug)]
+struct RobotsCache {
+    user_agent: String,
+    robots_txts: Mutex<HashMap<String, Arc<RobotsTxt>>>,
+}
+
+type BootstrapFuture = Pin<Box<dyn Future<Output = Result<(), FetchError>> + Send>>;
+struct Bootstrap {
+    hook: Box<dyn Fn(Fetcher) -> BootstrapFuture + Send + Sync>,
+    done: tokio::sync::OnceCell<()>,
+}
+impl std::fmt::Debug for Bootstrap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bootstrap")
+            .field("done", &self.done.initialized())
+            .finish_non_exhaustive()
+    }
+}
+impl Fetcher {
+    /// Creates a `Fetcher` with the default configuration.
+    ///
+    /// # Panics
+    /// Panics if the TLS backend cannot be initialized. Use [`Fetcher::builder`] to handle the error.
+    pub fn new() -> Fetcher {
+        Fetcher::builder()
+            .build()
+            .expect("building the default HTTP client failed")
+    }
+    /// Creates a builder to configure a `Fetcher`.
+    pub fn builder() -> FetcherBuilder {
+        FetcherBuilder {
+            client: reqwest::Client::builder(),
+            retry: RetryPolicy::default(),
+            robots_user_agent: None,
+            requests_per_second_per_host: None,
+            max_concurrency: None,
+            max_concurrency_per_host: None,
+            bootstrap: None,
+        }
+    }
+
+    /// Fetches the document at `url` and returns its body.
+    ///
+    /// Failed requests are retried according to the [`RetryPolicy`].
+    /// If robots.txt is respected and it disallows `url`, [`FetchError::Disallowed`] is returned without fetching.
+    pub async fn fetch(&self, url: &str) -> Result<String, FetchError> {
+        self.run_bootstrap().await?;
+        if !self.is_allowed(url).await? {
+            return Err(FetchError::Disallowed {
+                url: url.to_owned(),
+            });
+        }
+        self.fetch_with_retry(url).await
+    }
+    /// Submits `form` to `url` as a `POST` request and returns the response body.
+    ///
+    /// This is intended for logging in; cookies set by the response are sent with subsequent requests if the cookie store is enabled.
+    /// Unlike [`Fetcher::fetch`], the request is not retried and robots.txt is not checked.
+    pub async fn post_form(&self, url: &str, form: &[(&str, &str)]) -> Result<String, FetchError> {
+        self.run_bootstrap().await?;
+        self.send(url, self.client.post(url).form(form)).await
+    }
+    /// Fetches the document at `url` and extracts data from it.
+    pub async fn extract<T: HtmlExtractor>(&self, url: &str) -> Result<T, Error> {
+        let body = self.fetch(url).await?;
+        T::extract_from_str(&body)
+    }
+
+    /// Fetches the documents at `urls` and extracts data from them, running up to `concurrency` requests at once.
+    ///
+    /// `on_result` is called with each URL and its result as soon as it is ready, so the order is not preserved.
+    /// A failure of one URL doesn't affect the others.
+    /// ```no_run
+    /// use html_extractor::{html_extractor, fetch::Fetcher};
+    /// html_extractor! {
+    ///     Foo {
+    ///         title: String = (text of "title"),
+    ///     }
+    /// }
+    ///
+    /// async fn print_titles(urls: Vec<String>) {
+    ///     Fetcher::new()
+    ///         .extract_batch(urls, 4, |url, foo: Result<Foo, _>| match foo {
+    ///             Ok(foo) => println!("{}: {}", url, foo.title),
+    ///             Err(err) => eprintln!("{}: {}", url, err),
+    ///         })
+    ///         .await;
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub async fn extract_batch<T, I, F>(&self, urls: I, concurrency: usize, mut on_result: F)
+    where
+        T: HtmlExtractor,
+        I: IntoIterator,
+        I::Item: Into<String>,
+        F: FnMut(String, Result<T, Error>),
+    {
+        stream::iter(urls)
+            .map(|url| {
+                let url = url.into();
+                async move {
+                    let data = self.extract(&url).await;
+                    (url, data)
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .for_each(|(url, data)| {
+                on_result(url, data);
+                async {}
+            })
+            .await;
+    }
+    /// Fetches the sitemap at `url` and returns the URLs of the pages in it.
+    ///
+    /// If it is a sitemap index, the sitemaps listed in it are fetched recursively.
+    pub async fn sitemap_urls(&self, url: &str) -> Result<Vec<String>, FetchError> {
+        let mut urls = Vec::new();
+        let mut visited = HashSet::new();
+        let mut sitemap_urls = vec![url.to_owned()];
+        while let Some(sitemap_url) = sitemap_urls.pop() {
+            if !visited.insert(sitemap_url.clone()) {
+                continue;
+            }
+            let sitemap = Sitemap::parse(&self.fetch(&sitemap_url).await?);
+            urls.extend(sitemap.urls);
+            // pop in the listed order
+            sitemap_urls.extend(sitemap.sitemaps.into_iter().rev());
+        }
+        Ok(urls)
+    }
+    /// Fetches the sitemap at `sitemap_url` and extracts data from the pages whose URL satisfies `filter`.
+    ///
+    /// The pages are fetched one by one as the returned stream is polled, and each item is paired with the URL of the page.
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use html_extractor::{html_extractor, fetch::Fetcher};
+    /// html_extractor! {
+    ///     Article {
+    ///         title: String = (text of "h1"),
+    ///     }
+    /// }
+    ///
+    /// async fn print_titles() -> Result<(), html_extractor::Error> {
+    ///     let fetcher = Fetcher::new();
+    ///     let articles = fetcher
+    ///         .extract_sitemap::<Article>("https://example.com/sitemap.xml", |url| url.contains("/articles/"))
+    ///         .await?;
+    ///     futures_util::pin_mut!(articles);
+    ///     while let Some((url, article)) = articles.next().await {
+    ///         println!("{}: {}", url, article?.title);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub async fn extract_sitemap<'a, T: HtmlExtractor + 'a>(
+        &'a self,
+        sitemap_url: &str,
+        mut filter: impl FnMut(&str) -> bool,
+    ) -> Result<impl Stream<Item = (String, Result<T, Error>)> + 'a, FetchError> {
+        let mut urls = self.sitemap_urls(sitemap_url).await?;
+        urls.retain(|url| filter(url));
+        Ok(stream::iter(urls).then(move |url| async move {
+            let data = self.extract(&url).await;
+            (url, data)
+        }))
+    }
+
+    /// Returns whether robots.txt allows fetching `url`.
+    ///
+    /// It always returns `true` if robots.txt is not respected.
+    pub async fn is_allowed(&self, url: &str) -> Result<bool, FetchError> {
+        let robots = match &self.robots {
+            Some(robots) => robots,
+            None => return Ok(true),
+        };
+        let parsed_url = match reqwest::Url::parse(url) {
+            Ok(parsed_url) => parsed_url,
+            // let the request report the invalid URL
+            Err(_) => return Ok(true),
+        };
+        let path = match parsed_url.query() {
+            Some(query) => format!("{}?{}", parsed_url.path(), query),
+            None => parsed_url.path().to_owned(),
+        };
+        let robots_txt = self.robots_txt(url).await?;
+        Ok(robots_txt.is_allowed(&robots.user_agent, &path))
+    }
+    /// Returns the robots.txt of the host of `url`.
+    ///
+    /// The robots.txt is fetched once per host and cached.
+    /// If it is not found (4xx), everything is allowed. If the server fails to respond it (5xx), everything is disallowed.
+    pub async fn robots_txt(&self, url: &str) -> Result<Arc<RobotsTxt>, FetchError> {
+        let parsed_url = match reqwest::Url::parse(url) {
+            Ok(parsed_url) => parsed_url,
+            Err(_) => return Ok(Arc::new(RobotsTxt::allow_all())),
+        };
+        let origin = parsed_url.origin().ascii_serialization();
+        let robots_txts = match &self.robots {
+            Some(robots) => &robots.robots_txts,
+            None => return Ok(Arc::new(RobotsTxt::allow_all())),
+        };
+        if let Some(robots_txt) = robots_txts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&origin)
+        {
+            return Ok(Arc::clone(robots_txt));
+        }
+
+        let robots_txt = match self
+            .fetch_with_retry(&format!("{}/robots.txt", origin))
+            .await
+        {
+            Ok(body) => RobotsTxt::parse(&body),
+            Err(FetchError::Status { status, .. }) if (400..500).contains(&status) => {
+                RobotsTxt::allow_all()
+            }
+            Err(FetchError::Status { .. }) => RobotsTxt::disallow_all(),
+            Err(err) => return Err(err),
+        };
+        let robots_txt = Arc::new(robots_txt);
+        robots_txts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(origin, Arc::clone(&robots_txt));
+        Ok(robots_txt)
+    }
+
+    /// Returns the `Crawl-delay` in robots.txt for `url` if robots.txt is respected.
+    async fn robots_crawl_delay(&self, url: &str) -> Option<Duration> {
+        let robots = self.robots.as_ref()?;
+        let robots_txt = self.robots_txt(url).await.ok()?;
+        robots_txt.crawl_delay(&robots.user_agent)
+    }
+    async fn run_bootstrap(&self) -> Result<(), FetchError> {
+        if let Some(bootstrap) = &self.bootstrap {
+            bootstrap
+                .done
+                .get_or_try_init(|| {
+                    // the hook gets a fetcher without itself so that its requests don't wait for it
+                    (bootstrap.hook)(Fetcher {
+                        bootstrap: None,
+                        ..self.clone()
+                    })
+                })
+                .await?;
+        }
+        Ok(())
+    }
+    async fn fetch_with_retry(&self, url: &str) -> Result<String, FetchError> {
+        let mut attempt = 0;
+        loop {
+            match self.fetch_once(url).await {
+                Ok(body) => return Ok(body),
+                Err(err) if attempt < self.retry.max_retries && self.retry.is_transient(&err) => {
+                    tokio::time::sleep(self.retry.backoff(attempt, &err)).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+    async fn fetch_once(&self, url: &str) -> Result<String, FetchError> {
+        self.send(url, self.client.get(url)).await
+    }
+    async fn send(
+        &self,
+        url: &str,
+        request: reqwest::RequestBuilder,
+    ) -> Result<String, FetchError> {
+        let request_error = |source| FetchError::Request {
+            url: url.to_owned(),
+            source,
+        };
+        let _permit = self.rate_limiter.acquire(url, None).await;
+        let response = request.send().await.map_err(request_error)?;
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs);
+            return Err(FetchError::Status {
+                url: url.to_owned(),
+                status: status.as_u16(),
+                retry_after,
+            });
+        }
+        response.text().await.map_err(request_error)
+    }
+}
+impl Default for Fetcher {
+    fn default() -> Fetcher {
+        Fetcher::new()
+    }
+}
+
+/// A builder to configure a [`Fetcher`].
+#[derive(Debug)]
+pub struct FetcherBuilder {
+    client: reqwest::ClientBuilder,
+    retry: RetryPolicy,
+    robots_user_agent: Option<String>,
+    requests_per_second_per_host: Option<f64>,
+    max_concurrency: Option<usize>,
+    max_concurrency_per_host: Option<usize>,
+    bootstrap: Option<Bootstrap>,
+}
+impl FetcherBuilder {
+    /// Sets the retry policy. The default is [`RetryPolicy::default()`].
+    pub fn retry(mut self, retry: RetryPolicy) -> FetcherBuilder {
+        self.retry = retry;
+        self
+    }
+    /// Sets the `User-Agent` header sent with every request.
+    pub fn user_agent(mut self, user_agent: &str) -> FetcherBuilder {
+        self.client = self.client.user_agent(user_agent);
+        self
+    }
+    /// Sets headers sent with every request.
+    pub fn default_headers(mut self, headers: reqwest::header::HeaderMap) -> FetcherBuilder {
+        self.client = self.client.default_headers(headers);
+        self
+    }
+    /// Enables a cookie store, which keeps cookies set by responses and sends them with subsequent requests.
+    ///
+    /// The cookies are shared by the clones of the [`Fetcher`].
+    pub fn cookie_store(mut self, enable: bool) -> FetcherBuilder {
+        self.client = self.client.cookie_store(enable);
+        self
+    }
+    /// Uses `jar` as the cookie store.
+    ///
+    /// This allows cookies to be set in advance or shared with other [`Fetcher`]s.
+    pub fn cookie_jar(mut self, jar: Arc<reqwest::cookie::Jar>) -> FetcherBuilder {
+        self.client = self.client.cookie_provider(jar);
+        self
+    }
+    /// Sets a hook that runs once before the first request, e.g. to log in.
+    ///
+    /// The hook receives a clone of the [`Fetcher`] to send requests with.
+    /// If it fails, the request that triggered it fails and the hook runs again on the next request.
+    /// ```no_run
+    /// use html_extractor::fetch::Fetcher;
+    /// let fetcher = Fetcher::builder()
+    ///     .cookie_store(true)
+    ///     .bootstrap(|fetcher| async move {
+    ///         fetcher
+    ///             .post_form("https://example.com/login", &[("user", "foo"), ("password", "bar")])
+    ///             .await?;
+    ///         Ok(())
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn bootstrap<F, Fut>(mut self, hook: F) -> FetcherBuilder
+    where
+        F: Fn(Fetcher) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), FetchError>> + Send + 'static,
+    {
+        self.bootstrap = Some(Bootstrap {
+            hook: Box::new(move |fetcher| Box::pin(hook(fetcher))),
+            done: tokio::sync::OnceCell::new(),
+        });
+        self
+    }
+    /// Sets the timeout of each request.
+    pub fn timeout(mut self, timeout: Duration) -> FetcherBuilder {
+        self.client = self.client.timeout(timeout);
+        self
+    }
+    /// Makes the [`Fetcher`] respect robots.txt, matching the groups in it with `user_agent`.
+    ///
+    /// See [`Fetcher::robots_txt`] for how robots.txt is fetched.
+    pub fn respect_robots_txt(mut self, user_agent: &str) -> FetcherBuilder {
+        self.robots_user_agent = Some(user_agent.to_owned());
+        self
+    }
+    /// Limits the number of requests per second to each host.
+    ///
+    /// Requests exceeding the limit wait until they are allowed. Retries and robots.txt requests are also limited.
+    pub fn requests_per_second_per_host(mut self, requests_per_second: f64) -> FetcherBuilder {
+        self.requests_per_second_per_host = Some(requests_per_second);
+        self
+    }
+    /// Limits the number of requests in flight across all hosts.
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> FetcherBuilder {
+        self.max_concurrency = Some(max_concurrency);
+        self
+    }
+    /// Limits the number of requests in flight to each host.
+    pub fn max_concurrency_per_host(mut self, max_concurrency: usize) -> FetcherBuilder {
+        self.max_concurrency_per_host = Some(max_concurrency);
+        self
+    }
+    /// Builds the [`Fetcher`].
+    pub fn build(self) -> Result<Fetcher, FetchError> {
+        Ok(Fetcher {
+            client: self.client.build().map_err(FetchError::Client)?,
+            retry: self.retry,
+            robots: self.robots_user_agent.map(|user_agent| {
+                Arc::new(RobotsCache {
+                    user_agent,
+                    robots_txts: Mutex::new(HashMap::new()),
+                })
+            }),
+            rate_limiter: Arc::new(rate_limit::RateLimiter::new(
+                self.requests_per_second_per_host
+                    .filter(|rps| *rps > 0.0)
+                    .map(|rps| Duration::from_secs_f64(1.0 / rps)),
+                self.max_concurrency,
+                self.max_concurrency_per_host,
+            )),
+            bootstrap: self.bootstrap.map(Arc::new),
+        })
+    }
+}
+
+/// Extracts data from `documents` on the blocking thread pool of tokio, running up to `concurrency` extractions at once.
+///
+/// `on_result` is called with the index of each document and its result as soon as it is ready, so the order is not preserved.
+/// A failure of one document doesn't affect the others.
+pub async fn extract_documents_batch<T, I, F>(documents: I, concurrency: usize, mut on_result: F)
+where
+    T: HtmlExtractor + Send + 'static,
+    I: IntoIterator<Item = String>,
+    F: FnMut(usize, Result<T, Error>),
+{
+    stream::iter(documents.into_iter().enumerate())
+        .map(|(i, document)| async move {
+            let data = tokio::task::spawn_blocking(move || T::extract_from_str(&document)).await;
+            match data {
+                Ok(data) => (i, data),
+                Err(err) => std::panic::resume_unwind(err.into_panic()),
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .for_each(|(i, data)| {
+            on_result(i, data);
+            async {}
+        })
+        .await;
+}
+
+/// Fetches the document at `url` and extracts data from it with the default [`Fetcher`].
+///
+/// To reuse connections or to configure retries, create a [`Fetcher`] and use [`Fetcher::extract`] instead.
+pub async fn extract_from_url<T: HtmlExtractor>(url: &str) -> Result<T, Error> {
+    Fetcher::new().extract(url).await
+}
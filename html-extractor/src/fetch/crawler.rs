@@ -0,0 +1,340 @@
+use super::rate_limit::RateLimiter;
+use super::{FetchError, Fetcher};
+use crate::{Error, HtmlExtractor};
+use futures_util::stream::{self, FuturesUnordered, Stream, StreamExt};
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A store of the URLs a [`Crawler`] has seen and the URLs it is going to visit.
+///
+/// The default is [`MemoryUrlStore`]. A store backed by a database allows a long-running crawl to be resumed:
+/// the seed URLs are ignored if they have already been seen, and the crawl continues from the stored URLs.
+pub trait UrlStore: Send {
+    /// Adds `url`, found at `depth` links from a seed URL, to the URLs to visit.
+    ///
+    /// Returns `false` and does nothing if `url` has already been added.
+    fn push(&mut self, url: &str, depth: usize) -> bool;
+    /// Takes the next URL to visit and its depth.
+    fn pop(&mut self) -> Option<(String, usize)>;
+}
+
+/// A [`UrlStore`] that keeps the URLs in memory and visits them in breadth-first order.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryUrlStore {
+    seen: HashSet<String>,
+    frontier: VecDeque<(String, usize)>,
+}
+impl MemoryUrlStore {
+    /// Creates an empty store.
+    pub fn new() -> MemoryUrlStore {
+        MemoryUrlStore::default()
+    }
+}
+impl UrlStore for MemoryUrlStore {
+    fn push(&mut self, url: &str, depth: usize) -> bool {
+        if !self.seen.insert(url.to_owned()) {
+            return false;
+        }
+        self.frontier.push_back((url.to_owned(), depth));
+        true
+    }
+    fn pop(&mut self) -> Option<(String, usize)> {
+        self.frontier.pop_front()
+    }
+}
+
+type ExtractFn<O> = Box<dyn Fn(&ElementRef) -> Result<O, Error> + Send + Sync>;
+type FilterFn = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// A crawler that follows links from seed URLs and extracts data from the pages it visits.
+///
+/// Pages are routed to extractors by their URL. Pages that match no route are only used to find links.
+///
+/// By default, up to 8 pages are fetched concurrently, but only one at a time from each host,
+/// waiting for the `Crawl-delay` in robots.txt between requests to the host if the [`Fetcher`] respects robots.txt.
+/// These are configured with [`Crawler::concurrency`], [`Crawler::max_in_flight_per_host`] and [`Crawler::crawl_delay`],
+/// and apply in addition to the limits of the [`Fetcher`].
+/// ```no_run
+/// use futures_util::StreamExt;
+/// use html_extractor::{html_extractor, fetch::{Crawler, Fetcher}};
+/// use regex::Regex;
+/// use scraper::Selector;
+/// html_extractor! {
+///     Article {
+///         title: String = (text of "h1"),
+///     }
+/// }
+///
+/// async fn print_titles() {
+///     let articles = Crawler::new(Fetcher::new())
+///         .seed("https://example.com/")
+///         .follow_links(Selector::parse("a").unwrap())
+///         .route(Regex::new(r"/articles/\d+$").unwrap(), |article: Article| article)
+///         .max_depth(3)
+///         .crawl();
+///     futures_util::pin_mut!(articles);
+///     while let Some((url, article)) = articles.next().await {
+///         match article {
+///             Ok(article) => println!("{}: {}", url, article.title),
+///             Err(err) => eprintln!("{}: {}", url, err),
+///         }
+///     }
+/// }
+/// # fn main() {}
+/// ```
+pub struct Crawler<O> {
+    fetcher: Fetcher,
+    seeds: Vec<String>,
+    link_selector: Option<Selector>,
+    follow_if: Option<FilterFn>,
+    routes: Vec<(Regex, ExtractFn<O>)>,
+    store: Box<dyn UrlStore>,
+    max_depth: Option<usize>,
+    max_pages: Option<usize>,
+    concurrency: usize,
+    max_in_flight_per_host: usize,
+    crawl_delay: Option<Duration>,
+}
+impl<O> std::fmt::Debug for Crawler<O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Crawler")
+            .field("fetcher", &self.fetcher)
+            .field("seeds", &self.seeds)
+            .field("link_selector", &self.link_selector)
+            .field(
+                "routes",
+                &self
+                    .routes
+                    .iter()
+                    .map(|(pattern, _)| pattern)
+                    .collect::<Vec<_>>(),
+            )
+            .field("max_depth", &self.max_depth)
+            .field("max_pages", &self.max_pages)
+            .field("concurrency", &self.concurrency)
+            .field("max_in_flight_per_host", &self.max_in_flight_per_host)
+            .field("crawl_delay", &self.crawl_delay)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<O> Crawler<O> {
+    /// Creates a crawler that fetches pages with `fetcher`.
+    pub fn new(fetcher: Fetcher) -> Crawler<O> {
+        Crawler {
+            fetcher,
+            seeds: Vec::new(),
+            link_selector: None,
+            follow_if: None,
+            routes: Vec::new(),
+            store: Box::new(MemoryUrlStore::new()),
+            max_depth: None,
+            max_pages: None,
+            concurrency: 8,
+            max_in_flight_per_host: 1,
+            crawl_delay: None,
+        }
+    }
+    /// Adds a URL to start crawling from.
+    pub fn seed(mut self, url: &str) -> Crawler<O> {
+        self.seeds.push(url.to_owned());
+        self
+    }
+    /// Follows the `href` of the elements matching `selector`.
+    ///
+    /// Without this, only the seed URLs are visited.
+    pub fn follow_links(mut self, selector: Selector) -> Crawler<O> {
+        self.link_selector = Some(selector);
+        self
+    }
+    /// Follows only the links for which `filter` returns `true`.
+    ///
+    /// By default, links to the origins of the seed URLs are followed.
+    pub fn follow_if(
+        mut self,
+        filter: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Crawler<O> {
+        self.follow_if = Some(Box::new(filter));
+        self
+    }
+    /// Extracts `T` from the pages whose URL matches `pattern`, and maps it to the output with `map`.
+    ///
+    /// Routes are tried in the order they are added, and the first matching one is used.
+    pub fn route<T: HtmlExtractor>(
+        mut self,
+        pattern: Regex,
+        map: impl Fn(T) -> O + Send + Sync + 'static,
+    ) -> Crawler<O> {
+        self.routes
+            .push((pattern, Box::new(move |elem| T::extract(elem).map(&map))));
+        self
+    }
+    /// Sets the store of the seen URLs and the URLs to visit. The default is [`MemoryUrlStore`].
+    pub fn url_store(mut self, store: impl UrlStore + 'static) -> Crawler<O> {
+        self.store = Box::new(store);
+        self
+    }
+    /// Sets the maximum number of links to follow from a seed URL.
+    pub fn max_depth(mut self, max_depth: usize) -> Crawler<O> {
+        self.max_depth = Some(max_depth);
+        self
+    }
+    /// Sets the maximum number of pages to fetch.
+    pub fn max_pages(mut self, max_pages: usize) -> Crawler<O> {
+        self.max_pages = Some(max_pages);
+        self
+    }
+    /// Sets the maximum number of pages fetched concurrently. The default is 8.
+    ///
+    /// With more than one, the order of the results depends on when the pages are fetched.
+    pub fn concurrency(mut self, concurrency: usize) -> Crawler<O> {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+    /// Sets the maximum number of pages fetched concurrently from each host. The default is 1.
+    pub fn max_in_flight_per_host(mut self, max_in_flight: usize) -> Crawler<O> {
+        self.max_in_flight_per_host = max_in_flight.max(1);
+        self
+    }
+    /// Sets the minimum delay between requests to each host.
+    ///
+    /// If the [`Fetcher`] respects robots.txt and it specifies a longer `Crawl-delay`, that is used instead.
+    pub fn crawl_delay(mut self, crawl_delay: Duration) -> Crawler<O> {
+        self.crawl_delay = Some(crawl_delay);
+        self
+    }
+
+    /// Starts crawling and returns a stream of the extracted data paired with the URL of the page.
+    ///
+    /// Pages are taken in the order given by the [`UrlStore`], each URL at most once, as the stream is polled.
+    /// Failures to fetch a page are also yielded, whether or not it matches a route.
+    pub fn crawl(self) -> impl Stream<Item = (String, Result<O, Error>)> {
+        let mut state = CrawlState {
+            seed_origins: self
+                .seeds
+                .iter()
+                .filter_map(|seed| reqwest::Url::parse(seed).ok())
+                .map(|seed| seed.origin().ascii_serialization())
+                .collect(),
+            fetched: 0,
+            in_flight: FuturesUnordered::new(),
+            limiter: Arc::new(RateLimiter::new(
+                None,
+                None,
+                Some(self.max_in_flight_per_host),
+            )),
+            crawler: self,
+        };
+        for seed in std::mem::take(&mut state.crawler.seeds) {
+            state.enqueue(seed, 0);
+        }
+        stream::unfold(state, |mut state| async move {
+            let item = state.next().await?;
+            Some((item, state))
+        })
+    }
+}
+
+struct CrawlState<O> {
+    crawler: Crawler<O>,
+    seed_origins: HashSet<String>,
+    fetched: usize,
+    in_flight: FuturesUnordered<PageFuture>,
+    limiter: Arc<RateLimiter>,
+}
+
+type PageFuture = Pin<Box<dyn Future<Output = (String, usize, Result<String, FetchError>)> + Send>>;
+impl<O> CrawlState<O> {
+    fn enqueue(&mut self, mut url: String, depth: usize) {
+        if let Ok(mut parsed_url) = reqwest::Url::parse(&url) {
+            parsed_url.set_fragment(None);
+            url = parsed_url.into();
+        }
+        self.crawler.store.push(&url, depth);
+    }
+
+    async fn next(&mut self) -> Option<(String, Result<O, Error>)> {
+        loop {
+            while self.in_flight.len() < self.crawler.concurrency
+                && self.crawler.max_pages.is_none_or(|max| self.fetched < max)
+            {
+                let (url, depth) = match self.crawler.store.pop() {
+                    Some(next) => next,
+                    None => break,
+                };
+                self.fetched += 1;
+                self.in_flight.push(self.fetch_page(url, depth));
+            }
+
+            let (url, depth, body) = self.in_flight.next().await?;
+            let body = match body {
+                Ok(body) => body,
+                Err(err) => return Some((url, Err(err.into()))),
+            };
+            let (links, data) = self.process(&url, &body);
+            if self.crawler.max_depth.is_none_or(|max| depth < max) {
+                for link in links {
+                    self.enqueue(link, depth + 1);
+                }
+            }
+            if let Some(data) = data {
+                return Some((url, data));
+            }
+        }
+    }
+
+    fn fetch_page(&self, url: String, depth: usize) -> PageFuture {
+        let fetcher = self.crawler.fetcher.clone();
+        let limiter = Arc::clone(&self.limiter);
+        let crawl_delay = self.crawler.crawl_delay;
+        Box::pin(async move {
+            let crawl_delay = match (crawl_delay, fetcher.robots_crawl_delay(&url).await) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            let body = {
+                let _permit = limiter.acquire(&url, crawl_delay).await;
+                fetcher.fetch(&url).await
+            };
+            (url, depth, body)
+        })
+    }
+
+    /// Finds the links to follow in the page and extracts data from it if it matches a route.
+    fn process(&self, url: &str, body: &str) -> (Vec<String>, Option<Result<O, Error>>) {
+        let document = Html::parse_document(body);
+        let root = document.root_element();
+        let mut links = Vec::new();
+        if let (Some(selector), Ok(base_url)) =
+            (&self.crawler.link_selector, reqwest::Url::parse(url))
+        {
+            for elem in root.select(selector) {
+                let link = match elem.value().attr("href").map(|href| base_url.join(href)) {
+                    Some(Ok(link)) if matches!(link.scheme(), "http" | "https") => link,
+                    _ => continue,
+                };
+                let follow = match &self.crawler.follow_if {
+                    Some(follow_if) => follow_if(link.as_str()),
+                    None => self
+                        .seed_origins
+                        .contains(&link.origin().ascii_serialization()),
+                };
+                if follow {
+                    links.push(link.into());
+                }
+            }
+        }
+        let data = self
+            .crawler
+            .routes
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(url))
+            .map(|(_, extract)| extract(&root));
+        (links, data)
+    }
+}
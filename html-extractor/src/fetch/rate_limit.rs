@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Limits the request rate per host and the number of concurrent requests, globally and per host.
+#[derive(Debug, Default)]
+pub(super) struct RateLimiter {
+    interval: Option<Duration>,
+    concurrency: Option<Arc<Semaphore>>,
+    concurrency_per_host: Option<usize>,
+    hosts: Mutex<HashMap<String, Host>>,
+}
+
+#[derive(Debug)]
+struct Host {
+    next_slot: Instant,
+    semaphore: Option<Arc<Semaphore>>,
+}
+
+/// Allows a request while it is held.
+pub(super) struct Permit {
+    _host: Option<OwnedSemaphorePermit>,
+    _global: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    pub(super) fn new(
+        interval: Option<Duration>,
+        max_concurrency: Option<usize>,
+        max_concurrency_per_host: Option<usize>,
+    ) -> RateLimiter {
+        RateLimiter {
+            interval,
+            concurrency: max_concurrency.map(|n| Arc::new(Semaphore::new(n))),
+            concurrency_per_host: max_concurrency_per_host,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits until a request to `url` is allowed.
+    ///
+    /// `interval` overrides the interval between requests to the host if it is longer.
+    pub(super) async fn acquire(&self, url: &str, interval: Option<Duration>) -> Permit {
+        let interval = match (self.interval, interval) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        let origin = match reqwest::Url::parse(url) {
+            Ok(url) => url.origin().ascii_serialization(),
+            Err(_) => return self.acquire_global().await,
+        };
+
+        let host_semaphore = self.concurrency_per_host.map(|n| {
+            let mut hosts = self.hosts.lock().unwrap_or_else(PoisonError::into_inner);
+            let host = hosts.entry(origin.clone()).or_insert_with(Host::new);
+            Arc::clone(
+                host.semaphore
+                    .get_or_insert_with(|| Arc::new(Semaphore::new(n))),
+            )
+        });
+        let host_permit = match host_semaphore {
+            Some(semaphore) => semaphore.acquire_owned().await.ok(),
+            None => None,
+        };
+
+        if let Some(interval) = interval {
+            let slot = {
+                let mut hosts = self.hosts.lock().unwrap_or_else(PoisonError::into_inner);
+                let host = hosts.entry(origin).or_insert_with(Host::new);
+                let slot = host.next_slot.max(Instant::now());
+                host.next_slot = slot + interval;
+                slot
+            };
+            tokio::time::sleep_until(slot).await;
+        }
+
+        Permit {
+            _host: host_permit,
+            ..self.acquire_global().await
+        }
+    }
+
+    async fn acquire_global(&self) -> Permit {
+        let global_permit = match &self.concurrency {
+            Some(semaphore) => Arc::clone(semaphore).acquire_owned().await.ok(),
+            None => None,
+        };
+        Permit {
+            _host: None,
+            _global: global_permit,
+        }
+    }
+}
+
+impl Host {
+    fn new() -> Host {
+        Host {
+            next_slot: Instant::now(),
+            semaphore: None,
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use super::FetchError;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A policy to retry failed requests with exponential backoff.
+///
+/// By default, a request is retried up to 3 times when it times out, cannot connect,
+/// or the server responds with one of 408, 429, 500, 502, 503 and 504.
+/// The backoff starts at 500 milliseconds and doubles each time up to 30 seconds, with jitter.
+/// If the server responds with a `Retry-After` header in seconds, it is used as the backoff instead.
+/// ```
+/// use html_extractor::fetch::{Fetcher, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let fetcher = Fetcher::builder()
+///     .retry(
+///         RetryPolicy::new()
+///             .max_retries(5)
+///             .initial_backoff(Duration::from_secs(1))
+///             .retry_on_status(vec![429, 503])
+///             // do not retry requests to the admin pages
+///             .classify(|err, transient| transient && !err.to_string().contains("/admin/")),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    pub(super) max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: bool,
+    retry_on_status: Vec<u16>,
+    #[allow(clippy::type_complexity)]
+    classifier: Option<Arc<dyn Fn(&FetchError, bool) -> bool + Send + Sync>>,
+}
+impl RetryPolicy {
+    /// Creates the default retry policy.
+    pub fn new() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+    /// Creates a retry policy that never retries.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::default().max_retries(0)
+    }
+
+    /// Sets the maximum number of retries.
+    pub fn max_retries(mut self, max_retries: u32) -> RetryPolicy {
+        self.max_retries = max_retries;
+        self
+    }
+    /// Sets the backoff before the first retry.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> RetryPolicy {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+    /// Sets the upper limit of the backoff.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> RetryPolicy {
+        self.max_backoff = max_backoff;
+        self
+    }
+    /// Sets the factor by which the backoff is multiplied after each retry.
+    pub fn multiplier(mut self, multiplier: f64) -> RetryPolicy {
+        self.multiplier = multiplier;
+        self
+    }
+    /// Sets whether to randomize the backoff between 50% and 100% of its value.
+    pub fn jitter(mut self, jitter: bool) -> RetryPolicy {
+        self.jitter = jitter;
+        self
+    }
+    /// Sets the HTTP status codes on which requests are retried.
+    pub fn retry_on_status(mut self, statuses: impl IntoIterator<Item = u16>) -> RetryPolicy {
+        self.retry_on_status = statuses.into_iter().collect();
+        self
+    }
+    /// Sets a function that classifies failures into transient (retried) and permanent ones.
+    ///
+    /// The function receives the error and whether it is transient according to the default classification.
+    pub fn classify(
+        mut self,
+        classifier: impl Fn(&FetchError, bool) -> bool + Send + Sync + 'static,
+    ) -> RetryPolicy {
+        self.classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// Returns whether the request that failed with `err` should be retried.
+    pub fn is_transient(&self, err: &FetchError) -> bool {
+        let transient = match err {
+            FetchError::Client(_) | FetchError::Disallowed { .. } => false,
+            FetchError::Request { source, .. } => {
+                source.is_timeout() || source.is_connect() || source.is_request()
+            }
+            FetchError::Status { status, .. } => self.retry_on_status.contains(status),
+        };
+        match &self.classifier {
+            Some(classifier) => classifier(err, transient),
+            None => transient,
+        }
+    }
+
+    /// Returns the backoff before the retry following the `attempt`-th (zero-based) retry.
+    pub fn backoff(&self, attempt: u32, err: &FetchError) -> Duration {
+        if let FetchError::Status {
+            retry_after: Some(retry_after),
+            ..
+        } = err
+        {
+            return (*retry_after).min(self.max_backoff);
+        }
+
+        let backoff = self
+            .initial_backoff
+            .mul_f64(self.multiplier.powi(attempt as i32))
+            .min(self.max_backoff);
+        if self.jitter {
+            backoff.mul_f64(0.5 + random_fraction() / 2.0)
+        } else {
+            backoff
+        }
+    }
+}
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+            retry_on_status: vec![408, 429, 500, 502, 503, 504],
+            classifier: None,
+        }
+    }
+}
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_retries", &self.max_retries)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("retry_on_status", &self.retry_on_status)
+            .field("classifier", &self.classifier.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// Returns a pseudo-random number in `[0, 1)`, which is good enough for jitter.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+/// A parsed robots.txt.
+///
+/// Rules are matched as described in [RFC 9309](https://www.rfc-editor.org/rfc/rfc9309):
+/// the group for the most specific matching user agent (or `*`) applies, the longest matching rule wins,
+/// and `Allow` wins over `Disallow` when they are equally long. `*` and `$` in paths are supported.
+/// ```
+/// use html_extractor::fetch::RobotsTxt;
+///
+/// let robots_txt = RobotsTxt::parse("
+///     User-agent: *
+///     Disallow: /private/
+///     Allow: /private/public.html
+///     Crawl-delay: 2
+/// ");
+/// assert!(robots_txt.is_allowed("MyBot", "/index.html"));
+/// assert!(!robots_txt.is_allowed("MyBot", "/private/secret.html"));
+/// assert!(robots_txt.is_allowed("MyBot", "/private/public.html"));
+/// assert_eq!(robots_txt.crawl_delay("MyBot"), Some(std::time::Duration::from_secs(2)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RobotsTxt {
+    groups: Vec<Group>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    user_agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    allow: bool,
+    len: usize,
+    pattern: regex::Regex,
+}
+
+impl RobotsTxt {
+    /// Parses a robots.txt. Lines that cannot be understood are ignored.
+    pub fn parse(robots_txt: &str) -> RobotsTxt {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut in_user_agent_lines = false;
+        for line in robots_txt.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim().to_ascii_lowercase(), value.trim()),
+                None => continue,
+            };
+            match &*key {
+                "user-agent" => {
+                    if !in_user_agent_lines {
+                        groups.push(Group::default());
+                    }
+                    in_user_agent_lines = true;
+                    if let Some(group) = groups.last_mut() {
+                        group.user_agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                "allow" | "disallow" => {
+                    in_user_agent_lines = false;
+                    if value.is_empty() {
+                        continue;
+                    }
+                    if let (Some(group), Some(rule)) = (groups.last_mut(), Rule::new(&key, value)) {
+                        group.rules.push(rule);
+                    }
+                }
+                "crawl-delay" => {
+                    in_user_agent_lines = false;
+                    if let (Some(group), Ok(delay)) = (groups.last_mut(), value.parse::<f64>()) {
+                        if delay.is_finite() && delay >= 0.0 {
+                            group.crawl_delay = Some(Duration::from_secs_f64(delay));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        RobotsTxt { groups }
+    }
+    /// Creates a robots.txt that allows everything.
+    pub fn allow_all() -> RobotsTxt {
+        RobotsTxt::default()
+    }
+    /// Creates a robots.txt that disallows everything.
+    pub fn disallow_all() -> RobotsTxt {
+        RobotsTxt::parse("User-agent: *\nDisallow: /")
+    }
+
+    /// Returns whether `user_agent` is allowed to fetch `path` (including the query string, if any).
+    pub fn is_allowed(&self, user_agent: &str, path: &str) -> bool {
+        let group = match self.group(user_agent) {
+            Some(group) => group,
+            None => return true,
+        };
+        group
+            .rules
+            .iter()
+            .filter(|rule| rule.pattern.is_match(path))
+            .max_by_key(|rule| (rule.len, rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+    /// Returns the `Crawl-delay` for `user_agent`, if any.
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.group(user_agent).and_then(|group| group.crawl_delay)
+    }
+
+    fn group(&self, user_agent: &str) -> Option<&Group> {
+        let user_agent = user_agent.to_ascii_lowercase();
+        self.groups
+            .iter()
+            .flat_map(|group| group.user_agents.iter().map(move |ua| (ua, group)))
+            .filter(|(ua, _)| *ua == "*" || user_agent.contains(ua.as_str()))
+            .max_by_key(|(ua, _)| if *ua == "*" { 0 } else { ua.len() })
+            .map(|(_, group)| group)
+    }
+}
+
+impl Rule {
+    fn new(key: &str, path: &str) -> Option<Rule> {
+        let (path, anchored) = match path.strip_suffix('$') {
+            Some(path) => (path, true),
+            None => (path, false),
+        };
+        let pattern = path
+            .split('*')
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(".*");
+        let pattern = format!("^{}{}", pattern, if anchored { "$" } else { "" });
+        Some(Rule {
+            allow: key == "allow",
+            len: path.len(),
+            pattern: regex::Regex::new(&pattern).ok()?,
+        })
+    }
+}
@@ -0,0 +1,67 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// A parsed sitemap or sitemap index.
+///
+/// See [sitemaps.org](https://www.sitemaps.org/protocol.html) for the format.
+/// ```
+/// use html_extractor::fetch::Sitemap;
+///
+/// let sitemap = Sitemap::parse(r#"
+///     <?xml version="1.0" encoding="UTF-8"?>
+///     <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+///         <url><loc>https://example.com/?a=1&amp;b=2</loc></url>
+///         <url><loc>https://example.com/about</loc><lastmod>2020-01-01</lastmod></url>
+///     </urlset>
+/// "#);
+/// assert_eq!(sitemap.urls, vec!["https://example.com/?a=1&b=2", "https://example.com/about"]);
+/// assert!(sitemap.sitemaps.is_empty());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sitemap {
+    /// The URLs of the pages, listed in `<url>` elements.
+    pub urls: Vec<String>,
+    /// The URLs of the sitemaps, listed in `<sitemap>` elements of a sitemap index.
+    pub sitemaps: Vec<String>,
+}
+
+impl Sitemap {
+    /// Parses a sitemap or sitemap index. Entries that cannot be understood are ignored.
+    pub fn parse(xml: &str) -> Sitemap {
+        lazy_static! {
+            static ref ENTRY_REGEX: Regex =
+                Regex::new(r"(?s)<(url|sitemap)\b[^>]*>(.*?)</(?:url|sitemap)\s*>").unwrap();
+            static ref LOC_REGEX: Regex = Regex::new(r"(?s)<loc\b[^>]*>(.*?)</loc\s*>").unwrap();
+        }
+        let mut sitemap = Sitemap::default();
+        for entry in ENTRY_REGEX.captures_iter(xml) {
+            let loc = match LOC_REGEX.captures(&entry[2]) {
+                Some(loc) => unescape(loc[1].trim()),
+                None => continue,
+            };
+            if loc.is_empty() {
+                continue;
+            }
+            if &entry[1] == "url" {
+                sitemap.urls.push(loc);
+            } else {
+                sitemap.sitemaps.push(loc);
+            }
+        }
+        sitemap
+    }
+}
+
+fn unescape(text: &str) -> String {
+    if let Some(cdata) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|text| text.strip_suffix("]]>"))
+    {
+        return cdata.trim().to_owned();
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,82 @@
+use crate::{Error, HtmlExtractor};
+use scraper::{ElementRef, Html};
+
+/// An owned copy of the subtree of an element, for extracting data from it later.
+///
+/// `ElementRef` borrows the parsed document, so it cannot be stored in extracted structures.
+/// `OwnedFragment` keeps the serialized HTML of the element instead, and is parsed again when data is extracted from it.
+/// It implements [`HtmlExtractor`], so it can be the type of fields with the [`elem of ..` target](macro.html_extractor.html#target-specifier).
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor, OwnedFragment};
+/// html_extractor! {
+///     Page {
+///         title: String = (text of "h1"),
+///         widget: OwnedFragment = (elem of "#widget"),
+///     }
+///     #[derive(Debug, PartialEq)]
+///     Widget {
+///         count: usize = (text of ".count"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <h1>Title</h1>
+///         <div id="widget"><span class="count">3</span></div>
+///     "#;
+///     let page = Page::extract_from_str(input).unwrap();
+///     assert_eq!(page.widget.html(), r#"<div id="widget"><span class="count">3</span></div>"#);
+///
+///     // later, with another extractor
+///     let widget: Widget = page.widget.extract().unwrap();
+///     assert_eq!(widget, Widget { count: 3 });
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedFragment {
+    name: String,
+    html: String,
+}
+impl OwnedFragment {
+    /// Returns the serialized HTML of the element, including the element itself.
+    pub fn html(&self) -> &str {
+        &self.html
+    }
+    /// Returns the tag name of the element.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Parses the fragment and extracts data from the element, as the `elem of ..` target does.
+    pub fn extract<T: HtmlExtractor>(&self) -> Result<T, Error> {
+        let html = self.parse();
+        let root = html.root_element();
+        // the wrappers added by `parse` are ancestors with other names
+        let elem = root
+            .descendants()
+            .filter_map(ElementRef::wrap)
+            .find(|e| e.value().name() == self.name)
+            .unwrap_or(root);
+        T::extract(&elem)
+    }
+
+    /// Parses the fragment in a context where the element is allowed.
+    fn parse(&self) -> Html {
+        let (open, close) = match &*self.name {
+            "html" | "head" | "body" => return Html::parse_document(&self.html),
+            "caption" | "colgroup" | "thead" | "tbody" | "tfoot" => ("<table>", "</table>"),
+            "col" => ("<table><colgroup>", "</colgroup></table>"),
+            "tr" => ("<table><tbody>", "</tbody></table>"),
+            "td" | "th" => ("<table><tbody><tr>", "</tr></tbody></table>"),
+            _ => ("", ""),
+        };
+        Html::parse_fragment(&format!("{}{}{}", open, self.html, close))
+    }
+}
+impl HtmlExtractor for OwnedFragment {
+    fn extract(elem: &ElementRef) -> Result<Self, Error> {
+        Ok(OwnedFragment {
+            name: elem.value().name().to_owned(),
+            html: elem.html(),
+        })
+    }
+}
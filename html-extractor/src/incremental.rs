@@ -0,0 +1,119 @@
+//! Re-extraction of documents that are mostly unchanged since the last extraction.
+//!
+//! Structures with the [`#![incremental]`](../macro.html_extractor.html#struct-options) option implement
+//! [`IncrementalExtractor`]. A [`Fingerprint`] records a hash of the serialized HTML of the elements each field
+//! is extracted from, and [`IncrementalExtractor::reextract`] runs the extractors only for the fields
+//! whose elements have changed, keeping the previous values of the others.
+
+use crate::{Error, HtmlExtractor};
+use scraper::ElementRef;
+
+/// The hashes of the regions of a document the fields of a structure are extracted from.
+///
+/// The hash is stable across runs and builds, so fingerprints can be stored with the extracted data.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Fingerprint {
+    fields: Vec<(&'static str, u64)>,
+}
+impl Fingerprint {
+    /// Returns the hash of the region of the field.
+    ///
+    /// For tuple fields, the name of the first field is used.
+    pub fn get(&self, field: &str) -> Option<u64> {
+        self.fields
+            .iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, hash)| *hash)
+    }
+    /// Returns an iterator over the field names and the hashes.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        self.fields.iter().copied()
+    }
+    #[doc(hidden)]
+    pub fn __push(&mut self, field: &'static str, hash: u64) {
+        self.fields.push((field, hash));
+    }
+}
+
+/// The result of [`IncrementalExtractor::reextract`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reextracted<T> {
+    /// The extracted data.
+    pub data: T,
+    /// The fingerprint of the document, to be passed to the next re-extraction.
+    pub fingerprint: Fingerprint,
+    /// The fields that were extracted again because their regions changed.
+    ///
+    /// For tuple fields, the name of the first field is used.
+    pub recomputed: Vec<&'static str>,
+}
+
+/// A trait for re-extracting data only from the changed regions of a document.
+///
+/// It is implemented by [`html_extractor!`](../macro.html_extractor.html) for structures with the `#![incremental]` option.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor, incremental::IncrementalExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Page {
+///         #![incremental]
+///         title: String = (text of "h1"),
+///         price: usize = (text of "#price"),
+///     }
+/// }
+///
+/// fn main() {
+///     let old = r#"<h1>Coffee</h1><div id="price">100</div>"#;
+///     let new = r#"<h1>Coffee</h1><div id="price">120</div>"#;
+///
+///     let page = Page::extract_from_str(old).unwrap();
+///     let fingerprint = Page::fingerprint_from_str(old);
+///
+///     let result = Page::reextract_from_str(page, &fingerprint, new).unwrap();
+///     assert_eq!(result.data, Page { title: "Coffee".to_owned(), price: 120 });
+///     assert_eq!(result.recomputed, vec!["price"]);
+/// }
+/// ```
+pub trait IncrementalExtractor: HtmlExtractor {
+    /// Computes the fingerprint of the regions the fields are extracted from.
+    fn fingerprint(elem: &ElementRef) -> Fingerprint;
+    /// Extracts the fields whose regions differ from `previous_fingerprint`, and takes the others from `previous`.
+    ///
+    /// `previous` must have been extracted from the document `previous_fingerprint` was computed from.
+    /// Fields missing from `previous_fingerprint` are always extracted.
+    fn reextract(
+        previous: Self,
+        previous_fingerprint: &Fingerprint,
+        elem: &ElementRef,
+    ) -> Result<Reextracted<Self>, Error>;
+    /// Parses HTML string and computes the fingerprint of it.
+    fn fingerprint_from_str(html_str: &str) -> Fingerprint {
+        let html = scraper::Html::parse_document(html_str);
+        Self::fingerprint(&html.root_element())
+    }
+    /// Parses HTML string and re-extracts data from it.
+    fn reextract_from_str(
+        previous: Self,
+        previous_fingerprint: &Fingerprint,
+        html_str: &str,
+    ) -> Result<Reextracted<Self>, Error> {
+        let html = scraper::Html::parse_document(html_str);
+        Self::reextract(previous, previous_fingerprint, &html.root_element())
+    }
+}
+
+/// Hashes the serialized HTML of the elements with 64-bit FNV-1a.
+#[doc(hidden)]
+pub fn hash_elements<'a>(elems: impl Iterator<Item = ElementRef<'a>>) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    for elem in elems {
+        // terminate each element so that the boundaries between the elements are hashed too
+        for byte in elem.html().bytes().chain(Some(0)) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
@@ -0,0 +1,37 @@
+use std::borrow::Cow;
+
+/// Decodes HTML bytes into a string, borrowing them if they are valid UTF-8.
+#[cfg(not(feature = "encoding"))]
+pub(crate) fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(bytes)
+}
+
+/// Decodes HTML bytes into a string, borrowing them if they are valid UTF-8.
+#[cfg(feature = "encoding")]
+pub(crate) fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    let (encoding, bom_len) = match encoding_rs::Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => (encoding, bom_len),
+        None => (sniff_meta_charset(bytes).unwrap_or(encoding_rs::UTF_8), 0),
+    };
+    encoding.decode_without_bom_handling(&bytes[bom_len..]).0
+}
+
+/// Looks for the charset declared by `<meta>` in the first 1024 bytes.
+#[cfg(feature = "encoding")]
+fn sniff_meta_charset(bytes: &[u8]) -> Option<&'static encoding_rs::Encoding> {
+    use lazy_static::lazy_static;
+    use regex::bytes::Regex;
+    lazy_static! {
+        static ref CHARSET_REGEX: Regex =
+            Regex::new(r#"(?i)<meta\s[^>]*charset\s*=\s*["']?([\w-]+)"#).unwrap();
+    }
+    let head = &bytes[..bytes.len().min(1024)];
+    let label = CHARSET_REGEX.captures(head)?.get(1)?.as_bytes();
+    let encoding = encoding_rs::Encoding::for_label(label)?;
+    // a document that is decoded as ASCII-compatible bytes cannot declare UTF-16
+    if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+        Some(encoding_rs::UTF_8)
+    } else {
+        Some(encoding)
+    }
+}
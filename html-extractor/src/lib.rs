@@ -1,4 +1,14 @@
 #![allow(clippy::needless_doctest_main)]
+// explicit panics are forbidden in extraction, see the `Panics` section below
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::panic,
+        clippy::todo,
+        clippy::unimplemented,
+        clippy::unreachable
+    )
+)]
 //! This crate provides an easy way to extract data from HTML.
 //!
 //! [`HtmlExtractor`] is neither a parser nor a deserializer.
@@ -66,6 +76,21 @@
 //!     assert_eq!(foo, Foo { foo: 1 });
 //! }
 //! ```
+//!
+//! # Panics
+//! Extraction is meant not to panic on malformed input, such as broken HTML and capture groups that don't
+//! participate in the match. Such input fails with an [`Error`] instead.
+//! This is checked by fuzzing rather than guaranteed: the crate forbids explicit panics like `panic!` and `unreachable!`,
+//! but not `unwrap` or indexing, so a bug can still panic.
+//!
+//! Some input exhausts the stack, which aborts the process:
+//! * A structure that contains itself with `elem of ..` is extracted recursively, once for each level of the nested elements.
+//! * `inner_markdown of ..` and `sanitized_html of ..` use other crates, which may recurse for each level of the nested elements.
+//!
+//! Panics in user-supplied parsers and struct option functions are not caught.
+//!
+//! The `fuzz` directory of the repository contains [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz) targets:
+//! `extract` for the generated extractors, and `functions` for the functions in [`parsers`], [`render`], [`style`] and [`entities`].
 
 #[doc(hidden)]
 pub extern crate lazy_static;
@@ -73,17 +98,40 @@ pub extern crate lazy_static;
 pub extern crate regex;
 #[doc(hidden)]
 pub extern crate scraper;
+pub use context::ExtractWithContext;
 pub use error::Error;
+pub use fragment::OwnedFragment;
+pub mod context;
+#[doc(hidden)]
+pub mod convert;
+mod diagnostics;
+pub mod entities;
 pub mod error;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+mod fragment;
+pub mod incremental;
+mod input;
+pub mod options;
+pub mod parsers;
+pub mod prelude;
+pub mod provenance;
+pub mod render;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[doc(hidden)]
+pub mod select;
+pub mod style;
+mod suggest;
 
 /// Generates structures that implement [`HtmlExtractor`].
 ///
 /// # Syntax
 ///
 /// ## Defining structures
-/// In this macro, zero or more structures can be defined.
+/// In this macro, zero or more structures (and [enums](#defining-enums)) can be defined.
 ///
-/// Attributes can be attached to the structures, but currently attributes that may remove the structures (like `#[cfg]`) will not work.
+/// Attributes can be attached to the structures. `#[cfg]` is also attached to the generated implementations, so conditionally compiled structures work.
 /// ```no_run
 /// # use html_extractor::html_extractor;
 /// # fn main() {}
@@ -100,13 +148,215 @@ pub mod error;
 /// }
 /// ```
 ///
+/// ### Generic structures
+/// Structures can have generic parameters and a `where` clause after the name (and the [root selector](#root-selector)).
+/// The bounds the extraction needs must be given, such as `T: FromStr` and `T::Err: Debug` for the fields
+/// extracted with the default parser.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// use std::{fmt::Debug, str::FromStr};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Value<T> where T: FromStr, T::Err: Debug {
+///         value: T = (text of "#value"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"<div id="value">42</div>"#;
+///     assert_eq!(Value::<u8>::extract_from_str(input).unwrap(), Value { value: 42 });
+///     assert_eq!(Value::<String>::extract_from_str(input).unwrap().value, "42");
+/// }
+/// ```
+///
+/// ### Tuple structures
+/// Tuple structures are defined with the fields in `(..)`, each of which is a type and an extractor.
+/// The `where` clause comes after the fields, and the trailing `;` is optional.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     pub Price(
+///         pub f64 = (text of ".amount"),
+///         pub String = (attr["data-currency"] of ".amount"),
+///     );
+///     #[derive(Debug, PartialEq)]
+///     Title(String = (text of "h1"));
+/// }
+///
+/// fn main() {
+///     let input = r#"<h1>Coffee</h1><span class="amount" data-currency="USD">1.5</span>"#;
+///     assert_eq!(Price::extract_from_str(input).unwrap(), Price(1.5, "USD".to_owned()));
+///     assert_eq!(Title::extract_from_str(input).unwrap().0, "Coffee");
+/// }
+/// ```
+///
+/// ### Root selector
+/// A selector can be given after the name with `in`, like `Item in ".item" { .. }`.
+/// Then `extract_all` and `extract_all_from_str` are also generated, which extract the structure from every element
+/// that matches the selector. `extract_iter` does the same lazily, so that the extraction can stop early
+/// without collecting all the matches.
+/// ```
+/// use html_extractor::html_extractor;
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Product in ".product-card" {
+///         name: String = (text of ".name"),
+///         price: usize = (text of ".price"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <div class="product-card"><span class="name">apple</span><span class="price">100</span></div>
+///         <div class="product-card"><span class="name">orange</span><span class="price">80</span></div>
+///     "#;
+///     let products = Product::extract_all_from_str(input).unwrap();
+///     assert_eq!(
+///         products,
+///         vec![
+///             Product { name: "apple".to_owned(), price: 100 },
+///             Product { name: "orange".to_owned(), price: 80 },
+///         ]
+///     );
+///
+///     let html = scraper::Html::parse_document(input);
+///     let first_cheap = Product::extract_iter(&html.root_element())
+///         .filter_map(Result::ok)
+///         .find(|p| p.price < 90);
+///     assert_eq!(first_cheap.unwrap().name, "orange");
+/// }
+/// ```
+///
+/// ## Struct options
+/// Struct options can be written at the beginning of the structure body in the form of inner attributes.
+///
+/// `#![after_extract(function)]` calls the function after all the fields are extracted.
+/// The function must be `Fn(&mut Self) -> Result<(), html_extractor::Error>`, and an error returned from it fails the extraction.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor, Error};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         #![after_extract(make_slug)]
+///         title: String = (text of "#title"),
+///         slug: String = (text of "#title"),
+///     }
+/// }
+/// fn make_slug(foo: &mut Foo) -> Result<(), Error> {
+///     foo.slug = foo.title.to_lowercase().replace(" ", "-");
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <h1 id="title">Hello World</h1>
+///     "#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo, Foo {
+///         title: "Hello World".to_owned(),
+///         slug: "hello-world".to_owned(),
+///     });
+/// }
+/// ```
+///
+/// `#![validate(function)]` checks the invariants of the extracted structure after `after_extract`.
+/// The function must be `Fn(&Self) -> Result<(), E> where E: Into<Box<dyn std::error::Error + Send + Sync>>`,
+/// and an error returned from it fails the extraction with [`Error::Validation`](error::Error::Validation).
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         #![validate(check_price)]
+///         regular_price: usize = (text of "#regular-price"),
+///         sale_price: usize = (text of "#sale-price"),
+///     }
+/// }
+/// fn check_price(foo: &Foo) -> Result<(), &'static str> {
+///     if foo.sale_price <= foo.regular_price {
+///         Ok(())
+///     } else {
+///         Err("sale price must be less than or equal to regular price")
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <div id="regular-price">100</div>
+///         <div id="sale-price">120</div>
+///     "#;
+///     let err = Foo::extract_from_str(input).unwrap_err();
+///     assert_eq!(err.code(), "E_VALIDATION");
+/// }
+/// ```
+///
+/// `#![getters]` makes all the fields private and generates a getter method for each field instead.
+/// The visibility and the doc comments of the field are applied to the getter.
+/// ```
+/// mod foo {
+///     use html_extractor::html_extractor;
+///     html_extractor! {
+///         pub Foo {
+///             #![getters]
+///             /// The title of the page.
+///             pub title: String = (text of "#title"),
+///         }
+///     }
+/// }
+///
+/// fn main() {
+///     use html_extractor::HtmlExtractor;
+///     let input = r#"
+///         <h1 id="title">Hello World</h1>
+///     "#;
+///     let foo = foo::Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo.title(), "Hello World");
+/// }
+/// ```
+///
+/// `#![incremental]` implements [`IncrementalExtractor`](incremental::IncrementalExtractor), which re-extracts only the fields
+/// whose elements have changed since the previous extraction. It cannot be used with `after_extract` or `from page ..`.
+///
+/// `#![provenance]` implements [`ProvenanceExtractor`](provenance::ProvenanceExtractor), which also records the selector
+/// of each field and the paths of the elements it was extracted from. It cannot be used with `from page ..`.
+///
+/// `#![no_trim]` keeps the leading and trailing whitespace of the text in all the fields,
+/// as [`no_trim`](#transform-specifier) does for a field.
+///
+/// `#![context(name: Type)]` declares the type of the context given with
+/// [`ExtractWithContext::extract_with_context`], which is available to the expressions of the fields as a reference `name: &Type`.
+/// The structure implements [`ExtractWithContext<Type>`](ExtractWithContext) instead of [`HtmlExtractor`],
+/// and the context is given to the nested structures. It cannot be used with `incremental`, `provenance`, `from page ..`
+/// or the root selector.
+/// ```
+/// use html_extractor::{html_extractor, ExtractWithContext};
+/// use std::collections::HashMap;
+/// html_extractor! {
+///     Product {
+///         #![context(rates: HashMap<String, f64>)]
+///         currency: String = (text of ".currency"),
+///         // converted into USD
+///         price: f64 = (text of ".price", parse with |s: &str| s.parse::<f64>().map(|p| p * rates[field_currency])),
+///     }
+/// }
+///
+/// fn main() {
+///     let rates = vec![("EUR".to_owned(), 1.25)].into_iter().collect::<HashMap<_, _>>();
+///     let input = r#"<span class="currency">EUR</span><span class="price">10</span>"#;
+///     let product = Product::extract_from_str_with_context(input, &rates).unwrap();
+///     assert_eq!(product.price, 12.5);
+/// }
+/// ```
+///
 /// ## Defining fields in structures
 /// There are two types of fields, "single field" and "tuple field".
-/// Tuple fields are used to [capture data with regex](#capture-specifier).
+/// Tuple fields are used to [capture data with regex](#capture-specifier), or to extract [several attributes](#target-specifier) at once.
 ///
 /// Each field definition has a declaration part and an [extractor](#extractor-part-of-field-definitions) part.
+/// The type in the declaration part can be any Rust type, including the ones with commas like `HashMap<String, usize>`.
 ///
-/// Attributes can be attached to the fields, but currently attributes that may remove the fields (like `#[cfg]`) will not work.
+/// Attributes can be attached to the fields. `#[cfg]` also removes the extraction of the field, except on the fields of tuple structures.
 /// ```no_run
 /// # use html_extractor::html_extractor;
 /// # fn main() {}
@@ -125,21 +375,251 @@ pub mod error;
 /// }
 /// ```
 ///
+/// The fields defined earlier are available to the expressions of the later fields, like `parse with ..`, `default with ..`
+/// and `init with ..`, as references named after the fields with the prefix `field_`, like `field_currency` for `currency`.
+/// The prefix keeps them from shadowing the functions and the variables of the same names as the fields.
+/// In [`extract_into`](HtmlExtractor::extract_into), the previous value is given for a field that fails to be extracted.
+/// Note that [`reextract`](incremental::IncrementalExtractor::reextract) recomputes a field only when its own elements change,
+/// not when the fields it uses change.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// fn parse_price(s: &str, currency: &str) -> Result<u64, std::num::ParseFloatError> {
+///     let price = s.parse::<f64>()?;
+///     Ok(if currency == "JPY" { price as u64 } else { (price * 100.0) as u64 })
+/// }
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Product {
+///         currency: String = (text of ".currency"),
+///         // in the smallest unit of the currency
+///         price: u64 = (text of ".price", parse with |s| parse_price(s, field_currency)),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"<span class="currency">USD</span><span class="price">1.5</span>"#;
+///     assert_eq!(Product::extract_from_str(input).unwrap().price, 150);
+/// }
+/// ```
+///
+/// ## Defining enums
+/// Enums can be defined with `enum`, to extract data that has different shapes, like the different kinds of pages of a site.
+/// Each variant has a guard `if presence of ".."` and optionally fields, and the first variant whose guard selector matches
+/// any element is extracted. A variant without a guard matches anything, so it can only be the last one.
+/// If no variant matches, the extraction fails.
+///
+/// The fields are defined in the same way as in structures, except that they cannot have visibility or use `from page ..`.
+/// Struct options are not supported.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     pub enum Page {
+///         Captcha if presence of "#captcha",
+///         Article if presence of "article" {
+///             title: String = (text of "article h1"),
+///         },
+///         Listing if presence of ".listing" {
+///             items: Vec<String> = (text of ".listing li", collect),
+///         },
+///         Unknown,
+///     }
+/// }
+///
+/// fn main() {
+///     assert_eq!(
+///         Page::extract_from_str("<article><h1>Hello</h1></article>").unwrap(),
+///         Page::Article { title: "Hello".to_owned() },
+///     );
+///     assert_eq!(
+///         Page::extract_from_str(r#"<ul class="listing"><li>a</li><li>b</li></ul>"#).unwrap(),
+///         Page::Listing { items: vec!["a".to_owned(), "b".to_owned()] },
+///     );
+///     assert_eq!(
+///         Page::extract_from_str(r#"<div id="captcha"></div><article></article>"#).unwrap(),
+///         Page::Captcha,
+///     );
+///     assert_eq!(Page::extract_from_str("<p>?</p>").unwrap(), Page::Unknown);
+/// }
+/// ```
+///
+/// ## Mixins
+/// Field definitions shared by multiple structures can be defined once as a mixin with `mixin Name { .. }`,
+/// and included in structures in the same macro invocation with `use Name;`.
+/// A mixin can also include other mixins.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     mixin Timestamps {
+///         created: String = (text of ".created"),
+///         updated: String = (text of ".updated"),
+///     }
+///     #[derive(Debug, PartialEq)]
+///     Article {
+///         title: String = (text of "h1"),
+///         use Timestamps;
+///     }
+///     #[derive(Debug, PartialEq)]
+///     Comment {
+///         use Timestamps;
+///         body: String = (text of "p"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <h1>Hello</h1>
+///         <p>Nice</p>
+///         <span class="created">2020-01-01</span>
+///         <span class="updated">2020-01-02</span>
+///     "#;
+///     let comment = Comment::extract_from_str(input).unwrap();
+///     assert_eq!(comment, Comment {
+///         created: "2020-01-01".to_owned(),
+///         updated: "2020-01-02".to_owned(),
+///         body: "Nice".to_owned(),
+///     });
+/// }
+/// ```
+///
+/// ## Scope blocks
+/// Fields can be grouped in `in "selector" { .. }` in the structure body.
+/// They are extracted from the first element that matches the selector instead of the whole element,
+/// as if they were in a nested structure. Scope blocks can be nested, and can contain `use Mixin;`.
+///
+/// If no element matches the scope selector, the "optional" and "or default" collectors emit `None` and the default value,
+/// and the other collectors fail.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Page {
+///         title: String = (text of "h1"),
+///         in "#sidebar" {
+///             sidebar_title: String = (text of "h2"),
+///             links: Vec<String> = (attr["href"] of "a", collect),
+///         }
+///         in "#footer" {
+///             copyright: Option<String> = (text of ".copyright", optional),
+///         }
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <h1>Main</h1>
+///         <h2>Section</h2>
+///         <a href="/main">main</a>
+///         <div id="sidebar">
+///             <h2>Links</h2>
+///             <a href="/foo">foo</a>
+///             <a href="/bar">bar</a>
+///         </div>
+///     "#;
+///     let page = Page::extract_from_str(input).unwrap();
+///     assert_eq!(page, Page {
+///         title: "Main".to_owned(),
+///         sidebar_title: "Links".to_owned(),
+///         links: vec!["/foo".to_owned(), "/bar".to_owned()],
+///         copyright: None,
+///     });
+/// }
+/// ```
+///
+/// ## Selector aliases
+/// Selectors used in multiple fields can be named in a `selectors { .. }` block or with `selector NAME = "..";`,
+/// and referred to by the name instead of the literal string in the structures in the same macro invocation.
+/// Wherever a selector is written, literal strings and aliases can be concatenated with `+`, like `ROW + " td.price"`.
+/// The selectors are validated at compile time where they are defined.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     selectors {
+///         ROW = ".results > li",
+///         TITLE = ROW + " h3 a",
+///     }
+///     selector PRICE = ROW + " .price";
+///
+///     #[derive(Debug, PartialEq)]
+///     SearchResults {
+///         titles: Vec<String> = (text of TITLE, collect),
+///         urls: Vec<String> = (attr["href"] of TITLE, collect),
+///         prices: Vec<usize> = (text of PRICE, collect),
+///         has_results: bool = (presence of ROW),
+///         sponsored: bool = (presence of ROW + ".sponsored"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <ul class="results">
+///             <li><h3><a href="/foo">Foo</a></h3><span class="price">100</span></li>
+///             <li><h3><a href="/bar">Bar</a></h3><span class="price">200</span></li>
+///         </ul>
+///     "#;
+///     let results = SearchResults::extract_from_str(input).unwrap();
+///     assert_eq!(results, SearchResults {
+///         titles: vec!["Foo".to_owned(), "Bar".to_owned()],
+///         urls: vec!["/foo".to_owned(), "/bar".to_owned()],
+///         prices: vec![100, 200],
+///         has_results: true,
+///         sponsored: false,
+///     });
+/// }
+/// ```
+///
 /// ## Extractor part of field definitions
 /// The extractor part of field definitions specifies how to extract data from HTML.
-/// Extractor consists of [Target](#target-specifier), [Capture](#capture-specifier), [Collector](#collector-specifier) and [Parser](#parser-specifier) specifier.
+/// Extractor consists of [Target](#target-specifier), [Transform](#transform-specifier), [Capture](#capture-specifier), [Collector](#collector-specifier), [Parser](#parser-specifier), [Map](#map-specifier), [Validation](#validation-specifier) and [Page](#page-specifier) specifier.
 ///
-/// The order of specifiers does not matter. If the same specifier is written multiple times, the one given later applies.
+/// The order of specifiers does not matter, except that transform specifiers are applied in the order they are written.
+/// If the same specifier is written multiple times, the one given later applies.
 /// ### Target specifier
 /// Target specifier specifies a selector to select an element (or elements) and what of the selected element is extracted.
 ///
 /// If the specified selector is invalid, it will be a compile error.  
-/// If `text of ..` or `attr[..] of ..` is used, the type of field must implement [`FromStr`](std::str::FromStr).  
-/// If `elem of ..` is used, the type of field must implement [`HtmlExtractor`].  
+/// If `text of ..`, `attr[..] of ..` or `style[..] of ..` is used, the type of field must implement [`FromStr`](std::str::FromStr),
+/// or be `Arc<str>`, `Rc<str>` or `Box<str>`, including through type aliases.  
+/// If `elem of ..` is used, the type of field must implement [`HtmlExtractor`], or be `Arc<T>`, `Rc<T>` or `Box<T>` of such a type `T`.  
+/// `Box<T>` makes recursive structures possible.  
+/// To keep the element and extract data from it later, use [`OwnedFragment`].  
+/// `shadow of ..` is the same as `elem of ..`, but the data is extracted from the declarative shadow root
+/// (`<template shadowrootmode="..">`) of the matched element, which is found with [`shadow_root`].
+/// Selectors cannot match across the boundary of a shadow root, so use nested structures to extract data from them.  
 /// If `text of ..` is used, leading and trailing whitespace removed from the extracted string.  
+/// `own_text of ..` is the same as `text of ..`, but extracts the text nodes that are direct children of the element,
+/// concatenated, so the text in the child elements is skipped.  
+/// `text_all of ..` extracts all the text in the element including its descendants, like `textContent` of DOM,
+/// with every run of whitespace collapsed into a single space and leading and trailing whitespace removed.  
+/// `texts of ..` extracts all the text nodes in the element, including its descendants, as a collection like `Vec<String>`,
+/// each trimmed and parsed into the items, without the empty ones.
+/// It is useful for the lines separated with `<br>`, whose number is not known in advance.  
+/// `next_text of ..` extracts the text following the element up to the next element, concatenated and trimmed,
+/// like the value after a label in `<b>Price:</b> $100<br>`.  
+/// If `rendered_text of ..` is used, the contents of the element are rendered into readable text with line breaks
+/// for `<br>`, `<p>`, `<li>` and the other blocks, as [`render::html_to_text`] does.  
+/// `inner_markdown of ..` converts the contents of the element into Markdown, as `render::html_to_markdown` does.
+/// It is available only when the `markdown` feature is enabled.  
+/// `sanitized_html of ..` extracts inner HTML of the element cleaned by an HTML sanitizer, as `render::sanitized_html` does,
+/// so that rich text can be re-rendered safely. It is available only when the `sanitize` feature is enabled.  
+/// If `style[..] of ..` is used, the value of the property in the `style` attribute is extracted, as [`style::inline_style`] does.  
+/// If `attrs of ..` is used, all the attributes of the element are extracted as pairs of the name and the value,
+/// so the type of field must implement `FromIterator<(String, String)>`, like `HashMap<String, String>`.
+/// It is useful when the names of the attributes are not known in advance.
+/// `dataset of ..` is the same, but extracts only the `data-*` attributes, named without the `data-` prefix,
+/// like `user-id` for `data-user-id`. Unlike `dataset` of DOM, the names are not converted into camel case.
+/// `capture with ..`, transforms and `parse with ..` cannot be used with them.  
+/// If `attrs[..] of ..` is used with the names of attributes, like `attrs["href", "title"] of "a"`,
+/// the attributes are extracted into a tuple field, each parsed for its own field as `attr[..] of ..` is.
+/// It fails if any of the attributes is not found.  
+/// If `position of ..` is used, the index of the element among its sibling elements is extracted as `usize`,
+/// counting from zero, like the rank in a list without explicit numbers.
+/// To count the matched elements instead, use `collect enumerate`.
+/// `capture with ..`, transforms and `parse with ..` cannot be used with it, too.  
 /// If `presence of ..` is used, the type must be `bool` and any other specifier cannot be used,
 /// ```
 /// use html_extractor::{html_extractor, HtmlExtractor};
+/// use std::collections::BTreeMap;
 /// html_extractor! {
 ///     #[derive(Debug, PartialEq)]
 ///     Foo {
@@ -147,12 +627,30 @@ pub mod error;
 ///         foo: usize = (text of "#foo"),
 ///         // extracts the third text node in the element that first matched the selector "#bar"
 ///         bar: usize = (text[2] of "#bar"),
+///         // extracts the text directly in the element that first matched the selector "#fred", without the text in `<small>`
+///         fred: String = (own_text of "#fred"),
+///         // extracts all the text in the element that first matched the selector "#plugh"
+///         plugh: String = (text_all of "#plugh"),
+///         // extracts the text after the element that first matched the selector "#xyzzy"
+///         xyzzy: String = (next_text of "#xyzzy"),
+///         // extracts the text nodes in the element that first matched the selector "#bar"
+///         bar_lines: Vec<String> = (texts of "#bar"),
 ///         // extracts attribute "data-baz" in the element that first matched the selector "#baz"
 ///         baz: usize = (attr["data-baz"] of "#baz"),
+///         // extracts all the attributes of the element that first matched the selector "#waldo"
+///         waldo: BTreeMap<String, String> = (attrs of "#waldo"),
+///         // extracts attributes "type" and "checked" of the element that first matched the selector "#waldo"
+///         (waldo_type: String, waldo_checked: String) = (attrs["type", "checked"] of "#waldo"),
+///         // extracts the `data-*` attributes of the element that first matched the selector "#baz"
+///         data: BTreeMap<String, String> = (dataset of "#baz"),
+///         // extracts the index of the element that first matched the selector "#thud" among its siblings
+///         thud: usize = (position of "#thud"),
 ///         // extracts an element that first matched the selector "#qux" and parse it with `HtmlExtractor::extract()`
 ///         qux: Qux = (elem of "#qux"),
 ///         // extracts inner HTML of the element that first matched the selector "#grault",
 ///         grault: String = (inner_html of "#grault"),
+///         // renders the element that first matched the selector "#grault" into text
+///         grault_text: String = (rendered_text of "#grault"),
 ///         // stores if the elements that matches the selector "#garply" exist.
 ///         garply: bool = (presence of "#garply"),
 ///     }
@@ -166,7 +664,18 @@ pub mod error;
 ///     let input = r#"
 ///         <div id="foo">1</div>
 ///         <div id="bar">ignore first<br>ignore second<br>2</div>
+///         <div id="fred">$<small>USD</small>100</div>
+///         <div id="plugh">
+///             <b>Hello</b>,
+///             <i>world</i>!
+///         </div>
+///         <p><b id="xyzzy">Label:</b> value<br>ignored</p>
 ///         <div id="baz" data-baz="3"></div>
+///         <input id="waldo" type="checkbox" checked>
+///         <ol>
+///             <li>first</li>
+///             <li id="thud">second</li>
+///         </ol>
 ///         <div id="qux">
 ///             <div id="corge">4</div>
 ///         </div>
@@ -178,13 +687,236 @@ pub mod error;
 ///     assert_eq!(foo, Foo {
 ///         foo: 1,
 ///         bar: 2,
+///         fred: "$100".to_owned(),
+///         plugh: "Hello, world!".to_owned(),
+///         xyzzy: "value".to_owned(),
+///         bar_lines: vec!["ignore first".to_owned(), "ignore second".to_owned(), "2".to_owned()],
 ///         baz: 3,
+///         waldo: vec![("checked", ""), ("id", "waldo"), ("type", "checkbox")]
+///             .into_iter()
+///             .map(|(name, value)| (name.to_owned(), value.to_owned()))
+///             .collect(),
+///         waldo_type: "checkbox".to_owned(),
+///         waldo_checked: "".to_owned(),
+///         data: vec![("baz".to_owned(), "3".to_owned())].into_iter().collect(),
+///         thud: 1,
 ///         qux: Qux { corge: 4 },
 ///         grault: "inner<br>html".to_owned(),
+///         grault_text: "inner\nhtml".to_owned(),
 ///         garply: false,
 ///     });
 /// }
 /// ```
+///
+/// An index in `[..]` after the selector picks the matched element at the index instead of the first one,
+/// counting from zero in document order, like `text of "#list li"[2]`.
+/// Unlike `:nth-child(..)`, it counts the matched elements across different parents.
+/// `[last]` picks the last matched element, like the newest comment or the last page of pagination.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         third: String = (text of "li"[2]),
+///         missing: Option<String> = (text of "li"[5], optional),
+///         last: String = (text of "li"[last]),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"<ul><li>a</li><li>b</li></ul><ul><li>c</li></ul>"#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo, Foo { third: "c".to_owned(), missing: None, last: "c".to_owned() });
+/// }
+/// ```
+///
+/// `parent of` before the selector moves from the matched elements to their parent elements,
+/// like `attr["id"] of parent of ".price"`, which selects an element by its distinctive child.
+/// `parent[n] of` moves `n` levels up, so `parent[2] of` is the same as `parent of parent of`.
+/// `closest "sel" of` moves to the nearest element that matches `"sel"`, starting from the matched element itself,
+/// like `Element.closest()` of DOM, and skips the matched elements without such an element.
+/// `next of` moves to the next sibling element, skipping the text in between, and skips the matched elements without one.
+/// The position in `[..]` picks one of the matched elements before moving.
+/// An element can be moved outside the element being extracted from, and the same parent is yielded for each matched child.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         sale: String = (attr["id"] of parent of ".sale"),
+///         list: String = (attr["id"] of parent[2] of ".sale"),
+///         items: Vec<String> = (attr["id"] of parent of "li > span", collect),
+///         closest: String = (attr["id"] of closest "ul" of ".sale"),
+///         next: String = (attr["id"] of next of "#apple"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <ul id="fruits">
+///             <li id="apple"><span>$1</span></li>
+///             <li id="banana"><span class="sale">$2</span></li>
+///         </ul>
+///     "#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo, Foo {
+///         sale: "banana".to_owned(),
+///         list: "fruits".to_owned(),
+///         closest: "fruits".to_owned(),
+///         next: "banana".to_owned(),
+///         items: vec!["apple".to_owned(), "banana".to_owned()],
+///     });
+/// }
+/// ```
+///
+/// `self` in place of the selector selects the element being extracted from, instead of its descendants,
+/// like `attr["data-id"] of self` in a structure extracted with `elem of ".card"`.
+/// It is the same as the selector `":scope"`, and cannot be combined with other selectors with `+` or `and`.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         cards: Vec<Card> = (elem of ".card", collect),
+///     }
+///     #[derive(Debug, PartialEq)]
+///     Card {
+///         id: usize = (attr["data-id"] of self),
+///         title: String = (text of "h2"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"<div class="card" data-id="1"><h2>foo</h2></div><div class="card" data-id="2"><h2>bar</h2></div>"#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo.cards[1], Card { id: 2, title: "bar".to_owned() });
+/// }
+/// ```
+///
+/// Targets can be chained with `else`, like `text of "#new" else attr["title"] of "#old"`.
+/// The first target whose selector matches any element is used, and the other specifiers apply to it.
+/// If none matches, the last one is used, so that the collector decides what to do with no element.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         (price: usize,) = (text of ".price-new" else text of ".price-old", capture with r"(\d+)"),
+///         title: Option<String> = (text of "h1" else attr["content"] of r#"meta[property="og:title"]"#, optional),
+///     }
+/// }
+///
+/// fn main() {
+///     let old_layout = r#"<meta property="og:title" content="foo"><span class="price-old">$100</span>"#;
+///     let new_layout = r#"<h1>bar</h1><span class="price-new">$200</span><span class="price-old">$100</span>"#;
+///     assert_eq!(
+///         Foo::extract_from_str(old_layout).unwrap(),
+///         Foo { price: 100, title: Some("foo".to_owned()) },
+///     );
+///     assert_eq!(
+///         Foo::extract_from_str(new_layout).unwrap(),
+///         Foo { price: 200, title: Some("bar".to_owned()) },
+///     );
+///     assert_eq!(Foo::extract_from_str(r#"<span class="price-old">1</span>"#).unwrap().title, None);
+/// }
+/// ```
+///
+/// Selectors can be joined with `and`, like `text of ".old-item" and ".new-item"`.
+/// The target selects the elements that match any of them, in document order,
+/// which is the same as the selector list `".old-item, .new-item"`.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         items: Vec<String> = (text of ".old-item" and ".new-item", collect),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"<li class="new-item">a</li><li class="old-item">b</li><li class="new-item">c</li>"#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo.items, vec!["a", "b", "c"]);
+/// }
+/// ```
+///
+/// The selector of a target can also be a macro call like `format!(..)`, which is evaluated at extraction time
+/// and can use the [earlier fields](#defining-fields-in-structures) and the [context](#struct-options).
+/// Such a selector is not checked at compile time, and the extraction fails if it is invalid.
+/// It cannot be used with `#![incremental]` or `#![provenance]`.
+/// ```
+/// use html_extractor::{html_extractor, ExtractWithContext};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Row {
+///         #![context(row: usize)]
+///         selected: usize = (text of "#selected"),
+///         name: String = (text of format!("#row-{} .name", row)),
+///         selected_name: String = (text of format!("#row-{} .name", field_selected)),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <p id="selected">2</p>
+///         <div id="row-1"><span class="name">foo</span></div>
+///         <div id="row-2"><span class="name">bar</span></div>
+///     "#;
+///     let row = Row::extract_from_str_with_context(input, &1usize).unwrap();
+///     assert_eq!(row, Row { selected: 2, name: "foo".to_owned(), selected_name: "bar".to_owned() });
+/// }
+/// ```
+/// ### Transform specifier
+/// Transform specifiers specify how to clean up the string that is extracted with target specifier before capturing and parsing it.
+/// Multiple transform specifiers can be chained, and they are applied in the order they are written.
+///
+/// * `replace "from" with "to"` replaces all occurrences of `"from"` with `"to"`.
+/// * `normalize_whitespace` collapses every run of whitespace into a single space.
+/// * `lowercase` / `uppercase` converts the string to lowercase / uppercase.
+/// * `decode_entities` decodes the character references like `&amp;` and `&#x2019;` with [`entities::decode`],
+///   which is useful for `inner_html of ..`, since the text of the other targets is already decoded.
+///
+/// They cannot be used with target specifier `elem of ..`.
+///
+/// The leading and trailing whitespace is removed from the text of `text of ..`, `own_text of ..`, `texts of ..`,
+/// `next_text of ..` and `inner_html of ..` before the transforms, unless `no_trim` is given.
+/// It keeps the whitespace that is significant, like in `<pre>`, and `texts of ..` keeps the text nodes of only whitespace.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         // removes the thousands separators, and then parses
+///         foo: usize = (text of "#foo", replace "," with ""),
+///         // collapses the whitespace, converts to lowercase, and then captures
+///         (bar: String,) = (text of "#bar", normalize_whitespace, lowercase, capture with "^name: (.*)$"),
+///         // keeps the indentation
+///         baz: String = (text of "#baz", no_trim),
+///         // decodes `&amp;` in the markup
+///         qux: String = (inner_html of "#qux", decode_entities),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <div id="foo">1,000,000</div>
+///         <div id="bar">
+///             NAME:   John
+///             Smith
+///         </div>
+///         <pre id="baz">  fn main() {}</pre>
+///         <p id="qux">Tom <b>&amp;</b> Jerry</p>
+///     "#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo, Foo {
+///         foo: 1000000,
+///         bar: "john smith".to_owned(),
+///         baz: "  fn main() {}".to_owned(),
+///         qux: "Tom <b>&</b> Jerry".to_owned(),
+///     });
+/// }
+/// ```
+///
 /// ### Capture specifier
 /// Capture specifier specifies an regex that is used to capture desired data from the string that is extracted with target specifier.
 ///
@@ -196,6 +928,12 @@ pub mod error;
 ///
 /// If it is used without [collect specifier](#collect-specifier), the field must be a [tuple field](#defining-fields-in-structures).
 /// If it is used with [collect specifier](#collect-specifier), the type of the field must be [`FromIterator`](std::iter::FromIterator) of tuple.
+///
+/// The groups are mapped to the tuple elements by position, even if they are named.
+/// With `capture by name with "regex"`, the named groups like `(?P<price>..)` are mapped to the fields of the same names instead,
+/// and the other groups are ignored.
+/// Then every field must have the group of its name, and a field that is not a tuple field can take the group of its name, too.
+/// `capture by name with ..` cannot be used with collect specifier.
 /// ```
 /// use html_extractor::{html_extractor, HtmlExtractor};
 /// html_extractor! {
@@ -229,12 +967,44 @@ pub mod error;
 ///     });
 /// }
 /// ```
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         // the groups are mapped to the fields with the same names, regardless of the order
+///         (currency: String, price: usize) = (text of "#price", capture by name with r"(?P<price>\d+) (?P<currency>\w+)"),
+///         // a single field takes the group of its name
+///         stock: usize = (text of "#stock", capture by name with r"(in stock|left): (?P<stock>\d+)"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <div id="price">100 USD</div>
+///         <div id="stock">left: 3</div>
+///     "#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo, Foo {
+///         currency: "USD".to_owned(),
+///         price: 100,
+///         stock: 3,
+///     });
+/// }
+/// ```
 ///
 /// ### Collector specifier
 /// Collector specifier specifies how to collect HTML elements.  
 /// The default collector is "first", which collects only the first matched element.  
 /// The "collect" collector collects all the element into the type that implements [`FromIterator`](std::iter::FromIterator).  
-/// The "optional" collector collects the first element if it exists. If not, it emits `None`.
+/// It also collects into an array `[T; N]`, and fails if the number of the items is not `N`.  
+/// The "optional" collector collects the first element if it exists. If not, it emits `None`.  
+/// The "or default" collector collects the first element if it exists. If not, it emits `Default::default()`.
+/// "default with expr" emits the expression instead.  
+/// The "join with "separator"" collector joins the strings of all the matched elements with the separator,
+/// and parses the joined string. It can also be written after "collect skip n" or "collect limit n".  
+/// The "unique" collector collects the only matched element. It fails if no element or more than one element matches,
+/// which is useful when multiple matches mean that the selector is wrong.
 /// ```
 /// use html_extractor::{html_extractor, HtmlExtractor};
 /// html_extractor! {
@@ -255,6 +1025,13 @@ pub mod error;
 ///
 ///         // optionally extracts the first text node in the first element that matches the selector ".grault".
 ///         grault: Option<usize> = (text of ".grault", optional),
+///
+///         // falls back to `Default::default()` or the expression if no element matches the selector.
+///         garply: usize = (text of ".garply", or default),
+///         fred: String = (attr["title"] of ".fred", default with "untitled".to_owned()),
+///
+///         // extracts the first text node in the only element that matches the selector "#waldo".
+///         waldo: usize = (text of "#waldo", unique),
 ///     }
 ///     #[derive(Debug, PartialEq)]
 ///     Bar {
@@ -278,6 +1055,8 @@ pub mod error;
 ///         <div class="baz-qux-corge">baz=4, qux=5, corge=6</div>
 ///         <div class="baz-qux-corge">baz=7, qux=8, corge=9</div>
 ///         <div class="baz-qux-corge">baz=10, qux=11, corge=12</div>
+///
+///         <div id="waldo">1</div>
 ///     "#;
 ///     let foo = Foo::extract_from_str(input).unwrap();
 ///     assert_eq!(foo, Foo {
@@ -290,13 +1069,97 @@ pub mod error;
 ///         ],
 ///         baz_qux_corge: vec![(1, 2, 3), (4, 5, 6), (7, 8, 9), (10, 11, 12)],
 ///         grault: None,
+///         garply: 0,
+///         fred: "untitled".to_owned(),
+///         waldo: 1,
 ///     });
+///
+///     html_extractor! {
+///         Ambiguous {
+///             foo: usize = (text of ".foo", unique),
+///         }
+///     }
+///     assert!(Ambiguous::extract_from_str(input).is_err());
+/// }
+/// ```
+///
+/// With "collect", "filter with predicate" drops the items for which the predicate returns `false`.
+/// The predicate must be `Fn(&T) -> bool`, where `T` is the type of the items.
+/// "collect skip n" skips the first `n` matched elements, and "collect limit n" extracts at most `n` elements.
+/// They count the matched elements, before the items are filtered.
+/// "collect at least n" and "collect at most n" fail the extraction if fewer or more elements match,
+/// which detects the changes of the layout that would silently extract nothing.
+/// They count all the matched elements, before the elements are skipped or limited with "skip" and "limit".
+/// "collect enumerate" pairs each item with its index, like `(usize, T)`.
+/// The index counts the elements after skipped ones, and filtered items don't shift the indices of the rest.
+/// "key with (..)" pairs each item with a key, like `(K, T)`, so that the items can be collected into a map.
+/// The key is extracted with the extractor in the parentheses from the matched element,
+/// in which `self` selects the matched element itself.
+/// With `elem of ..`, "value with (..)" extracts the value in the same way, instead of with [`HtmlExtractor::extract`].
+/// If the keys are duplicated, the map keeps the last item as [`FromIterator`](std::iter::FromIterator) does.
+/// "group by (..)" extracts the key in the same way, and groups the values with the same key instead,
+/// into a map of collections like `HashMap<K, Vec<T>>` or `BTreeMap<K, BTreeSet<T>>`.
+/// "zip with (..)" pairs each item with the item at the same position of the extractor in the parentheses, like `(T, U)`,
+/// which collects from the same element as the field. It fails if the numbers of the items are different.
+/// "fold with function" accumulates the items into the field one by one, without collecting them into a `Vec`.
+/// The function must be `FnMut(A, T) -> A`, where `A` is the type of the field,
+/// and the accumulation starts from `Default::default()`, or the expression given with "fold from expr with function".
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// use std::collections::HashMap;
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         tags: Vec<String> = (text of ".tag", collect, filter with |t: &String| !t.is_empty()),
+///         // skips the header row, and extracts at most 2 rows
+///         rows: Vec<u32> = (text of "tr > td", collect skip 1 limit 2 at least 1),
+///         users: HashMap<u32, String> = (
+///             elem of ".user",
+///             collect,
+///             key with (attr["data-id"] of self),
+///             value with (text of ".name"),
+///         ),
+///         roles: HashMap<String, Vec<String>> = (
+///             elem of ".user",
+///             collect,
+///             group by (attr["data-role"] of self),
+///             value with (text of ".name"),
+///         ),
+///         specs: Vec<(String, String)> = (text of "dt", collect, zip with (text of "dd")),
+///         total: u32 = (text of "tr > td", collect skip 1, fold with |sum, n: u32| sum + n),
+///         description: String = (text of "dd", join with "\n"),
+///         product: u32 = (text of "tr > td", collect skip 1, fold from 1 with |acc, n: u32| acc * n),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <p class="tag">a</p><p class="tag"> </p><p class="tag">b</p>
+///         <div class="user" data-id="1" data-role="admin"><span class="name">foo</span></div>
+///         <div class="user" data-id="2" data-role="admin"><span class="name">bar</span></div>
+///         <dl><dt>color</dt><dd>red</dd><dt>size</dt><dd>L</dd></dl>
+///         <table>
+///             <tr><td>count</td></tr>
+///             <tr><td>1</td></tr>
+///             <tr><td>2</td></tr>
+///             <tr><td>3</td></tr>
+///         </table>
+///     "#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo.tags, vec!["a", "b"]);
+///     assert_eq!(foo.rows, vec![1, 2]);
+///     assert_eq!(foo.users[&2], "bar");
+///     assert_eq!(foo.roles["admin"], vec!["foo", "bar"]);
+///     assert_eq!(foo.specs[1], ("size".to_owned(), "L".to_owned()));
+///     assert_eq!((foo.total, foo.product), (6, 6));
+///     assert_eq!(foo.description, "red\nL");
 /// }
 /// ```
 /// ### Parser specifier
 /// Parser specifier specifies the parser used to parse the extracted string.  
-/// The default parser is [`::std::str::FromStr::from_str`].  
-/// The parser must be `Fn(&str) -> Result<_, T> where T: std::fmt::Debug`
+/// The default parser is [`::std::str::FromStr::from_str`], or [`From<&str>`](From) for `Arc<str>`, `Rc<str>` and `Box<str>`.  
+/// The parser must be `Fn(&str) -> Result<_, T> where T: std::fmt::Debug`  
+/// Some commonly used parsers are provided in the [`parsers`] module.
 /// ```
 /// use html_extractor::{html_extractor, HtmlExtractor};
 /// html_extractor! {
@@ -321,11 +1184,251 @@ pub mod error;
 /// }
 /// ```
 ///
+/// ### Map specifier
+/// Map specifier `map with function` converts each parsed value, before the values are collected.
+/// The function must be `Fn(T) -> U`, where `T` is the type to parse into and `U` is the type of the field
+/// (or of the items with `collect`), so `T` is usually given by the type of the argument.
+/// With a capture specifier, the function takes and returns the tuple of the captured values.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         name: String = (text of "#name", map with |s: String| s.to_lowercase()),
+///         cents: Vec<u64> = (text of "li", collect, map with |price: f64| (price * 100.0).round() as u64),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"<p id="name">FOO</p><li>1.5</li><li>0.25</li>"#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo, Foo { name: "foo".to_owned(), cents: vec![150, 25] });
+/// }
+/// ```
+///
+/// ### Validation specifier
+/// Validation specifier `validate with predicate` checks the value of the field after it is extracted and collected.
+/// The predicate must be `Fn(&T) -> bool`, where `T` is the type of the field, or the tuple of the types for tuple fields.
+/// If it returns `false`, the extraction fails with [`Error::InvalidInput`] that mentions the field.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         name: String = (text of "#name", validate with |s: &String| !s.is_empty()),
+///         age: u8 = (text of "#age", validate with |age| *age < 150),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"<p id="name">foo</p><p id="age">20</p>"#;
+///     assert_eq!(Foo::extract_from_str(input).unwrap(), Foo { name: "foo".to_owned(), age: 20 });
+///
+///     let err = Foo::extract_from_str(r#"<p id="name">foo</p><p id="age">200</p>"#).unwrap_err();
+///     assert!(err.to_string().contains("field `age` in struct `Foo`, the validation failed"));
+/// }
+/// ```
+///
+/// ### Page specifier
+/// Page specifier `from page "name"` specifies that the field is extracted from another document.
+///
+/// If any field of a structure has page specifier, an associated function `extract_multi(pages: &[(&str, &str)])` is generated for the structure.
+/// It takes pairs of a page name and an HTML string. The page named `main` is the main page, from which the fields without page specifier are extracted,
+/// and the other pages must be named as in the page specifiers of the fields. An unknown, duplicated or missing page name fails the extraction.
+/// `extract_multi_from_elements(pages: &[(&str, ElementRef)], options: &ExtractOptions)` takes the parsed pages and [`ExtractOptions`](options::ExtractOptions) instead.
+///
+/// Such a structure doesn't implement [`HtmlExtractor`], because the fields with page specifier cannot be extracted from a single element.
+/// Page specifier cannot be used with the root selector, `context`, `incremental` or `provenance`.
+/// ```
+/// use html_extractor::html_extractor;
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         // extracted from the main page
+///         name: String = (text of ".name"),
+///         // extracted from the page named "details"
+///         description: String = (text of "#description", from page "details"),
+///     }
+/// }
+///
+/// fn main() {
+///     let listing = r#"
+///         <div class="name">foo</div>
+///     "#;
+///     let details = r#"
+///         <p id="description">the description of foo</p>
+///     "#;
+///     let foo = Foo::extract_multi(&[("main", listing), ("details", details)]).unwrap();
+///     assert_eq!(foo, Foo {
+///         name: "foo".to_owned(),
+///         description: "the description of foo".to_owned(),
+///     });
+/// }
+/// ```
+///
+/// ### Skipped fields
+/// The extractor `(skip)` defines a field that is not extracted from HTML, which is initialized with [`Default::default()`],
+/// or with the expression given as `(skip, init with expr)`.
+/// [`extract_into`](HtmlExtractor::extract_into) and [`reextract`](incremental::IncrementalExtractor::reextract) keep the value of skipped fields.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         name: String = (text of ".name"),
+///         visits: usize = (skip),
+///         source: &'static str = (skip, init with "example.com"),
+///     }
+/// }
+///
+/// fn main() {
+///     let foo = Foo::extract_from_str(r#"<div class="name">foo</div>"#).unwrap();
+///     assert_eq!(foo, Foo {
+///         name: "foo".to_owned(),
+///         visits: 0,
+///         source: "example.com",
+///     });
+/// }
+/// ```
+///
 /// # Usage of the generated structures
 /// The generated structures implement trait [`HtmlExtractor`].
 /// See the document of the trait.
 pub use html_extractor_macros::html_extractor;
 
+/// Implements [`HtmlExtractor`] for a structure defined in plain Rust.
+///
+/// It is an alternative to [`html_extractor!`](macro.html_extractor.html) that works well with rustfmt, IDEs and other derives.
+/// Each field is annotated with `#[extract(..)]`, whose arguments correspond to the specifiers of the extractor part
+/// of `html_extractor!`:
+///
+/// | `#[extract(..)]`                                 | `html_extractor!`             |
+/// |---------------------------------------------------|-------------------------------|
+/// | `text = "sel"`                                    | `text of "sel"`               |
+/// | `text = "sel", nth = 2`                           | `text[2] of "sel"`            |
+/// | `attr = "href", selector = "sel"`                 | `attr["href"] of "sel"`       |
+/// | `style = "color", selector = "sel"`               | `style["color"] of "sel"`     |
+/// | `elem = "sel"`                                    | `elem of "sel"`               |
+/// | `shadow = "sel"`                                  | `shadow of "sel"`             |
+/// | `attrs = "sel"`                                   | `attrs of "sel"`              |
+/// | `attrs = ["a", "b"], selector = "sel"`            | `attrs["a", "b"] of "sel"`    |
+/// | `dataset = "sel"`                                 | `dataset of "sel"`            |
+/// | `own_text = "sel"`                                | `own_text of "sel"`           |
+/// | `position = "sel"`                                | `position of "sel"`           |
+/// | `text_all = "sel"`                                | `text_all of "sel"`           |
+/// | `texts = "sel"`                                   | `texts of "sel"`              |
+/// | `next_text = "sel"`                               | `next_text of "sel"`          |
+/// | `inner_html = "sel"`                              | `inner_html of "sel"`         |
+/// | `inner_markdown = "sel"`                          | `inner_markdown of "sel"`     |
+/// | `sanitized_html = "sel"`                          | `sanitized_html of "sel"`     |
+/// | `rendered_text = "sel"`                           | `rendered_text of "sel"`      |
+/// | `presence = "sel"`                                | `presence of "sel"`           |
+/// | `replace("from", "to")`                           | `replace "from" with "to"`    |
+/// | `normalize_whitespace`, `lowercase`, `uppercase`  | the same                      |
+/// | `no_trim`, `decode_entities`                      | the same                      |
+/// | `capture = "regex"`                               | `capture with "regex"`        |
+/// | `capture_by_name = "regex"`                       | `capture by name with "regex"` |
+/// | `collect`, `optional`, `unique`                   | the same                      |
+/// | `default`                                         | `or default`                  |
+/// | `default = expr`                                  | `default with expr`           |
+/// | `parse = path::to::parser`                        | `parse with path::to::parser` |
+/// | `map = function`                                  | `map with function`           |
+/// | `filter = predicate`                              | `filter with predicate`       |
+/// | `collect(skip = 1, limit = 10)`                   | `collect skip 1 limit 10`     |
+/// | `collect(enumerate)`                              | `collect enumerate`           |
+/// | `collect(at_least = 1, at_most = 50)`             | `collect at least 1 at most 50` |
+/// | `key(text of "..")`                               | `key with (text of "..")`     |
+/// | `value(text of "..")`                             | `value with (text of "..")`   |
+/// | `group(text of "..")`                             | `group by (text of "..")`     |
+/// | `zip(text of "..")`                               | `zip with (text of "..")`     |
+/// | `fold = function`                                 | `fold with function`          |
+/// | `fold = function, fold_from = expr`               | `fold from expr with function`|
+/// | `join = "separator"`                              | `join with "separator"`       |
+/// | `validate = predicate`                            | `validate with predicate`     |
+/// | `from_page = "name"`                              | `from page "name"`            |
+/// | `skip`                                            | `skip`                        |
+/// | `skip, init = expr`                               | `skip, init with expr`        |
+///
+/// With `capture = ".."`, the first capture group is parsed into the field, and with `capture_by_name = ".."`, the group named after the field.
+/// The struct options `after_extract = ..`, `validate = ..`, `incremental`, `provenance`, `no_trim` and `context(name: Type)`, and the
+/// [root selector](html_extractor#root-selector) `root = ".."` can be given with `#[extract(..)]` on the structure.
+/// Tuple fields, fallback targets with `else`, scope blocks, mixins, selector aliases and getters are not supported.
+/// ```
+/// use html_extractor::HtmlExtractor;
+///
+/// #[derive(Debug, PartialEq, HtmlExtractor)]
+/// #[extract(validate = |foo: &Foo| if foo.links.is_empty() { Err("no links") } else { Ok(()) })]
+/// struct Foo {
+///     #[extract(text = "#foo")]
+///     foo: usize,
+///     #[extract(attr = "href", selector = "a.link", collect)]
+///     links: Vec<String>,
+///     #[extract(text = "#price", replace(",", ""), capture = r"^\$(\d+)$")]
+///     price: usize,
+///     #[extract(elem = "#bar", optional)]
+///     bar: Option<Bar>,
+/// }
+///
+/// #[derive(Debug, PartialEq, HtmlExtractor)]
+/// struct Bar {
+///     #[extract(presence = ".baz")]
+///     baz: bool,
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <div id="foo">1</div>
+///         <a class="link" href="/a">a</a>
+///         <a class="link" href="/b">b</a>
+///         <div id="price">$1,000</div>
+///         <div id="bar"><span class="baz"></span></div>
+///     "#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo, Foo {
+///         foo: 1,
+///         links: vec!["/a".to_owned(), "/b".to_owned()],
+///         price: 1000,
+///         bar: Some(Bar { baz: true }),
+///     });
+/// }
+/// ```
+pub use html_extractor_macros::HtmlExtractor;
+
+/// Implements [`HtmlExtractor`] for a structure with `#[extract(..)]` annotations, like [`derive@HtmlExtractor`].
+///
+/// It is for structures that cannot take the derive, such as the ones generated by other macros.
+/// The `#[extract(..)]` attributes are removed, and the structure is emitted as is otherwise.
+/// The struct options and the root selector can be given as the arguments.
+/// ```
+/// use html_extractor::{extractor, HtmlExtractor};
+///
+/// macro_rules! record {
+///     ($($field:tt)*) => {
+///         #[extractor(root = "tr")]
+///         #[derive(Debug, PartialEq)]
+///         struct Record { $($field)* }
+///     };
+/// }
+/// record! {
+///     #[extract(text = "td.name")]
+///     name: String,
+///     #[extract(text = "td.age")]
+///     age: u8,
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <table>
+///             <tr><td class="name">Alice</td><td class="age">30</td></tr>
+///             <tr><td class="name">Bob</td><td class="age">25</td></tr>
+///         </table>
+///     "#;
+///     let records = Record::extract_all_from_str(input).unwrap();
+///     assert_eq!(records[1], Record { name: "Bob".to_owned(), age: 25 });
+/// }
+/// ```
+pub use html_extractor_macros::extractor;
+
 /// A trait for extracting data from HTML documents.
 ///
 /// It is recommended to use [`html_extractor!`](macro.html_extractor.html) to implement `HtmlExtractor`.
@@ -335,11 +1438,230 @@ where
 {
     /// Extracts data from [`scraper::element_ref::ElementRef`].
     fn extract(elem: &scraper::ElementRef) -> Result<Self, Error>;
+    /// Extracts data from [`scraper::element_ref::ElementRef`] within the limits of `options`.
+    ///
+    /// Fails with [`Error::Cancelled`] or [`Error::TimedOut`] when the limits are hit between fields or between
+    /// items of collected fields. The options are passed to the nested structures as an argument,
+    /// so they apply wherever the extraction runs.
+    ///
+    /// The default implementation, used by extractors not implemented with [`html_extractor!`](macro.html_extractor.html),
+    /// checks the options only before calling [`extract`](HtmlExtractor::extract).
+    fn extract_with_options(
+        elem: &scraper::ElementRef,
+        options: &options::ExtractOptions,
+    ) -> Result<Self, Error> {
+        options.check()?;
+        HtmlExtractor::extract(elem)
+    }
+    /// Parses HTML string and extracts data from it within the limits of `options`.
+    ///
+    /// Parsing is not interrupted. See [`extract_with_options`](HtmlExtractor::extract_with_options).
+    fn extract_from_str_with_options(
+        html_str: &str,
+        options: &options::ExtractOptions,
+    ) -> Result<Self, Error> {
+        let html = scraper::Html::parse_document(html_str);
+        Self::extract_with_options(&html.root_element(), options)
+    }
     /// Parses HTML string and extracts data from it.
     fn extract_from_str(html_str: &str) -> Result<Self, Error> {
         let html = scraper::Html::parse_document(html_str);
         HtmlExtractor::extract(&html.root_element())
     }
+    /// Decodes HTML bytes, parses it and extracts data from it.
+    ///
+    /// With the `encoding` feature, the encoding is detected from the BOM or the `<meta>` charset declaration
+    /// in the first 1024 bytes, falling back to UTF-8. Without it, the bytes are decoded as UTF-8.
+    /// Invalid sequences are replaced with U+FFFD. Valid UTF-8 input is not copied.
+    fn extract_from_bytes(html_bytes: &[u8]) -> Result<Self, Error> {
+        Self::extract_from_str(&input::decode(html_bytes))
+    }
+    /// Extracts data from [`scraper::element_ref::ElementRef`] into `self`, overwriting the fields that are extracted successfully.
+    ///
+    /// With [`UpdatePolicy::KeepPrevious`], the fields that fail keep their values and are returned with the errors.
+    /// With [`UpdatePolicy::AllOrNothing`], `self` is not modified if any field fails.
+    /// `after_extract` and `validate` run on the updated structure, and if they fail, the overwritten fields are restored.
+    ///
+    /// The default implementation, used by extractors not implemented with [`html_extractor!`](macro.html_extractor.html),
+    /// ignores the policy and behaves as [`UpdatePolicy::AllOrNothing`]: it replaces the whole `self` only if the extraction succeeds.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, UpdatePolicy};
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Product {
+    ///         name: String = (text of "h1"),
+    ///         price: usize = (text of "#price"),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut product = Product::extract_from_str(r#"<h1>Coffee</h1><div id="price">100</div>"#).unwrap();
+    ///
+    ///     // the price is broken on the new page
+    ///     let html = scraper::Html::parse_document(r#"<h1>Coffee Beans</h1><div id="price">N/A</div>"#);
+    ///     let failures = product.extract_into(&html.root_element(), UpdatePolicy::KeepPrevious).unwrap();
+    ///     assert_eq!(product, Product { name: "Coffee Beans".to_owned(), price: 100 });
+    ///     assert_eq!(failures[0].field, "price");
+    /// }
+    /// ```
+    fn extract_into(
+        &mut self,
+        elem: &scraper::ElementRef,
+        _policy: UpdatePolicy,
+    ) -> Result<Vec<error::FieldError>, Error> {
+        *self = Self::extract(elem)?;
+        Ok(Vec::new())
+    }
+    /// Parses HTML string and extracts data from it into `self`. See [`extract_into`](HtmlExtractor::extract_into).
+    fn extract_into_from_str(
+        &mut self,
+        html_str: &str,
+        policy: UpdatePolicy,
+    ) -> Result<Vec<error::FieldError>, Error> {
+        let html = scraper::Html::parse_document(html_str);
+        self.extract_into(&html.root_element(), policy)
+    }
+    /// Parses HTML fragment and extracts data from it.
+    ///
+    /// Unlike [`extract_from_str`](HtmlExtractor::extract_from_str), the elements are not wrapped in `<head>` and `<body>`.
+    fn extract_from_fragment(html_str: &str) -> Result<Self, Error> {
+        let html = scraper::Html::parse_fragment(html_str);
+        HtmlExtractor::extract(&html.root_element())
+    }
+    /// Parses each HTML fragment and extracts data from it.
+    ///
+    /// A failure of one fragment doesn't affect the others.
+    fn extract_from_fragments(fragments: &[&str]) -> Vec<Result<Self, Error>> {
+        fragments
+            .iter()
+            .map(|fragment| Self::extract_from_fragment(fragment))
+            .collect()
+    }
+}
+
+/// How [`HtmlExtractor::extract_into`] handles the fields that fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpdatePolicy {
+    /// Keeps the previous values of the fields that fail and updates the others.
+    #[default]
+    KeepPrevious,
+    /// Fails without modifying anything if any field fails.
+    AllOrNothing,
+}
+
+/// Returns the declarative shadow root of `host`, or `None` if it has none.
+///
+/// The shadow root is the first `<template>` child of `host` with the `shadowrootmode` attribute
+/// (or the older `shadowroot` attribute). Selectors from the returned element match the content of the shadow root.
+/// This is what the [`shadow of ..` target](macro.html_extractor.html#target-specifier) extracts data from.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Page {
+///         cards: Vec<Card> = (shadow of "product-card", collect),
+///     }
+///     #[derive(Debug, PartialEq)]
+///     Card {
+///         name: String = (text of ".name"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <product-card>
+///             <template shadowrootmode="open"><p class="name">Coffee</p><slot></slot></template>
+///         </product-card>
+///         <product-card>
+///             <template shadowrootmode="closed"><p class="name">Tea</p></template>
+///         </product-card>
+///     "#;
+///     let page = Page::extract_from_str(input).unwrap();
+///     assert_eq!(page.cards, vec![
+///         Card { name: "Coffee".to_owned() },
+///         Card { name: "Tea".to_owned() },
+///     ]);
+/// }
+/// ```
+pub fn shadow_root<'a>(host: &scraper::ElementRef<'a>) -> Option<scraper::ElementRef<'a>> {
+    host.children()
+        .filter_map(scraper::ElementRef::wrap)
+        .find(|e| {
+            e.value().name() == "template"
+                && (e.value().attr("shadowrootmode").is_some()
+                    || e.value().attr("shadowroot").is_some())
+        })
+}
+
+/// Parses HTML documents and extracts data from them in parallel on the rayon thread pool.
+///
+/// The results are in the same order as `docs`, and a failure of one document doesn't affect the others.
+///
+/// This function is available only when the `rayon` feature is enabled.
+/// ```
+/// use html_extractor::{html_extractor, extract_batch_par};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         foo: usize = (text of "#foo"),
+///     }
+/// }
+///
+/// fn main() {
+///     let docs = vec![
+///         r#"<div id="foo">1</div>"#.to_owned(),
+///         r#"<div id="bar">2</div>"#.to_owned(),
+///     ];
+///     let results = extract_batch_par::<Foo, _>(&docs);
+///     assert_eq!(results[0].as_ref().unwrap(), &Foo { foo: 1 });
+///     assert!(results[1].is_err());
+/// }
+/// ```
+#[cfg(feature = "rayon")]
+pub fn extract_batch_par<T, S>(docs: &[S]) -> Vec<Result<T, Error>>
+where
+    T: HtmlExtractor + Send,
+    S: AsRef<str> + Sync,
+{
+    use rayon::prelude::*;
+    docs.par_iter()
+        .map(|doc| T::extract_from_str(doc.as_ref()))
+        .collect()
+}
+
+/// Memory-maps the file at `path` and extracts data from it as [`HtmlExtractor::extract_from_bytes`] does.
+///
+/// This avoids reading huge files into a buffer before parsing.
+/// Use [`HtmlExtractor::extract_from_bytes`] with [`std::fs::read`] for files that may change.
+///
+/// This function is available only when the `mmap` feature is enabled.
+/// ```no_run
+/// use html_extractor::{html_extractor, extract_from_file};
+/// html_extractor! {
+///     Foo {
+///         foo: usize = (text of "#foo"),
+///     }
+/// }
+///
+/// fn main() {
+///     // safety: nothing modifies the file while it is extracted
+///     let foo = unsafe { extract_from_file::<Foo, _>("page.html") }.unwrap();
+/// }
+/// ```
+///
+/// # Safety
+/// The file must not be modified or truncated, by this or any other process, until this function returns.
+/// Otherwise the mapped bytes can change while they are read, which is undefined behavior.
+#[cfg(feature = "mmap")]
+pub unsafe fn extract_from_file<T, P>(path: P) -> Result<T, Error>
+where
+    T: HtmlExtractor,
+    P: AsRef<std::path::Path>,
+{
+    let file = std::fs::File::open(path)?;
+    // safety: the caller guarantees that the file is not modified while it is mapped
+    let mmap = memmap2::Mmap::map(&file)?;
+    T::extract_from_bytes(&mmap)
 }
 
 #[cfg(test)]
@@ -0,0 +1,127 @@
+//! Options to bound the time spent on an extraction and to enrich the errors.
+//!
+//! See [`HtmlExtractor::extract_with_options`](crate::HtmlExtractor::extract_with_options).
+
+use crate::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Options for [`HtmlExtractor::extract_with_options`](crate::HtmlExtractor::extract_with_options).
+///
+/// The options are checked before each field is extracted and before each item of a collected field,
+/// including the fields of nested structures, to which the options are passed as an argument.
+/// ```
+/// use html_extractor::{html_extractor, Error, HtmlExtractor};
+/// use html_extractor::options::{CancellationToken, ExtractOptions};
+/// use std::time::Duration;
+/// html_extractor! {
+///     Foo {
+///         foo: Vec<usize> = (text of ".foo", collect),
+///     }
+/// }
+///
+/// fn main() {
+///     let token = CancellationToken::new();
+///     let options = ExtractOptions::new()
+///         .timeout(Duration::from_secs(1))
+///         .cancellation_token(token.clone());
+///     let input = r#"<div class="foo">1</div>"#;
+///     assert!(Foo::extract_from_str_with_options(input, &options).is_ok());
+///
+///     token.cancel();
+///     assert!(matches!(
+///         Foo::extract_from_str_with_options(input, &options),
+///         Err(Error::Cancelled)
+///     ));
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    deadline: Option<Instant>,
+    token: Option<CancellationToken>,
+    suggestions: bool,
+}
+impl ExtractOptions {
+    /// Creates options without any limit.
+    pub fn new() -> ExtractOptions {
+        ExtractOptions::default()
+    }
+    /// Fails the extraction with [`Error::TimedOut`] once `deadline` has passed.
+    pub fn deadline(mut self, deadline: Instant) -> ExtractOptions {
+        self.deadline = Some(deadline);
+        self
+    }
+    /// Fails the extraction with [`Error::TimedOut`] once `timeout` has elapsed from now.
+    pub fn timeout(self, timeout: Duration) -> ExtractOptions {
+        self.deadline(Instant::now() + timeout)
+    }
+    /// Fails the extraction with [`Error::Cancelled`] once `token` is cancelled.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> ExtractOptions {
+        self.token = Some(token);
+        self
+    }
+    /// Enables or disables suggestions in the errors reported when no element matches a selector.
+    ///
+    /// When enabled, the document is scanned for selectors close to the one that failed,
+    /// such as an id with different casing, the same classes on a different tag, or a part of the selector,
+    /// and the ones that match are included in the error message.
+    /// This is disabled by default because scanning the document is slow.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, options::ExtractOptions};
+    /// html_extractor! {
+    ///     #[derive(Debug)]
+    ///     Foo {
+    ///         foo: usize = (text of "div #foo"),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let options = ExtractOptions::new().suggestions(true);
+    ///     let err = Foo::extract_from_str_with_options(r#"<div><span id="Foo">1</span></div>"#, &options).unwrap_err();
+    ///     assert!(err.to_string().contains("did you mean `div #Foo`"));
+    /// }
+    /// ```
+    pub fn suggestions(mut self, enabled: bool) -> ExtractOptions {
+        self.suggestions = enabled;
+        self
+    }
+
+    /// Returns [`Error::Cancelled`] if the token is cancelled, or [`Error::TimedOut`] if the deadline has passed.
+    ///
+    /// Extractors implemented by hand can call it between their steps in
+    /// [`extract_with_options`](crate::HtmlExtractor::extract_with_options).
+    pub fn check(&self) -> Result<(), Error> {
+        if self.token.as_ref().is_some_and(|t| t.is_cancelled()) {
+            return Err(Error::Cancelled);
+        }
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return Err(Error::TimedOut);
+        }
+        Ok(())
+    }
+    /// Returns whether the errors include suggestions.
+    pub(crate) fn suggestions_enabled(&self) -> bool {
+        self.suggestions
+    }
+}
+
+/// A token to cancel extractions from another thread.
+///
+/// Clones share the same state, so cancelling one cancels all of them.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+    /// Cancels the extractions using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    /// Returns `true` if [`cancel`](CancellationToken::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,916 @@
+//! Parsers that can be used with the [parser specifier](../macro.html_extractor.html#parser-specifier).
+//!
+//! Some of the parsers are available only when the corresponding feature is enabled.
+//!
+//! | Feature        | Parsers                                      |
+//! |----------------|----------------------------------------------|
+//! | (none)         | [`emails`], [`phone_numbers`], [`data_uri`], |
+//! |                | [`number_in`], [`localized_number`]          |
+//! | `rust_decimal` | [`decimal`], [`decimal_or_none`]             |
+//! | `chrono`       | [`datetime`], [`datetime_in`]                |
+//! | `chrono-tz`    | [`zoned_datetime`]                           |
+//! | `mime`         | [`mime`](fn@mime)                            |
+//! | `url`          | [`url_with_base`]                            |
+//! | `uuid`         | [`uuid`](fn@uuid)                            |
+//! | `whatlang`     | [`with_language`]                            |
+
+pub use self::contact::*;
+pub use self::data_uri::*;
+#[cfg(feature = "chrono")]
+pub use self::datetime::*;
+#[cfg(feature = "rust_decimal")]
+pub use self::decimal::*;
+#[cfg(feature = "mime")]
+pub use self::mime::*;
+pub use self::number::*;
+pub use self::settings::*;
+#[cfg(feature = "url")]
+pub use self::url::*;
+#[cfg(feature = "uuid")]
+pub use self::uuid::*;
+#[cfg(feature = "whatlang")]
+pub use self::whatlang::*;
+
+#[cfg(feature = "rust_decimal")]
+mod decimal {
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    /// Parses a decimal number into [`Decimal`](rust_decimal::Decimal) without going through `f64`.
+    ///
+    /// Whitespace and `'` used as digit group separators are ignored.
+    /// When both `.` and `,` appear, the one that appears last is the decimal separator.
+    /// When only `,` appears, it is the decimal separator unless every `,` is followed by a group of exactly three digits.
+    /// When only `.` appears, it is the decimal separator unless it appears more than once.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers};
+    /// use rust_decimal::Decimal;
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: Decimal = (text of "#foo", parse with parsers::decimal),
+    ///         bar: Decimal = (text of "#bar", parse with parsers::decimal),
+    ///         baz: Decimal = (text of "#baz", parse with parsers::decimal),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <div id="foo">1,234,567.89</div>
+    ///         <div id="bar">1.234.567,89</div>
+    ///         <div id="baz">0,10</div>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo, Foo {
+    ///         foo: "1234567.89".parse().unwrap(),
+    ///         bar: "1234567.89".parse().unwrap(),
+    ///         baz: "0.10".parse().unwrap(),
+    ///     });
+    /// }
+    /// ```
+    pub fn decimal(input: &str) -> Result<Decimal, rust_decimal::Error> {
+        Decimal::from_str(&normalize(input))
+    }
+
+    /// Same as [`decimal`], but emits `None` if the input is empty.
+    pub fn decimal_or_none(input: &str) -> Result<Option<Decimal>, rust_decimal::Error> {
+        if input.trim().is_empty() {
+            Ok(None)
+        } else {
+            decimal(input).map(Some)
+        }
+    }
+
+    fn normalize(input: &str) -> String {
+        let digits: String = input
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '\'')
+            .collect();
+
+        let decimal_separator = match (digits.rfind('.'), digits.rfind(',')) {
+            (Some(dot), Some(comma)) => Some(dot.max(comma)),
+            (None, Some(comma)) if !is_grouped_by_comma(&digits) => Some(comma),
+            (Some(dot), None) if digits.matches('.').count() == 1 => Some(dot),
+            _ => None,
+        };
+
+        digits
+            .char_indices()
+            .filter_map(|(i, c)| match c {
+                '.' | ',' if Some(i) == decimal_separator => Some('.'),
+                '.' | ',' => None,
+                c => Some(c),
+            })
+            .collect()
+    }
+
+    fn is_grouped_by_comma(digits: &str) -> bool {
+        digits
+            .split(',')
+            .skip(1)
+            .all(|group| group.len() == 3 && group.chars().all(|c| c.is_ascii_digit()))
+    }
+}
+
+mod number {
+    use super::ParserSettings;
+    use std::str::FromStr;
+
+    /// The decimal and digit group separators of numbers in a locale.
+    ///
+    /// Used with [`number_in`] and [`localized_number`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct NumberLocale {
+        decimal_separator: char,
+        group_separators: &'static [char],
+    }
+    impl NumberLocale {
+        /// `1,234.56`, as in `en`, `ja` and `zh`.
+        pub const POINT: NumberLocale = NumberLocale::new('.', &[',']);
+        /// `1.234,56`, as in `de`, `es`, `it`, `nl`, `pt` and `id`.
+        pub const COMMA: NumberLocale = NumberLocale::new(',', &['.']);
+        /// `1 234,56`, as in `fr`, `cs`, `pl`, `ru`, `sv` and `fi`.
+        pub const COMMA_SPACE: NumberLocale = NumberLocale::new(',', &[]);
+        /// `1'234.56`, as in `de-CH`.
+        pub const POINT_APOSTROPHE: NumberLocale = NumberLocale::new('.', &['\'', '’']);
+
+        /// Creates a locale with the separators.
+        ///
+        /// Whitespace is always accepted as a digit group separator.
+        pub const fn new(
+            decimal_separator: char,
+            group_separators: &'static [char],
+        ) -> NumberLocale {
+            NumberLocale {
+                decimal_separator,
+                group_separators,
+            }
+        }
+        /// Returns the locale of a language tag such as `de-DE` or `fr`.
+        ///
+        /// Returns `None` if the language is not known.
+        pub fn from_tag(tag: &str) -> Option<NumberLocale> {
+            let tag = tag.to_ascii_lowercase().replace('_', "-");
+            if tag == "de-ch" || tag == "it-ch" || tag == "de-li" {
+                return Some(NumberLocale::POINT_APOSTROPHE);
+            }
+            match tag.split('-').next()? {
+                "en" | "ja" | "zh" | "ko" | "th" | "he" | "hi" => Some(NumberLocale::POINT),
+                "de" | "es" | "it" | "nl" | "pt" | "id" | "da" | "tr" | "el" | "ro" => {
+                    Some(NumberLocale::COMMA)
+                }
+                "fr" | "cs" | "sk" | "pl" | "ru" | "uk" | "sv" | "fi" | "nb" | "no" | "hu" => {
+                    Some(NumberLocale::COMMA_SPACE)
+                }
+                _ => None,
+            }
+        }
+
+        /// Rewrites the number in the form `FromStr` of the number types accepts.
+        fn normalize(&self, input: &str) -> String {
+            let mut seen_decimal_separator = false;
+            input
+                .trim()
+                .chars()
+                .filter_map(|c| {
+                    if c == self.decimal_separator && !seen_decimal_separator {
+                        seen_decimal_separator = true;
+                        Some('.')
+                    } else if c == '−' {
+                        Some('-')
+                    } else if !seen_decimal_separator
+                        && (c.is_whitespace() || self.group_separators.contains(&c))
+                    {
+                        None
+                    } else {
+                        // including separators after the decimal separator, which make the parse fail
+                        Some(c)
+                    }
+                })
+                .collect()
+        }
+    }
+    impl Default for NumberLocale {
+        fn default() -> NumberLocale {
+            NumberLocale::POINT
+        }
+    }
+
+    /// Returns a parser that parses a number written in `locale`.
+    ///
+    /// Digit group separators and whitespace before the decimal separator are removed, and the decimal separator is
+    /// replaced with `.` before the input is parsed with `FromStr` of the field type, so it can be used with
+    /// integers, floats and other number types.
+    /// Unlike [`decimal`], it doesn't guess the separators, so `1.234` is `1234` in [`NumberLocale::COMMA`].
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers::{self, NumberLocale}};
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: f64 = (text of "#foo", parse with parsers::number_in(NumberLocale::COMMA)),
+    ///         bar: u32 = (text of "#bar", parse with parsers::number_in(NumberLocale::COMMA)),
+    ///         baz: f64 = (text of "#baz", parse with parsers::number_in(NumberLocale::COMMA_SPACE)),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <div id="foo">1.234,56</div>
+    ///         <div id="bar">1.234</div>
+    ///         <div id="baz">-1 234,5</div>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo, Foo { foo: 1234.56, bar: 1234, baz: -1234.5 });
+    /// }
+    /// ```
+    pub fn number_in<T: FromStr>(locale: NumberLocale) -> impl Fn(&str) -> Result<T, T::Err> {
+        move |input| T::from_str(&locale.normalize(input))
+    }
+
+    /// Returns a parser that parses a number written in the locale of `settings`, which is usually the context of the extraction.
+    ///
+    /// It is the same as [`number_in`] with [`ParserSettings::number_locale`], so one structure can be used for
+    /// sites in different locales by giving a different context.
+    ///
+    /// For collected fields, the type may have to be specified (`parse with parsers::localized_number::<f64>(..)`).
+    /// ```
+    /// use html_extractor::{html_extractor, ExtractWithContext, parsers::{self, NumberLocale, ParserSettings}};
+    /// struct Site {
+    ///     locale: NumberLocale,
+    /// }
+    /// impl ParserSettings for Site {
+    ///     fn number_locale(&self) -> NumberLocale {
+    ///         self.locale
+    ///     }
+    /// }
+    /// html_extractor! {
+    ///     Foo {
+    ///         #![context(site: Site)]
+    ///         price: f64 = (text of ".price", parse with parsers::localized_number(site)),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"<div class="price">1.234,56</div>"#;
+    ///     let site = Site { locale: NumberLocale::from_tag("de-DE").unwrap() };
+    ///     let foo = Foo::extract_from_str_with_context(input, &site).unwrap();
+    ///     assert_eq!(foo.price, 1234.56);
+    /// }
+    /// ```
+    pub fn localized_number<T: FromStr>(
+        settings: &(impl ParserSettings + ?Sized),
+    ) -> impl Fn(&str) -> Result<T, T::Err> {
+        number_in(settings.number_locale())
+    }
+}
+
+mod settings {
+    use super::NumberLocale;
+
+    /// Settings of the parsers that depend on the site, like the locale of numbers and the time zone.
+    ///
+    /// Implement it for the type of the [context](../macro.html_extractor.html#struct-options) and give the context to
+    /// [`localized_number`](super::localized_number) and [`zoned_datetime`](super::zoned_datetime),
+    /// so the settings are given once for each extraction instead of in each field.
+    /// It is also implemented for [`NumberLocale`] and `chrono_tz::Tz`, which give themselves and the defaults of the others.
+    pub trait ParserSettings {
+        /// The locale of numbers. [`NumberLocale::POINT`] by default.
+        fn number_locale(&self) -> NumberLocale {
+            NumberLocale::default()
+        }
+        /// The time zone of datetimes without a UTC offset or a time zone name. UTC by default.
+        ///
+        /// This method is available only when the `chrono-tz` feature is enabled.
+        #[cfg(feature = "chrono-tz")]
+        fn time_zone(&self) -> chrono_tz::Tz {
+            chrono_tz::Tz::UTC
+        }
+    }
+    impl ParserSettings for NumberLocale {
+        fn number_locale(&self) -> NumberLocale {
+            *self
+        }
+    }
+    #[cfg(feature = "chrono-tz")]
+    impl ParserSettings for chrono_tz::Tz {
+        fn time_zone(&self) -> chrono_tz::Tz {
+            *self
+        }
+    }
+}
+
+mod contact {
+    use std::convert::Infallible;
+
+    lazy_static::lazy_static! {
+        static ref EMAIL: regex::Regex = regex::Regex::new(
+            r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}"
+        )
+        .unwrap();
+        // the digit groups are separated by at most one space, `.` or `-`, and don't continue a word
+        static ref PHONE_NUMBER: regex::Regex = regex::Regex::new(
+            r"(?:tel:\+?|\+)?(?:\(\d+\)[ .-]?|(?-u:\b)\d+[ .-])*(?-u:\b)\d+(?-u:\b)"
+        )
+        .unwrap();
+        static ref NOT_PHONE_NUMBER: regex::Regex = regex::Regex::new(
+            r"^(?:\d{4}-\d{1,2}-\d{1,2}|\d{1,3}(?:\.\d{1,3}){3})$"
+        )
+        .unwrap();
+    }
+
+    /// Finds all email addresses in the input.
+    ///
+    /// It can be used for both text and `mailto:` links (`mailto:foo@example.com?subject=..`).
+    /// Duplicated addresses are removed.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers};
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: Vec<String> = (text of "#foo", parse with parsers::emails),
+    ///         bar: Vec<String> = (attr["href"] of "#bar", parse with parsers::emails),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <p id="foo">Contact: sales@example.com or support@example.co.jp</p>
+    ///         <a id="bar" href="mailto:info@example.com?subject=Hello">Mail us</a>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo, Foo {
+    ///         foo: vec!["sales@example.com".to_owned(), "support@example.co.jp".to_owned()],
+    ///         bar: vec!["info@example.com".to_owned()],
+    ///     });
+    /// }
+    /// ```
+    pub fn emails(input: &str) -> Result<Vec<String>, Infallible> {
+        Ok(dedup(EMAIL.find_iter(input).map(|m| m.as_str().to_owned())))
+    }
+
+    /// Finds all phone numbers in the input and normalizes them into the form of `+` (if any) followed by digits.
+    ///
+    /// It can be used for both text and `tel:` links (`tel:+1-555-0100`).
+    /// A sequence of 7 to 15 digits is regarded as a phone number if it starts with `+` or `tel:`,
+    /// or if it is split into two or more groups, like `555-010-0123` or `(555) 010 0123`.
+    /// The groups are separated by a single space, `.` or `-`, and the last group must have at least 3 digits,
+    /// so lists of small numbers like `10 20 30 40`, a single long number like an order ID, and numbers on different lines
+    /// are not regarded as phone numbers. Ones that look like a date (`2020-05-01`) or an IPv4 address are not, either.
+    /// Duplicated numbers are removed.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers};
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: Vec<String> = (text of "#foo", parse with parsers::phone_numbers),
+    ///         bar: Vec<String> = (attr["href"] of "#bar", parse with parsers::phone_numbers),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <p id="foo">Tel: (555) 010-0123, Fax: +81 3-1234-5678 (updated 2020-05-01)</p>
+    ///         <a id="bar" href="tel:+1-555-0100">Call us</a>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo, Foo {
+    ///         foo: vec!["5550100123".to_owned(), "+81312345678".to_owned()],
+    ///         bar: vec!["+15550100".to_owned()],
+    ///     });
+    /// }
+    /// ```
+    pub fn phone_numbers(input: &str) -> Result<Vec<String>, Infallible> {
+        Ok(dedup(
+            PHONE_NUMBER
+                .find_iter(input)
+                .map(|m| m.as_str())
+                .filter(|number| !NOT_PHONE_NUMBER.is_match(number))
+                .filter(|number| {
+                    let groups = number
+                        .split(|c: char| !c.is_ascii_digit())
+                        .filter(|group| !group.is_empty())
+                        .collect::<Vec<_>>();
+                    let prefixed = number.starts_with('+') || number.starts_with("tel:");
+                    (prefixed || groups.len() >= 2)
+                        && groups.last().is_some_and(|group| group.len() >= 3)
+                })
+                .map(|number| {
+                    number
+                        .chars()
+                        .filter(|c| *c == '+' || c.is_ascii_digit())
+                        .collect::<String>()
+                })
+                .filter(|number| (7..=15).contains(&number.trim_start_matches('+').len())),
+        ))
+    }
+
+    fn dedup(items: impl Iterator<Item = String>) -> Vec<String> {
+        let mut found = Vec::new();
+        for item in items {
+            if !found.contains(&item) {
+                found.push(item);
+            }
+        }
+        found
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod datetime {
+    use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+    use thiserror::Error;
+
+    const NAIVE_FORMATS: &[&str] = &[
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ];
+
+    /// An error returned by the datetime parsers.
+    #[derive(Debug, Error)]
+    pub enum DateTimeError {
+        #[error("cannot parse `{0}` as a datetime")]
+        Invalid(String),
+        #[error("`{0}` does not have a UTC offset")]
+        MissingOffset(String),
+        #[error("`{0}` is ambiguous or does not exist in the time zone")]
+        InvalidLocalTime(String),
+    }
+
+    /// Parses a datetime with a UTC offset, such as RFC 3339 (`2020-05-01T12:00:00+09:00`) or RFC 2822 (`Fri, 01 May 2020 12:00:00 +0900`).
+    ///
+    /// It fails if the input has no UTC offset. Use [`datetime_in`] to supply a default time zone.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers};
+    /// use chrono::{DateTime, FixedOffset};
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: DateTime<FixedOffset> = (attr["datetime"] of "#foo", parse with parsers::datetime),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <time id="foo" datetime="2020-05-01T12:00:00+09:00"></time>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo.foo.to_rfc3339(), "2020-05-01T12:00:00+09:00");
+    /// }
+    /// ```
+    pub fn datetime(input: &str) -> Result<DateTime<FixedOffset>, DateTimeError> {
+        match parse(input)? {
+            Parsed::WithOffset(datetime) => Ok(datetime),
+            Parsed::Naive(_) => Err(DateTimeError::MissingOffset(input.to_owned())),
+        }
+    }
+
+    /// Returns a parser that parses a datetime into the time zone `tz`.
+    ///
+    /// If the input has a UTC offset, it is honored and the datetime is converted into `tz`.
+    /// If not, the input is interpreted as a local time in `tz`.
+    /// `tz` can be any [`TimeZone`](chrono::TimeZone), such as [`FixedOffset`](chrono::FixedOffset), [`Utc`](chrono::Utc) or `chrono_tz::Tz`.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers};
+    /// use chrono::{DateTime, FixedOffset, Utc};
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: DateTime<Utc> = (text of "#foo", parse with parsers::datetime_in(Utc)),
+    ///         bar: DateTime<FixedOffset> = (text of "#bar", parse with parsers::datetime_in(FixedOffset::east_opt(9 * 3600).unwrap())),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <div id="foo">2020-05-01T12:00:00+09:00</div>
+    ///         <div id="bar">2020-05-01 12:00</div>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo.foo.to_rfc3339(), "2020-05-01T03:00:00+00:00");
+    ///     assert_eq!(foo.bar.to_rfc3339(), "2020-05-01T12:00:00+09:00");
+    /// }
+    /// ```
+    pub fn datetime_in<Tz: TimeZone>(
+        tz: Tz,
+    ) -> impl Fn(&str) -> Result<DateTime<Tz>, DateTimeError> {
+        move |input| match parse(input)? {
+            Parsed::WithOffset(datetime) => Ok(datetime.with_timezone(&tz)),
+            Parsed::Naive(naive) => tz
+                .from_local_datetime(&naive)
+                .single()
+                .ok_or_else(|| DateTimeError::InvalidLocalTime(input.to_owned())),
+        }
+    }
+
+    /// Returns a parser that parses a datetime followed by an IANA time zone name, such as `2020-05-01 12:00 Asia/Tokyo`.
+    ///
+    /// If the input has no time zone name, it is parsed in the same way as [`datetime_in`] with
+    /// [`ParserSettings::time_zone`](super::ParserSettings::time_zone) of `settings`, which is usually the context of the extraction.
+    /// A `chrono_tz::Tz` can also be given as the settings.
+    /// ```
+    /// use html_extractor::{html_extractor, ExtractWithContext, parsers::{self, ParserSettings}};
+    /// use chrono::DateTime;
+    /// use chrono_tz::Tz;
+    /// struct Site {
+    ///     time_zone: Tz,
+    /// }
+    /// impl ParserSettings for Site {
+    ///     fn time_zone(&self) -> Tz {
+    ///         self.time_zone
+    ///     }
+    /// }
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         #![context(site: Site)]
+    ///         foo: DateTime<Tz> = (text of "#foo", parse with parsers::zoned_datetime(site)),
+    ///         bar: DateTime<Tz> = (text of "#bar", parse with parsers::zoned_datetime(site)),
+    ///         baz: DateTime<Tz> = (text of "#bar", parse with parsers::zoned_datetime(&Tz::UTC)),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <div id="foo">2020-05-01 12:00 Asia/Tokyo</div>
+    ///         <div id="bar">2020-05-01 12:00</div>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str_with_context(input, &Site { time_zone: Tz::Europe__Berlin }).unwrap();
+    ///     assert_eq!(foo.foo.timezone(), Tz::Asia__Tokyo);
+    ///     assert_eq!(foo.foo.to_rfc3339(), "2020-05-01T12:00:00+09:00");
+    ///     assert_eq!(foo.bar.to_rfc3339(), "2020-05-01T12:00:00+02:00");
+    ///     assert_eq!(foo.baz.to_rfc3339(), "2020-05-01T12:00:00+00:00");
+    /// }
+    /// ```
+    #[cfg(feature = "chrono-tz")]
+    pub fn zoned_datetime(
+        settings: &(impl super::ParserSettings + ?Sized),
+    ) -> impl Fn(&str) -> Result<DateTime<chrono_tz::Tz>, DateTimeError> {
+        let default_tz = settings.time_zone();
+        move |input| {
+            let zoned = input
+                .trim()
+                .rsplit_once(char::is_whitespace)
+                .and_then(|(datetime, tz)| {
+                    tz.parse::<chrono_tz::Tz>().ok().map(|tz| (datetime, tz))
+                });
+            match zoned {
+                Some((datetime, tz)) => datetime_in(tz)(datetime),
+                None => datetime_in(default_tz)(input),
+            }
+        }
+    }
+
+    enum Parsed {
+        WithOffset(DateTime<FixedOffset>),
+        Naive(NaiveDateTime),
+    }
+
+    fn parse(input: &str) -> Result<Parsed, DateTimeError> {
+        let input = input.trim();
+        if let Ok(datetime) = input.parse::<DateTime<FixedOffset>>() {
+            return Ok(Parsed::WithOffset(datetime));
+        }
+        if let Ok(datetime) = DateTime::parse_from_rfc2822(input) {
+            return Ok(Parsed::WithOffset(datetime));
+        }
+        NAIVE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+            .map(Parsed::Naive)
+            .ok_or_else(|| DateTimeError::Invalid(input.to_owned()))
+    }
+}
+
+#[cfg(feature = "mime")]
+mod mime {
+    use mime::Mime;
+
+    /// Parses a MIME type, such as the `type` attribute of `<script>`, `<link>` or `<source>`, into [`Mime`](mime::Mime).
+    ///
+    /// Unlike [`Mime::from_str`](std::str::FromStr::from_str), whitespace around `;` and `=` is allowed.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers};
+    /// use mime::Mime;
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: Vec<Mime> = (attr["type"] of "script", collect, parse with parsers::mime),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <script type="application/ld+json"></script>
+    ///         <script type="Text/JavaScript ; charset = UTF-8"></script>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo.foo[0].essence_str(), "application/ld+json");
+    ///     assert_eq!(foo.foo[1].essence_str(), "text/javascript");
+    ///     assert_eq!(foo.foo[1].get_param(mime::CHARSET), Some(mime::UTF_8));
+    /// }
+    /// ```
+    pub fn mime(input: &str) -> Result<Mime, mime::FromStrError> {
+        input
+            .split(';')
+            .map(|segment| {
+                segment
+                    .split('=')
+                    .map(str::trim)
+                    .collect::<Vec<_>>()
+                    .join("=")
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+            .parse()
+    }
+}
+
+#[cfg(feature = "url")]
+mod url {
+    use url::Url;
+
+    /// Returns a parser that parses a URL, resolving relative references against `base`.
+    ///
+    /// Fields typed [`Url`](url::Url) can be extracted without this parser, but then only absolute URLs are accepted.
+    /// In both cases, an invalid URL is reported with [`url::ParseError`].
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers};
+    /// use url::Url;
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: Url = (attr["href"] of "#foo"),
+    ///         bar: Vec<Url> = (attr["href"] of ".bar", collect, parse with parsers::url_with_base(
+    ///             Url::parse("https://example.com/articles/").unwrap()
+    ///         )),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <a id="foo" href="https://example.com/"></a>
+    ///         <a class="bar" href="1"></a>
+    ///         <a class="bar" href="/about"></a>
+    ///         <a class="bar" href="https://example.org/"></a>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo.foo.as_str(), "https://example.com/");
+    ///     assert_eq!(
+    ///         foo.bar.iter().map(Url::as_str).collect::<Vec<_>>(),
+    ///         vec!["https://example.com/articles/1", "https://example.com/about", "https://example.org/"],
+    ///     );
+    /// }
+    /// ```
+    pub fn url_with_base(base: Url) -> impl Fn(&str) -> Result<Url, url::ParseError> {
+        move |input| base.join(input)
+    }
+}
+
+mod data_uri {
+    use thiserror::Error;
+
+    /// A parsed `data:` URL.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct DataUri {
+        /// The media type with its parameters, like `image/png` or `text/plain;charset=US-ASCII`.
+        pub media_type: String,
+        /// The decoded data.
+        pub data: Vec<u8>,
+    }
+    impl DataUri {
+        /// Returns the media type without its parameters, in lowercase.
+        pub fn mime_type(&self) -> String {
+            self.media_type
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase()
+        }
+    }
+
+    /// An error returned by [`data_uri`].
+    #[derive(Debug, Error, PartialEq, Eq)]
+    pub enum DataUriError {
+        #[error("`{0}` is not a data URL")]
+        NotDataUri(String),
+        #[error("the base64 data is invalid")]
+        InvalidBase64,
+    }
+
+    /// Parses a `data:` URL into its media type and the decoded data, as described in [RFC 2397](https://www.rfc-editor.org/rfc/rfc2397).
+    ///
+    /// The data is decoded from base64 if `;base64` is given, and percent-decoded otherwise.
+    /// Whitespace in base64 data is ignored. The media type defaults to `text/plain;charset=US-ASCII`.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers::{self, DataUri}};
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         image: DataUri = (attr["src"] of "img", parse with parsers::data_uri),
+    ///         json: DataUri = (attr["href"] of "a", parse with parsers::data_uri),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <img src="data:image/png;base64,iVBORw0KGgo=">
+    ///         <a href="data:application/json,%7B%22a%22%3A1%7D">download</a>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo.image.mime_type(), "image/png");
+    ///     assert_eq!(foo.image.data, b"\x89PNG\r\n\x1a\n");
+    ///     assert_eq!(foo.json.data, br#"{"a":1}"#);
+    /// }
+    /// ```
+    pub fn data_uri(input: &str) -> Result<DataUri, DataUriError> {
+        let not_data_uri = || DataUriError::NotDataUri(input.to_owned());
+        let trimmed = input.trim();
+        let rest = match trimmed.get(..5) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("data:") => &trimmed[5..],
+            _ => return Err(not_data_uri()),
+        };
+        let (header, data) = rest.split_once(',').ok_or_else(not_data_uri)?;
+
+        let header = header.trim();
+        // `get` avoids slicing inside a multibyte character of the media type
+        let (media_type, base64) = match header.len().checked_sub(7) {
+            Some(i)
+                if header
+                    .get(i..)
+                    .is_some_and(|suffix| suffix.eq_ignore_ascii_case(";base64")) =>
+            {
+                (&header[..i], true)
+            }
+            _ => (header, false),
+        };
+        let media_type = if media_type.is_empty() {
+            "text/plain;charset=US-ASCII".to_owned()
+        } else if media_type.starts_with(';') {
+            format!("text/plain{}", media_type)
+        } else {
+            media_type.to_owned()
+        };
+
+        let data = if base64 {
+            decode_base64(&percent_decode(data)).ok_or(DataUriError::InvalidBase64)?
+        } else {
+            percent_decode(data)
+        };
+        Ok(DataUri { media_type, data })
+    }
+
+    fn percent_decode(input: &str) -> Vec<u8> {
+        let bytes = input.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .filter(|_| bytes[i] == b'%')
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            match hex {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                }
+                None => {
+                    decoded.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+        decoded
+    }
+
+    fn decode_base64(input: &[u8]) -> Option<Vec<u8>> {
+        let mut decoded = Vec::with_capacity(input.len() / 4 * 3);
+        let mut buf = 0u32;
+        let mut bits = 0;
+        let mut padding = 0;
+        for &c in input {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' | b'-' => 62,
+                b'/' | b'_' => 63,
+                b'=' => {
+                    padding += 1;
+                    continue;
+                }
+                c if c.is_ascii_whitespace() => continue,
+                _ => return None,
+            };
+            if padding > 0 {
+                return None;
+            }
+            buf = buf << 6 | u32::from(value);
+            bits += 6;
+            if bits >= 8 {
+                bits -= 8;
+                decoded.push((buf >> bits) as u8);
+                buf &= (1 << bits) - 1;
+            }
+        }
+        // a single base64 character cannot make a byte
+        if bits >= 6 || padding > 2 {
+            return None;
+        }
+        Some(decoded)
+    }
+}
+
+#[cfg(feature = "uuid")]
+mod uuid {
+    use thiserror::Error;
+    use uuid::Uuid;
+
+    /// An error returned by [`uuid`](fn@uuid).
+    #[derive(Debug, Error)]
+    pub enum UuidError {
+        #[error("no UUID is found in `{0}`")]
+        NotFound(String),
+        #[error(transparent)]
+        Invalid(#[from] uuid::Error),
+    }
+
+    /// Finds a UUID in the input and parses it into [`Uuid`](uuid::Uuid).
+    ///
+    /// Unlike [`Uuid::from_str`](std::str::FromStr::from_str), surrounding text like `urn:uuid:`, `{..}` or `item-` is ignored.
+    /// Both hyphenated and simple (32 hex digits) forms are accepted.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers};
+    /// use uuid::Uuid;
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: Uuid = (attr["data-id"] of "#foo", parse with parsers::uuid),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <div id="foo" data-id="item-67e55044-10b1-426f-9247-bb680e5fe0c8"></div>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo.foo, Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap());
+    /// }
+    /// ```
+    pub fn uuid(input: &str) -> Result<Uuid, UuidError> {
+        lazy_static::lazy_static! {
+            static ref UUID: regex::Regex = regex::Regex::new(
+                r"(?i)\b(?:[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}|[0-9a-f]{32})\b"
+            )
+            .unwrap();
+        }
+        let found = UUID
+            .find(input)
+            .ok_or_else(|| UuidError::NotFound(input.to_owned()))?;
+        Ok(Uuid::parse_str(found.as_str())?)
+    }
+}
+
+#[cfg(feature = "whatlang")]
+mod whatlang {
+    use thiserror::Error;
+    use whatlang::Lang;
+
+    /// An error returned by [`with_language`].
+    #[derive(Debug, Error)]
+    #[error("cannot detect the language of `{0}`")]
+    pub struct LanguageError(pub String);
+
+    /// Detects the language of the input and returns it together with the input.
+    ///
+    /// The detection is done with [`whatlang`](https://docs.rs/whatlang), which is not reliable for very short texts.
+    /// [`Lang::code`](whatlang::Lang::code) returns the ISO 639-3 code of the language.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, parsers};
+    /// use whatlang::Lang;
+    /// html_extractor! {
+    ///     #[derive(Debug, PartialEq)]
+    ///     Foo {
+    ///         foo: (String, Lang) = (text of "#foo", parse with parsers::with_language),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <p id="foo">Der schnelle braune Fuchs springt über den faulen Hund.</p>
+    ///     "#;
+    ///     let foo = Foo::extract_from_str(input).unwrap();
+    ///     assert_eq!(foo.foo.1, Lang::Deu);
+    ///     assert_eq!(foo.foo.1.code(), "deu");
+    /// }
+    /// ```
+    pub fn with_language(input: &str) -> Result<(String, Lang), LanguageError> {
+        match whatlang::detect_lang(input) {
+            Some(lang) => Ok((input.to_owned(), lang)),
+            None => Err(LanguageError(input.to_owned())),
+        }
+    }
+}
@@ -0,0 +1,550 @@
+//! Ready-made extractors for common structures of HTML documents.
+//!
+//! They implement [`HtmlExtractor`](crate::HtmlExtractor), so they can be extracted directly or used as the type of
+//! fields with the [`elem of ..` target](../macro.html_extractor.html#target-specifier).
+//!
+//! | Extractor                   | Extracts                                                               |
+//! |-----------------------------|------------------------------------------------------------------------|
+//! | [`AccessibilityReport`]     | ARIA landmarks, role attributes, images missing alt text, form labels  |
+//! | [`Media`]                   | A `<video>` or `<audio>` element with its poster, flags and sources    |
+//! | [`Time`]                    | The `datetime` attribute and the text of a `<time>` element            |
+
+pub use self::a11y::*;
+pub use self::media::*;
+pub use self::time::*;
+
+/// Returns the text content of the element with collapsed whitespace.
+fn normalized_text(elem: &scraper::ElementRef) -> String {
+    elem.text()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+mod a11y {
+    use super::normalized_text;
+    use crate::{Error, HtmlExtractor};
+    use lazy_static::lazy_static;
+    use scraper::{ElementRef, Selector};
+
+    /// The structures of a document that matter for accessibility audits.
+    /// ```
+    /// use html_extractor::{HtmlExtractor, prelude::AccessibilityReport};
+    ///
+    /// let report = AccessibilityReport::extract_from_str(r#"
+    ///     <nav aria-label="Site"><a href="/">Home</a></nav>
+    ///     <main>
+    ///         <img src="/logo.png">
+    ///         <label for="q">Search</label><input id="q" name="q">
+    ///         <input name="email">
+    ///     </main>
+    /// "#).unwrap();
+    /// assert_eq!(report.landmarks[0].role, "navigation");
+    /// assert_eq!(report.landmarks[0].label.as_deref(), Some("Site"));
+    /// assert_eq!(report.images_missing_alt[0].src.as_deref(), Some("/logo.png"));
+    /// assert_eq!(report.form_controls[0].label.as_deref(), Some("Search"));
+    /// assert_eq!(report.form_controls[1].label, None);
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Default)]
+    pub struct AccessibilityReport {
+        /// The landmarks, given by `role` attributes or implied by elements like `<main>` and `<nav>`.
+        pub landmarks: Vec<Landmark>,
+        /// The elements that have a `role` attribute.
+        pub roles: Vec<RoleElement>,
+        /// The `<img>` elements without `alt` attribute. Images with `alt=""` are decorative and not included.
+        pub images_missing_alt: Vec<ImageMissingAlt>,
+        /// The form controls and their labels.
+        pub form_controls: Vec<FormControl>,
+    }
+
+    /// An ARIA landmark.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Landmark {
+        /// The landmark role, like `main` or `navigation`.
+        pub role: String,
+        /// The tag name of the element.
+        pub tag: String,
+        /// The accessible name given by `aria-label` or `aria-labelledby`.
+        pub label: Option<String>,
+    }
+
+    /// An element that has a `role` attribute.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RoleElement {
+        /// The value of the `role` attribute.
+        pub role: String,
+        /// The tag name of the element.
+        pub tag: String,
+        /// The accessible name given by `aria-label` or `aria-labelledby`.
+        pub label: Option<String>,
+    }
+
+    /// An `<img>` element without `alt` attribute.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ImageMissingAlt {
+        /// The `src` attribute.
+        pub src: Option<String>,
+    }
+
+    /// A form control and its label.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct FormControl {
+        /// The tag name of the element, like `input` or `select`.
+        pub tag: String,
+        /// The `type` attribute of `<input>`.
+        pub input_type: Option<String>,
+        /// The `id` attribute.
+        pub id: Option<String>,
+        /// The `name` attribute.
+        pub name: Option<String>,
+        /// The label given by `aria-labelledby`, `aria-label`, a `<label for>` or a wrapping `<label>`.
+        /// `None` means the control is not labelled.
+        pub label: Option<String>,
+    }
+
+    lazy_static! {
+        static ref ROLE_SELECTOR: Selector = Selector::parse("[role]").unwrap();
+        static ref IMG_SELECTOR: Selector = Selector::parse("img:not([alt])").unwrap();
+        static ref CONTROL_SELECTOR: Selector = Selector::parse(
+            "input:not([type=hidden]):not([type=submit]):not([type=reset]):not([type=button]):not([type=image]), select, textarea"
+        )
+        .unwrap();
+        static ref LABEL_SELECTOR: Selector = Selector::parse("label[for]").unwrap();
+    }
+
+    const LANDMARK_ROLES: &[&str] = &[
+        "banner",
+        "complementary",
+        "contentinfo",
+        "form",
+        "main",
+        "navigation",
+        "region",
+        "search",
+    ];
+
+    impl HtmlExtractor for AccessibilityReport {
+        fn extract(elem: &ElementRef) -> Result<Self, Error> {
+            let root = root_of(elem);
+            let mut report = AccessibilityReport::default();
+
+            for e in elem.select(&ROLE_SELECTOR) {
+                let role = e.value().attr("role").unwrap_or_default().trim().to_owned();
+                report.roles.push(RoleElement {
+                    role,
+                    tag: e.value().name().to_owned(),
+                    label: aria_label(&root, &e),
+                });
+            }
+
+            for e in elem.descendants().filter_map(ElementRef::wrap) {
+                let role = match landmark_role(&e) {
+                    Some(role) => role,
+                    None => continue,
+                };
+                report.landmarks.push(Landmark {
+                    role,
+                    tag: e.value().name().to_owned(),
+                    label: aria_label(&root, &e),
+                });
+            }
+
+            for e in elem.select(&IMG_SELECTOR) {
+                report.images_missing_alt.push(ImageMissingAlt {
+                    src: e.value().attr("src").map(str::to_owned),
+                });
+            }
+
+            for e in elem.select(&CONTROL_SELECTOR) {
+                report.form_controls.push(FormControl {
+                    tag: e.value().name().to_owned(),
+                    input_type: if e.value().name() == "input" {
+                        e.value().attr("type").map(str::to_owned)
+                    } else {
+                        None
+                    },
+                    id: e.value().id().map(str::to_owned),
+                    name: e.value().attr("name").map(str::to_owned),
+                    label: control_label(&root, &e),
+                });
+            }
+
+            Ok(report)
+        }
+    }
+
+    /// Returns the landmark role of the element, explicit or implied by the tag.
+    fn landmark_role(elem: &ElementRef) -> Option<String> {
+        if let Some(role) = elem.value().attr("role") {
+            // only the first token is used as the primary role
+            let role = role.split_whitespace().next()?.to_ascii_lowercase();
+            return LANDMARK_ROLES.contains(&&*role).then_some(role);
+        }
+        let in_sectioning = || {
+            elem.ancestors().filter_map(ElementRef::wrap).any(|a| {
+                matches!(
+                    a.value().name(),
+                    "article" | "aside" | "main" | "nav" | "section"
+                )
+            })
+        };
+        let has_name = || {
+            elem.value().attr("aria-label").is_some()
+                || elem.value().attr("aria-labelledby").is_some()
+        };
+        let role = match elem.value().name() {
+            "header" if !in_sectioning() => "banner",
+            "footer" if !in_sectioning() => "contentinfo",
+            "main" => "main",
+            "nav" => "navigation",
+            "aside" => "complementary",
+            "search" => "search",
+            "form" if has_name() => "form",
+            "section" if has_name() => "region",
+            _ => return None,
+        };
+        Some(role.to_owned())
+    }
+
+    /// Returns the accessible name given by `aria-labelledby` or `aria-label`.
+    fn aria_label(root: &ElementRef, elem: &ElementRef) -> Option<String> {
+        if let Some(ids) = elem.value().attr("aria-labelledby") {
+            let label = ids
+                .split_whitespace()
+                .filter_map(|id| find_by_id(root, id))
+                .map(|e| normalized_text(&e))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !label.is_empty() {
+                return Some(label);
+            }
+        }
+        elem.value()
+            .attr("aria-label")
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+            .map(str::to_owned)
+    }
+
+    /// Returns the label of the form control.
+    fn control_label(root: &ElementRef, elem: &ElementRef) -> Option<String> {
+        if let Some(label) = aria_label(root, elem) {
+            return Some(label);
+        }
+        if let Some(id) = elem.value().id() {
+            let label = root
+                .select(&LABEL_SELECTOR)
+                .find(|label| label.value().attr("for") == Some(id));
+            if let Some(label) = label {
+                return Some(normalized_text(&label)).filter(|label| !label.is_empty());
+            }
+        }
+        elem.ancestors()
+            .filter_map(ElementRef::wrap)
+            .find(|a| a.value().name() == "label")
+            .map(|label| normalized_text(&label))
+            .filter(|label| !label.is_empty())
+    }
+
+    fn find_by_id<'a>(root: &ElementRef<'a>, id: &str) -> Option<ElementRef<'a>> {
+        root.descendants()
+            .filter_map(ElementRef::wrap)
+            .find(|e| e.value().id() == Some(id))
+    }
+
+    /// Returns the root element of the document, so that labels outside `elem` are found.
+    fn root_of<'a>(elem: &ElementRef<'a>) -> ElementRef<'a> {
+        elem.ancestors()
+            .filter_map(ElementRef::wrap)
+            .last()
+            .unwrap_or(*elem)
+    }
+}
+
+mod media {
+    use crate::{Error, HtmlExtractor};
+    use lazy_static::lazy_static;
+    use scraper::{ElementRef, Selector};
+
+    /// A `<video>` or `<audio>` element.
+    ///
+    /// If the element to extract from is not a media element, the first `<video>` or `<audio>` element in it is used.
+    /// The `src` attribute of the element and the `<source>` children are merged into [`sources`](Media::sources).
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, prelude::{Media, MediaKind}};
+    /// html_extractor! {
+    ///     Page {
+    ///         videos: Vec<Media> = (elem of "video", collect),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <video src="/intro.mp4" poster="/intro.jpg" controls></video>
+    ///         <video muted autoplay>
+    ///             <source src="/clip.webm" type="video/webm">
+    ///             <source src="/clip.mp4" type="video/mp4">
+    ///         </video>
+    ///     "#;
+    ///     let page = Page::extract_from_str(input).unwrap();
+    ///     assert_eq!(page.videos[0].kind, MediaKind::Video);
+    ///     assert_eq!(page.videos[0].poster.as_deref(), Some("/intro.jpg"));
+    ///     assert!(page.videos[0].controls);
+    ///     assert_eq!(page.videos[0].src(), Some("/intro.mp4"));
+    ///
+    ///     assert!(page.videos[1].muted && page.videos[1].autoplay);
+    ///     assert_eq!(page.videos[1].sources.len(), 2);
+    ///     assert_eq!(page.videos[1].sources[1].mime_type.as_deref(), Some("video/mp4"));
+    /// }
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Media {
+        /// Whether the element is `<video>` or `<audio>`.
+        pub kind: MediaKind,
+        /// The `poster` attribute. Always `None` for `<audio>`.
+        pub poster: Option<String>,
+        /// The presence of the `controls` attribute.
+        pub controls: bool,
+        /// The presence of the `autoplay` attribute.
+        pub autoplay: bool,
+        /// The presence of the `loop` attribute.
+        pub looping: bool,
+        /// The presence of the `muted` attribute.
+        pub muted: bool,
+        /// The `src` attribute of the element (if any), followed by the `<source>` children in document order.
+        ///
+        /// Sources with missing or empty `src` are skipped.
+        pub sources: Vec<MediaSource>,
+    }
+    impl Media {
+        /// Returns the first source, which is the one a browser tries first.
+        pub fn src(&self) -> Option<&str> {
+            self.sources.first().map(|s| &*s.src)
+        }
+    }
+
+    /// The kind of a media element.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum MediaKind {
+        /// `<video>`
+        Video,
+        /// `<audio>`
+        Audio,
+    }
+
+    /// A source of a media element.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct MediaSource {
+        /// The `src` attribute.
+        pub src: String,
+        /// The `type` attribute of `<source>`. Always `None` for the `src` attribute of the media element.
+        pub mime_type: Option<String>,
+        /// The `media` attribute of `<source>`. Always `None` for the `src` attribute of the media element.
+        pub media: Option<String>,
+    }
+
+    lazy_static! {
+        static ref MEDIA_SELECTOR: Selector = Selector::parse("video, audio").unwrap();
+    }
+
+    impl HtmlExtractor for Media {
+        fn extract(elem: &ElementRef) -> Result<Self, Error> {
+            let media = match elem.value().name() {
+                "video" | "audio" => *elem,
+                _ => elem
+                    .select(&MEDIA_SELECTOR)
+                    .next()
+                    .ok_or(Error::InvalidInput(std::borrow::Cow::Borrowed(
+                        "no `<video>` or `<audio>` element is found",
+                    )))?,
+            };
+            let value = media.value();
+            let attr = |e: &ElementRef, name| {
+                e.value()
+                    .attr(name)
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_owned)
+            };
+
+            let mut sources = Vec::new();
+            if let Some(src) = attr(&media, "src") {
+                sources.push(MediaSource {
+                    src,
+                    mime_type: None,
+                    media: None,
+                });
+            }
+            for source in media
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|e| e.value().name() == "source")
+            {
+                if let Some(src) = attr(&source, "src") {
+                    sources.push(MediaSource {
+                        src,
+                        mime_type: attr(&source, "type"),
+                        media: attr(&source, "media"),
+                    });
+                }
+            }
+
+            let kind = if value.name() == "video" {
+                MediaKind::Video
+            } else {
+                MediaKind::Audio
+            };
+            Ok(Media {
+                kind,
+                poster: match kind {
+                    MediaKind::Video => attr(&media, "poster"),
+                    MediaKind::Audio => None,
+                },
+                controls: value.attr("controls").is_some(),
+                autoplay: value.attr("autoplay").is_some(),
+                looping: value.attr("loop").is_some(),
+                muted: value.attr("muted").is_some(),
+                sources,
+            })
+        }
+    }
+}
+
+mod time {
+    use super::normalized_text;
+    use crate::{Error, HtmlExtractor};
+    use lazy_static::lazy_static;
+    use scraper::{ElementRef, Selector};
+
+    /// A `<time>` element.
+    ///
+    /// If the element to extract from is not a `<time>` element, the first `<time>` element in it is used.
+    /// With the `chrono` feature, it can be converted into a datetime, preferring the machine-readable `datetime` attribute
+    /// and falling back to parsing the text with a format.
+    /// ```
+    /// use html_extractor::{html_extractor, HtmlExtractor, prelude::Time};
+    /// html_extractor! {
+    ///     Article {
+    ///         published: Time = (elem of ".published"),
+    ///         updated: Time = (elem of ".updated"),
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let input = r#"
+    ///         <time class="published" datetime="2020-01-02T03:04:05Z">Jan 2</time>
+    ///         <time class="updated">2020/02/03 04:05</time>
+    ///     "#;
+    ///     let article = Article::extract_from_str(input).unwrap();
+    ///     assert_eq!(article.published.datetime.as_deref(), Some("2020-01-02T03:04:05Z"));
+    ///     assert_eq!(article.updated.text, "2020/02/03 04:05");
+    ///     # #[cfg(feature = "chrono")]
+    ///     # {
+    ///     use chrono::{TimeZone, Utc};
+    ///     assert_eq!(
+    ///         article.published.to_datetime_in("%Y/%m/%d %H:%M", Utc).unwrap(),
+    ///         Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap(),
+    ///     );
+    ///     assert_eq!(
+    ///         article.updated.to_datetime_in("%Y/%m/%d %H:%M", Utc).unwrap(),
+    ///         Utc.with_ymd_and_hms(2020, 2, 3, 4, 5, 0).unwrap(),
+    ///     );
+    ///     # }
+    /// }
+    /// ```
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Time {
+        /// The `datetime` attribute.
+        pub datetime: Option<String>,
+        /// The text content with collapsed whitespace.
+        pub text: String,
+    }
+
+    lazy_static! {
+        static ref TIME_SELECTOR: Selector = Selector::parse("time").unwrap();
+    }
+
+    impl HtmlExtractor for Time {
+        fn extract(elem: &ElementRef) -> Result<Self, Error> {
+            let time = if elem.value().name() == "time" {
+                *elem
+            } else {
+                elem.select(&TIME_SELECTOR)
+                    .next()
+                    .ok_or(Error::InvalidInput(std::borrow::Cow::Borrowed(
+                        "no `<time>` element is found",
+                    )))?
+            };
+            Ok(Time {
+                datetime: time.value().attr("datetime").map(|d| d.trim().to_owned()),
+                text: normalized_text(&time),
+            })
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    impl Time {
+        /// Converts into a datetime in `tz`.
+        ///
+        /// The `datetime` attribute is parsed as [`parsers::datetime_in`](crate::parsers::datetime_in) does, or as a date at midnight.
+        /// If it is missing or invalid, the text is parsed with `format` (see [`chrono::format::strftime`]),
+        /// which may omit the UTC offset or the time.
+        /// Datetimes without the UTC offset are in the local time of `tz`.
+        pub fn to_datetime_in<Tz: chrono::TimeZone>(
+            &self,
+            format: &str,
+            tz: Tz,
+        ) -> Result<chrono::DateTime<Tz>, crate::parsers::DateTimeError> {
+            use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+            let local = |naive: NaiveDateTime, input: &str| {
+                tz.from_local_datetime(&naive).single().ok_or_else(|| {
+                    crate::parsers::DateTimeError::InvalidLocalTime(input.to_owned())
+                })
+            };
+            if let Some(datetime) = &self.datetime {
+                if let Ok(datetime) = crate::parsers::datetime_in(tz.clone())(datetime) {
+                    return Ok(datetime);
+                }
+                if let Ok(date) = NaiveDate::parse_from_str(datetime, "%Y-%m-%d") {
+                    return local(date.and_time(Default::default()), datetime);
+                }
+            }
+            let text = &*self.text;
+            if let Ok(datetime) = DateTime::parse_from_str(text, format) {
+                return Ok(datetime.with_timezone(&tz));
+            }
+            if let Ok(datetime) = NaiveDateTime::parse_from_str(text, format) {
+                return local(datetime, text);
+            }
+            if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+                return local(date.and_time(Default::default()), text);
+            }
+            Err(crate::parsers::DateTimeError::Invalid(text.to_owned()))
+        }
+        /// Converts into a date, preferring the `datetime` attribute and falling back to parsing the text with `format`.
+        ///
+        /// The date part of the `datetime` attribute is used as written, regardless of its UTC offset.
+        pub fn to_date(
+            &self,
+            format: &str,
+        ) -> Result<chrono::NaiveDate, crate::parsers::DateTimeError> {
+            use chrono::{NaiveDate, NaiveDateTime};
+
+            let from_attr = self
+                .datetime
+                .as_deref()
+                .and_then(|datetime| datetime.get(..10))
+                .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+            if let Some(date) = from_attr {
+                return Ok(date);
+            }
+            let text = &*self.text;
+            NaiveDate::parse_from_str(text, format)
+                .or_else(|_| NaiveDateTime::parse_from_str(text, format).map(|dt| dt.date()))
+                .map_err(|_| crate::parsers::DateTimeError::Invalid(text.to_owned()))
+        }
+    }
+}
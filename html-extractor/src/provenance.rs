@@ -0,0 +1,124 @@
+//! Records of where in a document the extracted data came from.
+//!
+//! Structures with the [`#![provenance]`](../macro.html_extractor.html#struct-options) option implement
+//! [`ProvenanceExtractor`], which extracts data together with a [`Provenance`] that maps each field
+//! to its selector and the paths of the elements it was extracted from.
+
+use crate::{Error, HtmlExtractor};
+use scraper::ElementRef;
+
+/// Where the fields of an extracted structure came from.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Provenance {
+    fields: Vec<FieldProvenance>,
+}
+impl Provenance {
+    /// Returns the provenance of the field.
+    pub fn get(&self, field: &str) -> Option<&FieldProvenance> {
+        self.fields.iter().find(|f| f.field == field)
+    }
+    /// Returns an iterator over the provenances of the fields in the order of definition.
+    pub fn iter(&self) -> impl Iterator<Item = &FieldProvenance> {
+        self.fields.iter()
+    }
+    /// Starts the entries of the fields extracted together, with the selector of their first target.
+    #[doc(hidden)]
+    pub fn __begin(&mut self, fields: &[&'static str], selector: &'static str) {
+        for field in fields {
+            self.fields.push(FieldProvenance {
+                field,
+                selector,
+                nodes: Vec::new(),
+            });
+        }
+    }
+    /// Sets the selector of the last `count` entries to the one of the target actually used, for fallback targets.
+    #[doc(hidden)]
+    pub fn __select(&mut self, count: usize, selector: &'static str) {
+        let start = self.fields.len() - count;
+        for field in &mut self.fields[start..] {
+            field.selector = selector;
+        }
+    }
+    /// Adds the element a value came from to the last `count` entries.
+    #[doc(hidden)]
+    pub fn __node(&mut self, count: usize, elem: &ElementRef) {
+        let path = node_path(elem);
+        let start = self.fields.len() - count;
+        for field in &mut self.fields[start..] {
+            field.nodes.push(path.clone());
+        }
+    }
+}
+
+/// Where a field came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldProvenance {
+    /// The name of the field.
+    pub field: &'static str,
+    /// The selector of the field, with aliases resolved.
+    /// With fallback targets given with `else`, it is the one of the target used.
+    pub selector: &'static str,
+    /// The paths of the elements the data was extracted from.
+    ///
+    /// They are recorded while the data is extracted, so they are the elements the values actually came from,
+    /// after the navigations like `closest ".." of ..`, `skip`, `limit` and `filter with ..` are applied.
+    /// For collected fields, each item corresponds to the path at the same index.
+    /// For `optional` and `presence of ..` fields, it is empty if no element matched.
+    ///
+    /// The paths are selectors like `html > body:nth-child(2) > div:nth-child(3)`, which match only the element.
+    pub nodes: Vec<String>,
+}
+
+/// A trait for extracting data together with its [`Provenance`].
+///
+/// It is implemented by [`html_extractor!`](../macro.html_extractor.html) for structures with the `#![provenance]` option.
+/// ```
+/// use html_extractor::{html_extractor, provenance::ProvenanceExtractor};
+/// html_extractor! {
+///     Product {
+///         #![provenance]
+///         price: usize = (text of ".price"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"<div><p>Coffee</p><p class="price">100</p></div>"#;
+///     let (product, provenance) = Product::extract_with_provenance_from_str(input).unwrap();
+///     assert_eq!(product.price, 100);
+///
+///     let price = provenance.get("price").unwrap();
+///     assert_eq!(price.selector, ".price");
+///     assert_eq!(price.nodes, vec!["html > body:nth-child(2) > div:nth-child(1) > p:nth-child(2)"]);
+/// }
+/// ```
+pub trait ProvenanceExtractor: HtmlExtractor {
+    /// Extracts data from [`scraper::element_ref::ElementRef`] and records where it came from.
+    fn extract_with_provenance(elem: &ElementRef) -> Result<(Self, Provenance), Error>;
+    /// Parses HTML string, extracts data from it and records where it came from.
+    fn extract_with_provenance_from_str(html_str: &str) -> Result<(Self, Provenance), Error> {
+        let html = scraper::Html::parse_document(html_str);
+        Self::extract_with_provenance(&html.root_element())
+    }
+}
+
+/// Returns a selector that matches only `elem`, from the root element of the document.
+pub fn node_path(elem: &ElementRef) -> String {
+    let mut path = elem
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .collect::<Vec<_>>();
+    path.reverse();
+    path.push(*elem);
+    path.iter()
+        .enumerate()
+        .map(|(depth, e)| {
+            if depth == 0 {
+                return e.value().name().to_owned();
+            }
+            let nth = e.prev_siblings().filter_map(ElementRef::wrap).count() + 1;
+            format!("{}:nth-child({})", e.value().name(), nth)
+        })
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
@@ -0,0 +1,250 @@
+//! Rendering of elements into readable text.
+//!
+//! [`html_to_text`] is what the [`rendered_text of ..` target](../macro.html_extractor.html#target-specifier) extracts.
+//! With the `markdown` feature, [`html_to_markdown`] converts the element into Markdown
+//! for the [`inner_markdown of ..` target](../macro.html_extractor.html#target-specifier).
+//! With the `sanitize` feature, [`sanitized_html`] cleans the inner HTML of the element
+//! for the [`sanitized_html of ..` target](../macro.html_extractor.html#target-specifier).
+
+use ego_tree::iter::Edge;
+use ego_tree::NodeRef;
+use scraper::node::Node;
+use scraper::ElementRef;
+
+/// The elements that are separated from the others with a blank line.
+const PARAGRAPHS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "pre",
+    "hr",
+];
+/// The elements that start on a new line.
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "caption",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "legend",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "section",
+    "summary",
+    "table",
+    "tr",
+    "ul",
+];
+/// The elements whose contents are not rendered.
+const HIDDEN: &[&str] = &["head", "script", "style", "template", "noscript"];
+
+/// Renders the contents of the element into plain text, roughly as a browser shows it.
+///
+/// * Runs of whitespace are collapsed into a single space, except in `<pre>`.
+/// * `<br>` is a line break, and blocks like `<div>` and `<li>` start on new lines.
+/// * Paragraphs like `<p>`, headings and `<pre>` are separated with a blank line.
+/// * The cells in a table row are separated with a tab.
+/// * The contents of `<script>`, `<style>`, `<template>` and `<noscript>` are skipped.
+///
+/// The leading and trailing whitespace of the lines and of the whole text is removed.
+/// ```
+/// use html_extractor::render::html_to_text;
+/// let html = scraper::Html::parse_fragment(
+///     "<h1>Title</h1><p>First   line<br>second line</p><ul><li>one</li><li>two</li></ul>",
+/// );
+/// assert_eq!(
+///     html_to_text(&html.root_element()),
+///     "Title\n\nFirst line\nsecond line\n\none\ntwo",
+/// );
+/// ```
+pub fn html_to_text(elem: &ElementRef) -> String {
+    let mut renderer = Renderer::default();
+    // the tree is walked without recursion, so that deeply nested elements cannot overflow the stack
+    for edge in elem.traverse() {
+        match edge {
+            Edge::Open(node) if node.id() != elem.id() => renderer.open(node),
+            Edge::Close(node) if node.id() != elem.id() => renderer.close(node),
+            _ => {}
+        }
+    }
+    renderer.finish()
+}
+
+/// Converts the contents of the element into Markdown with [`html2md`].
+///
+/// The leading and trailing whitespace of the result is removed.
+///
+/// This function is available only when the `markdown` feature is enabled.
+/// ```
+/// use html_extractor::render::html_to_markdown;
+/// let html = scraper::Html::parse_fragment(
+///     r#"<h1>Title</h1><p>See <a href="/docs">the <b>docs</b></a>.</p><ul><li>one</li><li>two</li></ul>"#,
+/// );
+/// assert_eq!(
+///     html_to_markdown(&html.root_element()),
+///     "Title\n==========\n\nSee [the **docs**](/docs).\n\n* one\n* two",
+/// );
+/// ```
+#[cfg(feature = "markdown")]
+pub fn html_to_markdown(elem: &ElementRef) -> String {
+    html2md::parse_html(&elem.inner_html()).trim().to_owned()
+}
+
+/// Sanitizes the inner HTML of the element with [`ammonia`], so that it can be safely embedded in other pages.
+///
+/// The default [`ammonia`] policy is used:
+/// scripts, styles, event handlers and `javascript:` URLs are removed, and only the harmless tags and attributes are kept.
+///
+/// This function is available only when the `sanitize` feature is enabled.
+/// ```
+/// use html_extractor::render::sanitized_html;
+/// let html = scraper::Html::parse_fragment(
+///     r#"<p onclick="steal()">Hello, <b>world</b><script>steal()</script></p>"#,
+/// );
+/// assert_eq!(
+///     sanitized_html(&html.root_element()),
+///     "<p>Hello, <b>world</b></p>",
+/// );
+/// ```
+#[cfg(feature = "sanitize")]
+pub fn sanitized_html(elem: &ElementRef) -> String {
+    ammonia::clean(&elem.inner_html())
+}
+
+#[derive(Default)]
+struct Renderer {
+    text: String,
+    /// The number of line breaks required before the next text, which the breaks already written count toward.
+    pending_breaks: usize,
+    /// The number of the open `<pre>` elements.
+    pre_depth: usize,
+    /// The number of the open elements in a hidden element, including itself.
+    hidden_depth: usize,
+}
+impl Renderer {
+    fn open(&mut self, node: NodeRef<Node>) {
+        let elem = match node.value() {
+            Node::Text(text) if self.hidden_depth == 0 => {
+                return self.push_text(text, self.pre_depth > 0)
+            }
+            Node::Element(elem) => elem,
+            _ => return,
+        };
+        let name = elem.name();
+        if self.hidden_depth > 0 || HIDDEN.contains(&name) {
+            self.hidden_depth += 1;
+            return;
+        }
+        if name == "br" {
+            self.trim_end();
+            self.text.push('\n');
+            return;
+        }
+        if (name == "td" || name == "th")
+            && node.prev_siblings().any(|node| node.value().is_element())
+        {
+            self.push_text("\t", true);
+        }
+        if name == "pre" {
+            self.pre_depth += 1;
+        }
+        self.request_breaks(breaks(name));
+    }
+    fn close(&mut self, node: NodeRef<Node>) {
+        let name = match node.value() {
+            Node::Element(elem) => elem.name(),
+            _ => return,
+        };
+        if self.hidden_depth > 0 {
+            self.hidden_depth -= 1;
+            return;
+        }
+        if name == "pre" {
+            self.pre_depth -= 1;
+        }
+        self.request_breaks(breaks(name));
+    }
+    fn request_breaks(&mut self, breaks: usize) {
+        self.pending_breaks = self.pending_breaks.max(breaks);
+    }
+    fn push_text(&mut self, text: &str, pre: bool) {
+        let collapsed;
+        let mut text = if pre {
+            text
+        } else {
+            collapsed = collapse_whitespace(text);
+            &collapsed
+        };
+        if text.is_empty() {
+            return;
+        }
+        if !pre {
+            // a space at the start of a line or a cell, or after another space, is not rendered
+            if self.pending_breaks > 0
+                || self.text.is_empty()
+                || self.text.ends_with(&[' ', '\t', '\n'][..])
+            {
+                text = text.trim_start_matches(' ');
+                if text.is_empty() {
+                    return;
+                }
+            }
+        }
+        if self.pending_breaks > 0 && !self.text.is_empty() {
+            self.trim_end();
+            let written = self.text.len() - self.text.trim_end_matches('\n').len();
+            for _ in written..self.pending_breaks {
+                self.text.push('\n');
+            }
+        }
+        self.pending_breaks = 0;
+        self.text.push_str(text);
+    }
+    /// Removes the spaces at the end of the line.
+    fn trim_end(&mut self) {
+        let len = self.text.trim_end_matches(&[' ', '\t'][..]).len();
+        self.text.truncate(len);
+    }
+    fn finish(self) -> String {
+        self.text.trim().to_owned()
+    }
+}
+
+/// The number of line breaks around the element.
+fn breaks(name: &str) -> usize {
+    if PARAGRAPHS.contains(&name) {
+        2
+    } else if BLOCKS.contains(&name) {
+        1
+    } else {
+        0
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    for (i, word) in text.split(|c: char| c.is_ascii_whitespace()).enumerate() {
+        if i > 0 && !collapsed.ends_with(' ') {
+            collapsed.push(' ');
+        }
+        collapsed.push_str(word);
+    }
+    collapsed
+}
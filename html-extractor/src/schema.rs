@@ -0,0 +1,484 @@
+//! Extraction rules loaded at runtime.
+//!
+//! A [`Schema`] describes the fields to extract with the same vocabulary as
+//! [`#[derive(HtmlExtractor)]`](crate::HtmlExtractor), and produces a [`serde_json::Value`].
+//! It implements [`Deserialize`](serde::Deserialize), so it can be loaded from JSON with [`Schema::from_json`],
+//! or from any other format serde supports, such as YAML with `serde_yaml`.
+//!
+//! This module is available only when the `schema` feature is enabled.
+//! ```
+//! use html_extractor::schema::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_json(r#"{
+//!     "title": { "text": "h1" },
+//!     "products": {
+//!         "elem": ".product",
+//!         "collect": true,
+//!         "fields": {
+//!             "name": { "text": ".name", "normalize_whitespace": true },
+//!             "price": { "text": ".price", "replace": [[",", ""]], "capture": "(\\d+)", "type": "integer" },
+//!             "url": { "attr": "href", "selector": "a" },
+//!             "sold_out": { "presence": ".sold-out" }
+//!         }
+//!     }
+//! }"#).unwrap();
+//!
+//! let value = schema.extract_from_str(r#"
+//!     <h1>Shop</h1>
+//!     <div class="product"><a href="/a"><span class="name">Coffee  beans</span></a><span class="price">1,200 yen</span></div>
+//!     <div class="product"><a href="/b"><span class="name">Tea</span></a><span class="price">800 yen</span><span class="sold-out"></span></div>
+//! "#).unwrap();
+//! assert_eq!(value, json!({
+//!     "title": "Shop",
+//!     "products": [
+//!         { "name": "Coffee beans", "price": 1200, "url": "/a", "sold_out": false },
+//!         { "name": "Tea", "price": 800, "url": "/b", "sold_out": true },
+//!     ],
+//! }));
+//! ```
+//!
+//! # Fields
+//! A schema is an object that maps the field names to the rules. The keys of a rule are:
+//!
+//! | Key                                              | Meaning                                                     |
+//! |--------------------------------------------------|-------------------------------------------------------------|
+//! | `text`, `inner_html`, `presence`                 | The target, as the specifiers of `html_extractor!`          |
+//! | `attr`, `style`                                  | The attribute or the property, with the target `selector`   |
+//! | `elem`, `shadow`                                 | The target, with the nested schema `fields`                 |
+//! | `nth`                                            | The index of the text node for `text`                       |
+//! | `replace`                                        | A list of `["from", "to"]` pairs                            |
+//! | `normalize_whitespace`, `lowercase`, `uppercase` | Transforms, applied in this order after `replace`           |
+//! | `capture`                                        | A regex; one group gives a value, more give an array        |
+//! | `collect`, `optional`, `unique`                  | The collector, as the specifiers of `html_extractor!`       |
+//! | `type`                                           | `"string"` (default), `"integer"`, `"float"` or `"boolean"` |
+//!
+//! Exactly one target must be given. The fields in the output object are sorted by name.
+//!
+//! The selectors and the regexes are compiled once for each source string and shared by all the schemas,
+//! so loading many schemas with the same patterns doesn't compile them again.
+
+use crate::error::Error;
+use crate::options::ExtractOptions;
+use regex::Regex;
+use scraper::{ElementRef, Selector};
+use serde::Deserialize;
+use serde_json::{Map, Number, Value};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::sync::{Mutex, PoisonError};
+use thiserror::Error;
+
+lazy_static::lazy_static! {
+    /// The compiled selectors and regexes, keyed by the source strings.
+    static ref SELECTORS: Mutex<HashMap<String, Selector>> = Mutex::new(HashMap::new());
+    static ref REGEXES: Mutex<HashMap<String, Regex>> = Mutex::new(HashMap::new());
+}
+
+/// Compiles `source` with `compile`, or returns the one compiled before.
+/// The lock is not held while compiling, so two threads may compile the same source at once, and the first one is kept.
+fn cached<T: Clone, E>(
+    cache: &Mutex<HashMap<String, T>>,
+    source: &str,
+    compile: impl FnOnce(&str) -> Result<T, E>,
+) -> Result<T, E> {
+    if let Some(value) = cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(source)
+    {
+        return Ok(value.clone());
+    }
+    let value = compile(source)?;
+    Ok(cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(source.to_owned())
+        .or_insert(value)
+        .clone())
+}
+
+/// A set of extraction rules. See the [module documentation](self).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "BTreeMap<String, RawField>")]
+pub struct Schema {
+    fields: Vec<Field>,
+}
+
+/// An error in a schema.
+#[derive(Debug, Error)]
+pub enum SchemaError {
+    /// The JSON is invalid or doesn't have the structure of a schema.
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    /// A field is invalid.
+    #[error("field `{field}`: {message}")]
+    InvalidField {
+        /// The path to the field, joined with `.`.
+        field: String,
+        /// What is wrong.
+        message: String,
+    },
+}
+
+impl Schema {
+    /// Loads a schema from JSON.
+    pub fn from_json(json: &str) -> Result<Schema, SchemaError> {
+        let fields: BTreeMap<String, RawField> = serde_json::from_str(json)?;
+        Schema::try_from(fields)
+    }
+    /// Extracts the fields from [`scraper::element_ref::ElementRef`] into a JSON object.
+    pub fn extract(&self, elem: &ElementRef) -> Result<Value, Error> {
+        self.extract_with_options(elem, &ExtractOptions::new())
+    }
+    /// Extracts the fields into a JSON object within the limits of `options`,
+    /// as [`HtmlExtractor::extract_with_options`](crate::HtmlExtractor::extract_with_options) does.
+    pub fn extract_with_options(
+        &self,
+        elem: &ElementRef,
+        options: &ExtractOptions,
+    ) -> Result<Value, Error> {
+        let mut object = Map::new();
+        for field in &self.fields {
+            options.check()?;
+            object.insert(field.name.clone(), field.extract(elem, options)?);
+        }
+        Ok(Value::Object(object))
+    }
+    /// Parses HTML string and extracts the fields from it into a JSON object.
+    pub fn extract_from_str(&self, html_str: &str) -> Result<Value, Error> {
+        let html = scraper::Html::parse_document(html_str);
+        self.extract(&html.root_element())
+    }
+}
+
+impl TryFrom<BTreeMap<String, RawField>> for Schema {
+    type Error = SchemaError;
+    fn try_from(fields: BTreeMap<String, RawField>) -> Result<Schema, SchemaError> {
+        compile(fields, "")
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[doc(hidden)]
+pub struct RawField {
+    text: Option<String>,
+    nth: Option<usize>,
+    attr: Option<String>,
+    style: Option<String>,
+    selector: Option<String>,
+    elem: Option<String>,
+    shadow: Option<String>,
+    inner_html: Option<String>,
+    presence: Option<String>,
+    fields: Option<BTreeMap<String, RawField>>,
+    #[serde(default)]
+    replace: Vec<(String, String)>,
+    #[serde(default)]
+    normalize_whitespace: bool,
+    #[serde(default)]
+    lowercase: bool,
+    #[serde(default)]
+    uppercase: bool,
+    capture: Option<String>,
+    #[serde(default)]
+    collect: bool,
+    #[serde(default)]
+    optional: bool,
+    #[serde(default)]
+    unique: bool,
+    #[serde(rename = "type", default)]
+    ty: ValueType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ValueType {
+    #[default]
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    selector_str: String,
+    selector: Selector,
+    target: Target,
+    replace: Vec<(String, String)>,
+    normalize_whitespace: bool,
+    lowercase: bool,
+    uppercase: bool,
+    capture: Option<Regex>,
+    collector: Collector,
+    ty: ValueType,
+}
+
+#[derive(Debug, Clone)]
+enum Target {
+    Text(usize),
+    Attr(String),
+    Style(String),
+    Elem { shadow: bool, schema: Schema },
+    InnerHtml,
+    Presence,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Collector {
+    First,
+    Collect,
+    Optional,
+    Unique,
+}
+
+fn compile(fields: BTreeMap<String, RawField>, parent: &str) -> Result<Schema, SchemaError> {
+    let fields = fields
+        .into_iter()
+        .map(|(name, raw)| {
+            let path = if parent.is_empty() {
+                name.clone()
+            } else {
+                format!("{}.{}", parent, name)
+            };
+            let invalid = |message: &str| SchemaError::InvalidField {
+                field: path.clone(),
+                message: message.to_owned(),
+            };
+
+            let mut targets = Vec::new();
+            if let Some(s) = raw.text {
+                targets.push((s, Target::Text(raw.nth.unwrap_or(0))));
+            }
+            if let Some(s) = raw.inner_html {
+                targets.push((s, Target::InnerHtml));
+            }
+            if let Some(s) = raw.presence {
+                targets.push((s, Target::Presence));
+            }
+            let target_selector = raw.selector;
+            let selector = || {
+                target_selector
+                    .clone()
+                    .ok_or_else(|| invalid("`attr` and `style` require `selector`"))
+            };
+            if let Some(a) = raw.attr {
+                targets.push((selector()?, Target::Attr(a)));
+            }
+            if let Some(p) = raw.style {
+                targets.push((selector()?, Target::Style(p)));
+            }
+            for (s, shadow) in [(raw.elem, false), (raw.shadow, true)] {
+                if let Some(s) = s {
+                    let fields = raw
+                        .fields
+                        .clone()
+                        .ok_or_else(|| invalid("`elem` and `shadow` require `fields`"))?;
+                    let schema = compile(fields, &path)?;
+                    targets.push((s, Target::Elem { shadow, schema }));
+                }
+            }
+            if targets.len() != 1 {
+                return Err(invalid(
+                    "exactly one of `text`, `attr`, `style`, `elem`, `shadow`, `inner_html` and `presence` is required",
+                ));
+            }
+            let (selector_str, target) = targets.remove(0);
+            let selector = cached(&SELECTORS, &selector_str, |s| {
+                Selector::parse(s).map_err(|e| format!("{:?}", e))
+            })
+            .map_err(|e| invalid(&format!("cannot parse the selector: {}", e)))?;
+            let capture = raw
+                .capture
+                .map(|r| cached(&REGEXES, &r, Regex::new))
+                .transpose()
+                .map_err(|e| invalid(&format!("cannot parse the regex: {}", e)))?;
+
+            let collector = match (raw.collect, raw.optional, raw.unique) {
+                (false, false, false) => Collector::First,
+                (true, false, false) => Collector::Collect,
+                (false, true, false) => Collector::Optional,
+                (false, false, true) => Collector::Unique,
+                _ => return Err(invalid("only one of `collect`, `optional` and `unique` can be used")),
+            };
+            let is_text = matches!(
+                target,
+                Target::Text(_) | Target::Attr(_) | Target::Style(_) | Target::InnerHtml
+            );
+            let transforms = !raw.replace.is_empty()
+                || raw.normalize_whitespace
+                || raw.lowercase
+                || raw.uppercase;
+            if !is_text && (transforms || capture.is_some() || raw.ty != ValueType::String) {
+                return Err(invalid(
+                    "transforms, `capture` and `type` cannot be used with `elem`, `shadow` and `presence`",
+                ));
+            }
+            if let Target::Presence = target {
+                if !matches!(collector, Collector::First) {
+                    return Err(invalid("collectors cannot be used with `presence`"));
+                }
+            }
+            if raw.nth.is_some() && !matches!(target, Target::Text(_)) {
+                return Err(invalid("`nth` can be used only with `text`"));
+            }
+            if raw.fields.is_some() && !matches!(target, Target::Elem { .. }) {
+                return Err(invalid("`fields` can be used only with `elem` and `shadow`"));
+            }
+
+            Ok(Field {
+                name,
+                selector_str,
+                selector,
+                target,
+                replace: raw.replace,
+                normalize_whitespace: raw.normalize_whitespace,
+                lowercase: raw.lowercase,
+                uppercase: raw.uppercase,
+                capture,
+                collector,
+                ty: raw.ty,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Schema { fields })
+}
+
+impl Field {
+    fn error(&self, message: String) -> Error {
+        Error::InvalidInput(Cow::Owned(format!(
+            "extracting the data of field `{}`, {}",
+            self.name, message
+        )))
+    }
+
+    fn extract(&self, elem: &ElementRef, options: &ExtractOptions) -> Result<Value, Error> {
+        let mut elems = elem.select(&self.selector);
+        if let Target::Presence = self.target {
+            return Ok(Value::Bool(elems.next().is_some()));
+        }
+        let not_found = || {
+            self.error(format!(
+                "no element matched the selector `{}`",
+                self.selector_str
+            ))
+        };
+        match self.collector {
+            Collector::First => self.extract_elem(&elems.next().ok_or_else(not_found)?, options),
+            Collector::Optional => match elems.next() {
+                Some(e) => self.extract_elem(&e, options),
+                None => Ok(Value::Null),
+            },
+            Collector::Unique => {
+                let first = elems.next().ok_or_else(not_found)?;
+                let rest_count = elems.count();
+                if rest_count > 0 {
+                    return Err(self.error(format!(
+                        "{} elements matched the selector, but exactly one is expected",
+                        rest_count + 1
+                    )));
+                }
+                self.extract_elem(&first, options)
+            }
+            Collector::Collect => {
+                let mut items = Vec::new();
+                for e in elems {
+                    options.check()?;
+                    items.push(self.extract_elem(&e, options)?);
+                }
+                Ok(Value::Array(items))
+            }
+        }
+    }
+
+    fn extract_elem(&self, elem: &ElementRef, options: &ExtractOptions) -> Result<Value, Error> {
+        let data = match &self.target {
+            Target::Text(nth) => elem
+                .text()
+                .nth(*nth)
+                .ok_or_else(|| self.error(format!("{}th text node is not found", nth)))?
+                .trim()
+                .to_owned(),
+            Target::Attr(attr) => elem
+                .value()
+                .attr(attr)
+                .ok_or_else(|| self.error(format!("attribute `{}` is not found", attr)))?
+                .to_owned(),
+            Target::Style(property) => crate::style::inline_style(elem, property)
+                .ok_or_else(|| self.error(format!("style property `{}` is not found", property)))?,
+            Target::InnerHtml => elem.inner_html().trim().to_owned(),
+            Target::Elem { shadow, schema } => {
+                let root = if *shadow {
+                    crate::shadow_root(elem).ok_or_else(|| {
+                        self.error("the element has no declarative shadow root".to_owned())
+                    })?
+                } else {
+                    *elem
+                };
+                return schema.extract_with_options(&root, options);
+            }
+            Target::Presence => return Ok(Value::Bool(true)),
+        };
+
+        let mut data = data;
+        for (from, to) in &self.replace {
+            data = data.replace(&**from, to);
+        }
+        if self.normalize_whitespace {
+            data = data.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        if self.lowercase {
+            data = data.to_lowercase();
+        }
+        if self.uppercase {
+            data = data.to_uppercase();
+        }
+
+        let regex = match &self.capture {
+            Some(regex) => regex,
+            None => return self.parse(&data),
+        };
+        let caps = regex
+            .captures(&data)
+            .ok_or_else(|| self.error("nothing is captured with regex".to_owned()))?;
+        let groups = (1..caps.len())
+            .map(|i| {
+                let group = caps
+                    .get(i)
+                    .ok_or_else(|| self.error(format!("capture group {} did not match", i)))?;
+                self.parse(group.as_str())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match <[Value; 1]>::try_from(groups) {
+            Ok([value]) => Ok(value),
+            Err(groups) => Ok(Value::Array(groups)),
+        }
+    }
+
+    fn parse(&self, data: &str) -> Result<Value, Error> {
+        let cannot_parse =
+            |e: &dyn std::fmt::Display| self.error(format!("cannot parse `{}`: {}", data, e));
+        Ok(match self.ty {
+            ValueType::String => Value::String(data.to_owned()),
+            ValueType::Integer => Value::Number(
+                data.trim()
+                    .parse::<i64>()
+                    .map_err(|e| cannot_parse(&e))?
+                    .into(),
+            ),
+            ValueType::Float => {
+                let f = data.trim().parse::<f64>().map_err(|e| cannot_parse(&e))?;
+                Value::Number(
+                    Number::from_f64(f).ok_or_else(|| cannot_parse(&"not a finite number"))?,
+                )
+            }
+            ValueType::Boolean => {
+                Value::Bool(data.trim().parse::<bool>().map_err(|e| cannot_parse(&e))?)
+            }
+        })
+    }
+}
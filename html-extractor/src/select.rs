@@ -0,0 +1,38 @@
+//! Specialized lookups for simple selectors.
+//!
+//! [`html_extractor!`](crate::html_extractor) uses them instead of [`ElementRef::select`] for the selectors
+//! that consist of a single id (`#foo`) or a single class (`.foo`), which skips the general selector matching.
+//! The elements are the same as the ones `ElementRef::select` yields, in the same order.
+//!
+//! It also parses the selectors built at extraction time, like `text of format!("#row-{}", id)`.
+
+use crate::Error;
+use scraper::{ElementRef, Selector};
+
+/// Returns an iterator over the descendants of `elem` with the id, like `elem.select("#id")`.
+pub fn by_id<'a>(elem: &ElementRef<'a>, id: &'static str) -> impl Iterator<Item = ElementRef<'a>> {
+    descendants(elem).filter(move |e| e.value().id() == Some(id))
+}
+
+/// Returns an iterator over the descendants of `elem` with the class, like `elem.select(".class")`.
+pub fn by_class<'a>(
+    elem: &ElementRef<'a>,
+    class: &'static str,
+) -> impl Iterator<Item = ElementRef<'a>> {
+    descendants(elem).filter(move |e| e.value().classes().any(|c| c == class))
+}
+
+fn descendants<'a>(elem: &ElementRef<'a>) -> impl Iterator<Item = ElementRef<'a>> {
+    // `descendants` starts with the element itself, which `select` doesn't match
+    elem.descendants().skip(1).filter_map(ElementRef::wrap)
+}
+
+/// Parses a selector built at extraction time, which cannot be checked at compile time.
+pub fn parse(selector: &str) -> Result<Selector, Error> {
+    Selector::parse(selector).map_err(|err| {
+        Error::InvalidInput(std::borrow::Cow::Owned(format!(
+            "cannot parse the selector `{}`: {:?}",
+            selector, err
+        )))
+    })
+}
@@ -0,0 +1,383 @@
+//! The CSS of documents.
+//!
+//! [`StyleSheet`] collects and parses the contents of `<style>` elements, and [`inline_style`] reads a property
+//! from the `style` attribute, which is what the [`style[..] of ..` target](../macro.html_extractor.html#target-specifier)
+//! extracts.
+//!
+//! The parser is small and tolerant: it doesn't validate selectors or values, doesn't evaluate media queries,
+//! and skips what it cannot parse.
+//! The rules nested in more than [`MAX_NESTING`] at-rules like `@media` are skipped, too.
+
+use crate::{Error, HtmlExtractor};
+use lazy_static::lazy_static;
+use scraper::{ElementRef, Selector};
+
+/// The style rules in the `<style>` elements.
+///
+/// If the element to extract from is a `<style>` element, only it is used. Otherwise, all the `<style>` elements in it are used
+/// in document order. It is empty if there is none.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor, style::StyleSheet};
+/// html_extractor! {
+///     Page {
+///         styles: StyleSheet = (elem of "head"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"
+///         <head>
+///             <style>
+///                 :root { --primary: #0af; }
+///                 .ad, .popup { display: none !important; }
+///             </style>
+///             <style>
+///                 @media (prefers-color-scheme: dark) {
+///                     :root { --primary: #08c; }
+///                 }
+///             </style>
+///         </head>
+///     "#;
+///     let page = Page::extract_from_str(input).unwrap();
+///     assert_eq!(page.styles.rules.len(), 3);
+///     assert_eq!(page.styles.property(".popup", "display"), Some("none"));
+///
+///     // media queries are not evaluated, so the last rule wins
+///     assert_eq!(page.styles.property(":root", "--primary"), Some("#08c"));
+///     let light = page.styles.rules_for(":root").find(|r| r.media.is_none()).unwrap();
+///     assert_eq!(light.get("--primary"), Some("#0af"));
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StyleSheet {
+    /// The style rules in document order.
+    ///
+    /// The rules in conditional group rules like `@media` and `@supports` are included.
+    /// Other at-rules like `@font-face` and `@keyframes` are skipped.
+    pub rules: Vec<CssRule>,
+}
+impl StyleSheet {
+    /// Parses the CSS.
+    pub fn parse(css: &str) -> StyleSheet {
+        let mut rules = Vec::new();
+        parse_rules(&strip_comments(css), None, 0, &mut rules);
+        StyleSheet { rules }
+    }
+    /// Returns an iterator over the rules whose selector list contains `selector`.
+    ///
+    /// Selectors are compared as text, ignoring the differences in whitespace.
+    pub fn rules_for<'a>(&'a self, selector: &str) -> impl Iterator<Item = &'a CssRule> {
+        let selector = normalize_whitespace(selector);
+        self.rules
+            .iter()
+            .filter(move |rule| rule.selectors.contains(&selector))
+    }
+    /// Returns the value of the property in the rules for `selector`.
+    ///
+    /// As in the cascade, `!important` declarations win, and then the later declarations win.
+    /// Media queries are not evaluated, and specificity is not considered.
+    pub fn property(&self, selector: &str, property: &str) -> Option<&str> {
+        cascade(
+            self.rules_for(selector)
+                .flat_map(|rule| rule.declarations.iter()),
+            property,
+        )
+    }
+}
+impl HtmlExtractor for StyleSheet {
+    fn extract(elem: &ElementRef) -> Result<Self, Error> {
+        lazy_static! {
+            static ref STYLE_SELECTOR: Selector = Selector::parse("style").unwrap();
+        }
+        if elem.value().name() == "style" {
+            return Ok(StyleSheet::parse(&elem.text().collect::<String>()));
+        }
+        let css = elem
+            .select(&STYLE_SELECTOR)
+            .map(|style| style.text().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(StyleSheet::parse(&css))
+    }
+}
+
+/// A style rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssRule {
+    /// The selectors in the selector list, with whitespace normalized.
+    pub selectors: Vec<String>,
+    /// The conditions of the enclosing `@media` rules, joined with `and`.
+    pub media: Option<String>,
+    /// The declarations in the order of appearance.
+    pub declarations: Vec<Declaration>,
+}
+impl CssRule {
+    /// Returns the value of the property in the rule.
+    pub fn get(&self, property: &str) -> Option<&str> {
+        cascade(self.declarations.iter(), property)
+    }
+}
+
+/// A property declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    /// The name of the property. It is lowercased, except for custom properties (`--*`).
+    pub property: String,
+    /// The value, without `!important`.
+    pub value: String,
+    /// Whether the declaration is `!important`.
+    pub important: bool,
+}
+
+/// Parses a list of declarations, such as the value of the `style` attribute.
+pub fn parse_declarations(css: &str) -> Vec<Declaration> {
+    split_top_level(&strip_comments(css), ';')
+        .into_iter()
+        // nested rules
+        .filter(|declaration| find_top_level(declaration, &['{']).is_none())
+        .filter_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            let property = property.trim();
+            if property.is_empty() {
+                return None;
+            }
+            let property = if property.starts_with("--") {
+                property.to_owned()
+            } else {
+                property.to_ascii_lowercase()
+            };
+            let mut value = value.trim();
+            let mut important = false;
+            if let Some(i) = value.rfind('!') {
+                if value[i + 1..].trim().eq_ignore_ascii_case("important") {
+                    important = true;
+                    value = value[..i].trim_end();
+                }
+            }
+            Some(Declaration {
+                property,
+                value: value.to_owned(),
+                important,
+            })
+        })
+        .collect()
+}
+
+/// Returns the value of the property in the `style` attribute of the element.
+///
+/// `!important` declarations win, and then the later declarations win.
+/// ```
+/// use html_extractor::{html_extractor, HtmlExtractor};
+/// html_extractor! {
+///     #[derive(Debug, PartialEq)]
+///     Foo {
+///         color: String = (style["color"] of "#foo"),
+///     }
+/// }
+///
+/// fn main() {
+///     let input = r#"<div id="foo" style="COLOR: red; background: url('a;b.png'); color: blue">foo</div>"#;
+///     let foo = Foo::extract_from_str(input).unwrap();
+///     assert_eq!(foo, Foo { color: "blue".to_owned() });
+/// }
+/// ```
+pub fn inline_style(elem: &ElementRef, property: &str) -> Option<String> {
+    let declarations = parse_declarations(elem.value().attr("style")?);
+    cascade(declarations.iter(), property).map(str::to_owned)
+}
+
+/// Returns the value of the winning declaration of the property.
+fn cascade<'a>(
+    declarations: impl Iterator<Item = &'a Declaration>,
+    property: &str,
+) -> Option<&'a str> {
+    declarations
+        .filter(|d| {
+            if property.starts_with("--") {
+                d.property == property
+            } else {
+                d.property.eq_ignore_ascii_case(property)
+            }
+        })
+        .fold(None, |winner: Option<&Declaration>, d| match winner {
+            Some(w) if w.important && !d.important => Some(w),
+            _ => Some(d),
+        })
+        .map(|d| &*d.value)
+}
+
+/// The maximum depth of the at-rules like `@media` whose contents are parsed.
+///
+/// The blocks nested deeper are skipped, so that hostile CSS cannot overflow the stack.
+pub const MAX_NESTING: usize = 32;
+
+fn parse_rules(css: &str, media: Option<&str>, depth: usize, rules: &mut Vec<CssRule>) {
+    let mut rest = css;
+    loop {
+        rest = rest.trim_start();
+        let (i, c) = match find_top_level(rest, &['{', ';']) {
+            Some(found) => found,
+            None => return,
+        };
+        if c == ';' {
+            // statements like `@import` and `@charset`
+            rest = &rest[i + 1..];
+            continue;
+        }
+        let prelude = rest[..i].trim();
+        let end = block_end(rest, i);
+        let body = &rest[i + 1..end];
+        rest = rest.get(end + 1..).unwrap_or("");
+
+        if let Some(at_rule) = prelude.strip_prefix('@') {
+            if depth >= MAX_NESTING {
+                continue;
+            }
+            let (name, condition) = at_rule
+                .split_once(char::is_whitespace)
+                .unwrap_or((at_rule, ""));
+            match &*name.to_ascii_lowercase() {
+                "media" => {
+                    let condition = normalize_whitespace(condition);
+                    let media = match media {
+                        Some(outer) => format!("{} and {}", outer, condition),
+                        None => condition,
+                    };
+                    parse_rules(body, Some(&media), depth + 1, rules);
+                }
+                "supports" | "layer" | "container" | "document" | "scope" => {
+                    parse_rules(body, media, depth + 1, rules)
+                }
+                _ => {}
+            }
+        } else {
+            rules.push(CssRule {
+                selectors: split_top_level(prelude, ',')
+                    .into_iter()
+                    .map(normalize_whitespace)
+                    .filter(|s| !s.is_empty())
+                    .collect(),
+                media: media.map(str::to_owned),
+                declarations: parse_declarations(body),
+            });
+        }
+    }
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut quote = None;
+    let mut chars = css.chars().peekable();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                out.push(c);
+                if c == '\\' {
+                    out.extend(chars.next());
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None if c == '/' && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = None;
+                for c in chars.by_ref() {
+                    if prev == Some('*') && c == '/' {
+                        break;
+                    }
+                    prev = Some(c);
+                }
+                out.push(' ');
+            }
+            None => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+/// Calls `f` with the byte index, the char and the nesting depth of each char outside strings.
+fn scan(css: &str, mut f: impl FnMut(usize, char, usize) -> bool) {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in css.char_indices() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '(' | '[' | '{' => {
+                if f(i, c, depth) {
+                    return;
+                }
+                depth += 1;
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                if f(i, c, depth) {
+                    return;
+                }
+            }
+            _ => {
+                if f(i, c, depth) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn find_top_level(css: &str, targets: &[char]) -> Option<(usize, char)> {
+    let mut found = None;
+    scan(css, |i, c, depth| {
+        if depth == 0 && targets.contains(&c) {
+            found = Some((i, c));
+        }
+        found.is_some()
+    });
+    found
+}
+
+/// Returns the index of the `}` closing the block opened at `open`, or the length if it is not closed.
+fn block_end(css: &str, open: usize) -> usize {
+    let mut end = css.len();
+    scan(&css[open..], |i, c, depth| {
+        if c == '}' && depth == 0 {
+            end = open + i;
+            true
+        } else {
+            false
+        }
+    });
+    end
+}
+
+fn split_top_level(css: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    scan(css, |i, c, depth| {
+        if depth == 0 && c == separator {
+            parts.push(&css[start..i]);
+            start = i + 1;
+        }
+        false
+    });
+    parts.push(&css[start..]);
+    parts
+}
+
+fn normalize_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,154 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::{ElementRef, Selector};
+
+/// The maximum number of suggestions included in an error.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Looks for selectors close to `selector` that match elements in `root`, formatted with notes.
+pub(crate) fn suggest(root: &ElementRef, selector: &str) -> Vec<String> {
+    candidates(root, selector)
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(candidate, note)| format!("`{}` ({})", candidate, note))
+        .collect()
+}
+
+/// Looks for selectors close to `selector` that match elements in `root`, and returns them with notes.
+pub(crate) fn candidates(root: &ElementRef, selector: &str) -> Vec<(String, String)> {
+    let compounds = match split_compounds(selector) {
+        Some(compounds) if !compounds.is_empty() => compounds,
+        _ => return Vec::new(),
+    };
+    let mut suggestions = Vec::new();
+    let mut add = |candidate: String, note: String| {
+        if candidate != selector
+            && !suggestions.iter().any(|(c, _)| *c == candidate)
+            && matches(root, &candidate)
+        {
+            suggestions.push((candidate, note));
+        }
+    };
+
+    // ids and classes with different casing
+    lazy_static! {
+        static ref NAME_REGEX: Regex = Regex::new(r"([#.])([\w-]+)").unwrap();
+    }
+    for caps in NAME_REGEX.captures_iter(selector) {
+        let (kind, name) = (&caps[1], &caps[2]);
+        for elem in root.descendants().filter_map(ElementRef::wrap) {
+            let actual_names: Vec<&str> = match kind {
+                "#" => elem.value().id().into_iter().collect(),
+                _ => elem.value().classes().collect(),
+            };
+            for actual in actual_names {
+                if actual != name && actual.eq_ignore_ascii_case(name) {
+                    let whole = caps.get(0).unwrap();
+                    let candidate = format!(
+                        "{}{}{}{}",
+                        &selector[..whole.start()],
+                        kind,
+                        actual,
+                        &selector[whole.end()..]
+                    );
+                    add(
+                        candidate,
+                        format!("`{}{}` has different casing", kind, actual),
+                    );
+                }
+            }
+        }
+    }
+
+    // the same ids and classes on a different tag
+    for (i, compound) in compounds.iter().enumerate() {
+        let tag_len = match compound.find(['#', '.', '[', ':']) {
+            Some(tag_len) if tag_len > 0 => tag_len,
+            _ => continue,
+        };
+        let with_tag = |tag: &str, rest: &[&str]| {
+            let mut parts = compounds[..i].to_vec();
+            let replaced = format!("{}{}", tag, &compound[tag_len..]);
+            parts.push(&replaced);
+            parts.extend(rest);
+            parts.join(" ")
+        };
+        let without_tag = Selector::parse(&with_tag("", &[])).ok();
+        let mut tags = without_tag
+            .iter()
+            .flat_map(|selector| root.select(selector))
+            .map(|elem| elem.value().name().to_owned())
+            .collect::<Vec<_>>();
+        tags.dedup();
+        for tag in tags {
+            let candidate = with_tag(&tag, &compounds[i + 1..]);
+            add(candidate, format!("the element is `<{}>`", tag));
+        }
+    }
+
+    // the last part only, ignoring the ancestors
+    let last = compounds.last().unwrap();
+    if compounds.len() > 1 {
+        add(last.to_string(), "the ancestors don't match".to_owned());
+    }
+
+    // the ancestors matching without the last parts
+    for len in (1..compounds.len()).rev() {
+        if is_combinator(compounds[len - 1]) {
+            continue;
+        }
+        let candidate = compounds[..len].join(" ");
+        if matches(root, &candidate) {
+            add(
+                candidate,
+                "nothing in it matches the rest of the selector".to_owned(),
+            );
+            break;
+        }
+    }
+
+    suggestions
+}
+
+fn matches(root: &ElementRef, selector: &str) -> bool {
+    Selector::parse(selector).is_ok_and(|selector| root.select(&selector).next().is_some())
+}
+
+fn is_combinator(part: &str) -> bool {
+    matches!(part, ">" | "+" | "~")
+}
+
+/// Splits a selector into compound selectors and combinators.
+///
+/// Returns `None` for selector lists, which are not supported.
+fn split_compounds(selector: &str) -> Option<Vec<&str>> {
+    let mut compounds = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = None;
+    for (i, c) in selector.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '(') => depth += 1,
+            (None, ']' | ')') => depth -= 1,
+            (None, ',') if depth == 0 => return None,
+            (None, c) if depth == 0 && (c.is_whitespace() || "> + ~".contains(c)) => {
+                if let Some(s) = start.take() {
+                    compounds.push(&selector[s..i]);
+                }
+                if !c.is_whitespace() {
+                    compounds.push(&selector[i..i + c.len_utf8()]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        compounds.push(&selector[s..]);
+    }
+    Some(compounds)
+}
@@ -69,6 +69,10 @@ fn test() {
                 inner<br>html
             </div>
             <div id="data16">&lt;</div>
+            <div id="data17">
+                <div>1,000 Items</div>
+                <div>2,000 Items</div>
+            </div>
         "#,
     )
     .unwrap();
@@ -129,6 +133,9 @@ fn test() {
             data16_1: std::cmp::Ordering::Less,
             data16_2: std::cmp::Ordering::Less,
             presence_of_data16: true,
+
+            data17: vec![1000, 2000],
+            data17_1: "1,000 ITEMS".to_owned(),
         }
     );
 }
@@ -182,6 +189,9 @@ html_extractor::html_extractor! {
         }),
 
         presence_of_data16: bool = (presence of "#data16"),
+
+        data17: Vec<usize> = (text of "#data17 > div", collect, replace "," with "", lowercase, replace " items" with ""),
+        (data17_1: String,) = (text of "#data17 > div", uppercase, capture with "^(.*)$"),
     }
     #[derive(Debug, PartialEq)]
     pub(crate) InnerData {
@@ -196,3 +206,4748 @@ fn custom_parser(input: &str) -> Result<std::cmp::Ordering, ()> {
         _ => Err(()),
     }
 }
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn decimal() {
+    use html_extractor::parsers;
+    use rust_decimal::Decimal;
+
+    let data = DecimalData::extract_from_str(
+        r#"
+            <div id="decimal1">1,234,567.89</div>
+            <div id="decimal2">1.234.567,89</div>
+            <div id="decimal3">1 234 567,89</div>
+            <div id="decimal4">1'234'567.89</div>
+            <div id="decimal5">0,10</div>
+            <div id="decimal6">1,000</div>
+            <div id="decimal7">0.1000000000000000000000000001</div>
+            <div id="empty"></div>
+        "#,
+    )
+    .unwrap();
+
+    let expected: Decimal = "1234567.89".parse().unwrap();
+    assert_eq!(
+        data,
+        DecimalData {
+            decimal1: expected,
+            decimal2: expected,
+            decimal3: expected,
+            decimal4: expected,
+            decimal5: "0.10".parse().unwrap(),
+            decimal6: "1000".parse().unwrap(),
+            decimal7: "0.1000000000000000000000000001".parse().unwrap(),
+            optional_decimal: Some("0.10".parse().unwrap()),
+            empty: None,
+        }
+    );
+
+    assert!(parsers::decimal("1.5 EUR").is_err());
+}
+#[cfg(feature = "rust_decimal")]
+html_extractor::html_extractor! {
+    #[derive(Debug, PartialEq)]
+    DecimalData {
+        decimal1: rust_decimal::Decimal = (text of "#decimal1", parse with html_extractor::parsers::decimal),
+        decimal2: rust_decimal::Decimal = (text of "#decimal2", parse with html_extractor::parsers::decimal),
+        decimal3: rust_decimal::Decimal = (text of "#decimal3", parse with html_extractor::parsers::decimal),
+        decimal4: rust_decimal::Decimal = (text of "#decimal4", parse with html_extractor::parsers::decimal),
+        decimal5: rust_decimal::Decimal = (text of "#decimal5", parse with html_extractor::parsers::decimal),
+        decimal6: rust_decimal::Decimal = (text of "#decimal6", parse with html_extractor::parsers::decimal),
+        decimal7: rust_decimal::Decimal = (text of "#decimal7", parse with html_extractor::parsers::decimal),
+        optional_decimal: Option<rust_decimal::Decimal> = (text of "#decimal5", parse with html_extractor::parsers::decimal, optional),
+        empty: Option<rust_decimal::Decimal> = (inner_html of "#empty", parse with html_extractor::parsers::decimal_or_none),
+    }
+}
+
+#[cfg(feature = "chrono-tz")]
+#[test]
+fn datetime() {
+    use chrono::TimeZone;
+    use chrono_tz::Tz;
+    use html_extractor::parsers;
+
+    let berlin = parsers::datetime_in(Tz::Europe__Berlin);
+    assert_eq!(
+        berlin("2020-03-29 01:30").unwrap(),
+        Tz::Europe__Berlin
+            .with_ymd_and_hms(2020, 3, 29, 1, 30, 0)
+            .unwrap()
+    );
+    // skipped by the daylight saving time transition
+    assert!(berlin("2020-03-29 02:30").is_err());
+    assert!(parsers::datetime("2020-03-29 01:30").is_err());
+    assert!(parsers::datetime("yesterday").is_err());
+
+    let zoned = parsers::zoned_datetime(&Tz::UTC);
+    assert_eq!(
+        zoned("2020-05-01T12:00:00+09:00 Europe/Berlin")
+            .unwrap()
+            .to_rfc3339(),
+        "2020-05-01T05:00:00+02:00"
+    );
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn uuid() {
+    use html_extractor::parsers::{self, UuidError};
+
+    let expected = uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    assert_eq!(
+        parsers::uuid("urn:uuid:67E55044-10B1-426F-9247-BB680E5FE0C8").unwrap(),
+        expected
+    );
+    assert_eq!(
+        parsers::uuid("{67e5504410b1426f9247bb680e5fe0c8}").unwrap(),
+        expected
+    );
+    assert!(matches!(
+        parsers::uuid("item-67e55044"),
+        Err(UuidError::NotFound(_))
+    ));
+}
+
+#[cfg(feature = "url")]
+#[test]
+fn url() {
+    use html_extractor::parsers;
+    use url::Url;
+
+    let parse = parsers::url_with_base(Url::parse("https://example.com/a/b").unwrap());
+    assert_eq!(
+        parse("../c?d#e").unwrap().as_str(),
+        "https://example.com/c?d#e"
+    );
+    assert_eq!(
+        parse("//example.org").unwrap().as_str(),
+        "https://example.org/"
+    );
+    assert_eq!(
+        parse("https://[::1").unwrap_err(),
+        url::ParseError::InvalidIpv6Address
+    );
+}
+
+#[test]
+fn contact() {
+    use html_extractor::parsers;
+
+    assert_eq!(
+        parsers::emails("a@b, mailto:foo.bar+baz@mail.example.com, FOO.BAR+BAZ@mail.example.com")
+            .unwrap(),
+        vec![
+            "foo.bar+baz@mail.example.com",
+            "FOO.BAR+BAZ@mail.example.com"
+        ]
+    );
+    assert_eq!(
+        parsers::phone_numbers("192.168.0.1, 2020-5-1, 12-34, tel:555.010.0123, 555-010-0123")
+            .unwrap(),
+        vec!["5550100123"]
+    );
+    assert_eq!(
+        parsers::phone_numbers("電話03-1234-5678、tel:5550100, +81 3 1234 5678").unwrap(),
+        vec!["0312345678", "5550100", "+81312345678"]
+    );
+    // a list of quantities, single long IDs and numbers on different lines
+    assert!(parsers::phone_numbers("Qty: 10 20 30 40")
+        .unwrap()
+        .is_empty());
+    assert!(parsers::phone_numbers("Order 1234567, SKU 98765432")
+        .unwrap()
+        .is_empty());
+    // the digits in a word are not joined to the number
+    assert_eq!(
+        parsers::phone_numbers("ID12345 678-9012").unwrap(),
+        vec!["6789012"]
+    );
+    assert!(parsers::phone_numbers("555\n0100123\n12 345\n6789")
+        .unwrap()
+        .is_empty());
+    assert!(parsers::phone_numbers("555  010  0123").unwrap().is_empty());
+}
+
+#[test]
+fn data_uri() {
+    use html_extractor::parsers::{self, DataUriError};
+
+    let data = parsers::data_uri("data:,A%20brief%20note").unwrap();
+    assert_eq!(data.media_type, "text/plain;charset=US-ASCII");
+    assert_eq!(data.data, b"A brief note");
+    let data = parsers::data_uri("DATA:;charset=utf-8;BASE64,44GC\n").unwrap();
+    assert_eq!(data.mime_type(), "text/plain");
+    assert_eq!(data.data, "あ".as_bytes());
+    assert_eq!(parsers::data_uri("data:;base64,aGk").unwrap().data, b"hi");
+    assert_eq!(
+        parsers::data_uri("data:;base64,a=b"),
+        Err(DataUriError::InvalidBase64)
+    );
+    assert!(matches!(
+        parsers::data_uri("https://example.com/"),
+        Err(DataUriError::NotDataUri(_))
+    ));
+    // the byte offset of `;base64` falls inside a multibyte character
+    let data = parsers::data_uri("data:éééé,x").unwrap();
+    assert_eq!(data.media_type, "éééé");
+    assert_eq!(data.data, b"x");
+    let data = parsers::data_uri("data:text/é;base64,aGk").unwrap();
+    assert_eq!(data.media_type, "text/é");
+    assert_eq!(data.data, b"hi");
+    assert!(parsers::data_uri("dat\u{e9}:,x").is_err());
+}
+
+#[cfg(feature = "whatlang")]
+#[test]
+fn with_language() {
+    use html_extractor::parsers;
+
+    assert_eq!(
+        parsers::with_language("これは日本語の文章です。")
+            .unwrap()
+            .1,
+        whatlang::Lang::Jpn
+    );
+    assert!(parsers::with_language("12345").is_err());
+}
+
+#[test]
+fn multi_page() {
+    use html_extractor::options::{CancellationToken, ExtractOptions};
+    use scraper::Html;
+
+    let main = r#"
+        <div id="data1"><div class="data1-1">1</div></div>
+    "#;
+    let sub = r#"
+        <div id="data1"><div class="data1-1">2</div></div>
+        <div id="data2">3</div>
+    "#;
+
+    assert_eq!(
+        MultiPageData::extract_multi(&[("main", main), ("sub", sub)]).unwrap(),
+        MultiPageData {
+            main: InnerData { data1_1: 1 },
+            sub: InnerData { data1_1: 2 },
+            sub_text: 3,
+        }
+    );
+    // the main page is found by its name
+    assert_eq!(
+        MultiPageData::extract_multi(&[("sub", sub), ("main", main)]).unwrap(),
+        MultiPageData::extract_multi(&[("main", main), ("sub", sub)]).unwrap(),
+    );
+    let message =
+        |pages: &[(&str, &str)]| MultiPageData::extract_multi(pages).unwrap_err().to_string();
+    assert!(message(&[("main", main)]).contains("page `sub` is not given"));
+    assert!(message(&[("sub", sub)]).contains("the main page `main` is not given"));
+    assert!(message(&[]).contains("the main page `main` is not given"));
+    assert!(
+        message(&[("main", main), ("sbu", sub)]).contains("page `sbu` is not used by any field")
+    );
+    assert!(message(&[("main", main), ("sub", sub), ("sub", sub)])
+        .contains("page `sub` is given more than once"));
+
+    let (main, sub) = (Html::parse_document(main), Html::parse_document(sub));
+    let pages = [("main", main.root_element()), ("sub", sub.root_element())];
+    assert!(MultiPageData::extract_multi_from_elements(&pages, &ExtractOptions::new()).is_ok());
+    let token = CancellationToken::new();
+    token.cancel();
+    assert!(matches!(
+        MultiPageData::extract_multi_from_elements(
+            &pages,
+            &ExtractOptions::new().cancellation_token(token)
+        ),
+        Err(html_extractor::Error::Cancelled)
+    ));
+}
+html_extractor::html_extractor! {
+    #[derive(Debug, PartialEq)]
+    MultiPageData {
+        main: InnerData = (elem of "#data1"),
+        sub: InnerData = (elem of "#data1", from page "sub"),
+        sub_text: usize = (text of "#data2", from page "sub"),
+    }
+}
+
+#[test]
+fn mixin() {
+    let input = r#"
+        <h1>title</h1>
+        <span class="created">1</span>
+        <span class="updated">2</span>
+        <a class="author">foo</a>
+    "#;
+    assert_eq!(
+        MixinData::extract_from_str(input).unwrap(),
+        MixinData {
+            title: "title".to_owned(),
+            created: 1,
+            updated: 2,
+            author: "foo".to_owned(),
+        }
+    );
+}
+html_extractor::html_extractor! {
+    mixin Timestamps {
+        created: usize = (text of ".created"),
+        updated: usize = (text of ".updated"),
+    }
+    mixin Metadata {
+        use Timestamps;
+        author: String = (text of ".author"),
+    }
+    #[derive(Debug, PartialEq)]
+    MixinData {
+        title: String = (text of "h1"),
+        use Metadata
+    }
+}
+
+#[test]
+fn unique() {
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        UniqueData {
+            foo: usize = (text of ".foo", unique),
+        }
+    }
+    assert_eq!(
+        UniqueData::extract_from_str(r#"<div class="foo">1</div>"#).unwrap(),
+        UniqueData { foo: 1 }
+    );
+    assert!(UniqueData::extract_from_str("").is_err());
+    let err = UniqueData::extract_from_str(
+        r#"<div class="foo">1</div><div class="foo">2</div><div class="foo">3</div>"#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("field `foo`"));
+    assert!(err.contains("3 elements matched"));
+}
+
+#[test]
+fn suggestions() {
+    html_extractor::html_extractor! {
+        #[derive(Debug)]
+        SuggestionData {
+            _foo: String = (text of "#content ul.items > li.item a"),
+        }
+    }
+    let options = html_extractor::options::ExtractOptions::new().suggestions(true);
+    let message = |input| {
+        SuggestionData::extract_from_str_with_options(input, &options)
+            .unwrap_err()
+            .to_string()
+    };
+
+    // suggestions are disabled by default
+    let err = SuggestionData::extract_from_str(
+        r#"<ul class="Items"><li class="item"><a>1</a></li></ul>"#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(!err.contains("did you mean"), "{}", err);
+    // class with different casing
+    let err =
+        message(r#"<div id="content"><ul class="Items"><li class="item"><a>1</a></li></ul></div>"#);
+    assert!(err.contains("`#content ul.Items > li.item a`"), "{}", err);
+    // different tag
+    let err =
+        message(r#"<div id="content"><ol class="items"><li class="item"><a>1</a></li></ol></div>"#);
+    assert!(err.contains("`#content ol.items > li.item a`"), "{}", err);
+    // the ancestors don't match
+    let err =
+        message(r#"<div id="main"><ul class="items"><li class="item"><a>1</a></li></ul></div>"#);
+    assert!(err.contains("`a`"), "{}", err);
+    // only the ancestors match
+    let err = message(r#"<div id="content"><ul class="items"><li class="item">1</li></ul></div>"#);
+    assert!(err.contains("`#content ul.items > li.item`"), "{}", err);
+    // nothing is close
+    let err = message("<p>1</p>");
+    assert!(!err.contains("did you mean"), "{}", err);
+}
+
+#[test]
+fn fragments() {
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        FragmentData {
+            foo: usize = (text of "html > p"),
+        }
+    }
+    // the document parser wraps the elements in `<body>`
+    assert!(FragmentData::extract_from_str("<p>1</p>").is_err());
+
+    let results = FragmentData::extract_from_fragments(&["<p>1</p>", "<div>2</div>", "<p>3</p>"]);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), &FragmentData { foo: 1 });
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap(), &FragmentData { foo: 3 });
+}
+
+#[test]
+fn bytes() {
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        BytesData {
+            foo: String = (text of "p"),
+        }
+    }
+    assert_eq!(
+        BytesData::extract_from_bytes("<p>こんにちは</p>".as_bytes()).unwrap(),
+        BytesData {
+            foo: "こんにちは".to_owned()
+        }
+    );
+    assert_eq!(
+        BytesData::extract_from_bytes(b"<p>a\xffb</p>").unwrap(),
+        BytesData {
+            foo: "a\u{fffd}b".to_owned()
+        }
+    );
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn bytes_encoding() {
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        EncodingData {
+            foo: String = (text of "p"),
+        }
+    }
+    // "こんにちは" in Shift_JIS
+    let mut input = b"<meta charset=\"Shift_JIS\"><p>".to_vec();
+    input.extend_from_slice(b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd</p>");
+    assert_eq!(
+        EncodingData::extract_from_bytes(&input).unwrap(),
+        EncodingData {
+            foo: "こんにちは".to_owned()
+        }
+    );
+    // "あ" in UTF-16LE with BOM
+    assert_eq!(
+        EncodingData::extract_from_bytes(b"\xff\xfe<\x00p\x00>\x00\x42\x30").unwrap(),
+        EncodingData {
+            foo: "あ".to_owned()
+        }
+    );
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mmap() {
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        FileData {
+            foo: Vec<usize> = (text of "p", collect),
+        }
+    }
+    let path =
+        std::env::temp_dir().join(format!("html-extractor-test-{}.html", std::process::id()));
+    std::fs::write(&path, "<p>1</p><p>2</p>").unwrap();
+    // safety: the file is written only before it is extracted
+    let data = unsafe { html_extractor::extract_from_file::<FileData, _>(&path) };
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(data.unwrap(), FileData { foo: vec![1, 2] });
+    assert!(unsafe { html_extractor::extract_from_file::<FileData, _>(&path) }.is_err());
+}
+
+#[test]
+fn prelude_accessibility() {
+    use html_extractor::prelude::{AccessibilityReport, FormControl, Landmark};
+
+    let report = AccessibilityReport::extract_from_str(
+        r#"
+        <header><h1>Site</h1></header>
+        <div role="navigation main" aria-labelledby="nav-title"><h2 id="nav-title">Menu</h2></div>
+        <main>
+            <article><header>not a banner</header></article>
+            <section>no name</section>
+            <section aria-label="Results"></section>
+            <img src="a.png" alt="">
+            <img src="b.png">
+            <div role="button">Click</div>
+            <label>Name <input type="text" name="name"></label>
+            <select id="color" aria-label="Color"></select>
+            <input type="hidden" name="token">
+        </main>
+        <footer></footer>
+    "#,
+    )
+    .unwrap();
+
+    let landmark = |role: &str, tag: &str, label: Option<&str>| Landmark {
+        role: role.to_owned(),
+        tag: tag.to_owned(),
+        label: label.map(str::to_owned),
+    };
+    assert_eq!(
+        report.landmarks,
+        vec![
+            landmark("banner", "header", None),
+            landmark("navigation", "div", Some("Menu")),
+            landmark("main", "main", None),
+            landmark("region", "section", Some("Results")),
+            landmark("contentinfo", "footer", None),
+        ]
+    );
+    assert_eq!(
+        report.roles.iter().map(|r| &*r.role).collect::<Vec<_>>(),
+        vec!["navigation main", "button"]
+    );
+    assert_eq!(report.images_missing_alt.len(), 1);
+    assert_eq!(report.images_missing_alt[0].src.as_deref(), Some("b.png"));
+    assert_eq!(
+        report.form_controls,
+        vec![
+            FormControl {
+                tag: "input".to_owned(),
+                input_type: Some("text".to_owned()),
+                id: None,
+                name: Some("name".to_owned()),
+                label: Some("Name".to_owned()),
+            },
+            FormControl {
+                tag: "select".to_owned(),
+                input_type: None,
+                id: Some("color".to_owned()),
+                name: None,
+                label: Some("Color".to_owned()),
+            },
+        ]
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn prelude_time() {
+    use chrono::{FixedOffset, NaiveDate, TimeZone};
+    use html_extractor::prelude::Time;
+
+    let tz = FixedOffset::east_opt(9 * 3600).unwrap();
+    let time = |input: &str| Time::extract_from_str(input).unwrap();
+
+    // the attribute is preferred
+    let t = time(r#"<time datetime="2020-01-02T03:04:05+00:00">yesterday</time>"#);
+    assert_eq!(
+        t.to_datetime_in("%Y/%m/%d", tz).unwrap(),
+        tz.with_ymd_and_hms(2020, 1, 2, 12, 4, 5).unwrap()
+    );
+    assert_eq!(
+        t.to_date("%Y/%m/%d").unwrap(),
+        NaiveDate::from_ymd_opt(2020, 1, 2).unwrap()
+    );
+    // a date in the attribute
+    let t = time(r#"<time datetime="2020-01-02">Jan 2</time>"#);
+    assert_eq!(
+        t.to_datetime_in("%Y/%m/%d", tz).unwrap(),
+        tz.with_ymd_and_hms(2020, 1, 2, 0, 0, 0).unwrap()
+    );
+    // falls back to the text
+    let t = time("<p>published: <time> 2020/01/02 </time></p>");
+    assert_eq!(t.datetime, None);
+    assert_eq!(
+        t.to_datetime_in("%Y/%m/%d", tz).unwrap(),
+        tz.with_ymd_and_hms(2020, 1, 2, 0, 0, 0).unwrap()
+    );
+    let t = time(r#"<time datetime="invalid">2020/01/02 03:04 +0000</time>"#);
+    assert_eq!(
+        t.to_datetime_in("%Y/%m/%d %H:%M %z", tz).unwrap(),
+        tz.with_ymd_and_hms(2020, 1, 2, 12, 4, 0).unwrap()
+    );
+    assert!(t.to_date("%Y-%m-%d").is_err());
+    assert!(Time::extract_from_str("<p>no time</p>").is_err());
+}
+
+#[test]
+fn incremental() {
+    use html_extractor::incremental::IncrementalExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Incremental {
+            #![incremental]
+            #![validate(|data: &Incremental| if data.price > 0 { Ok(()) } else { Err("free") })]
+            title: String = (text of "h1"),
+            (currency: String, price: usize) = (text of "#price", capture with "^(\\D+)(\\d+)$"),
+            tags: Vec<String> = (text of ".tag", collect),
+        }
+    }
+
+    let v1 = r#"<h1>Coffee</h1><div id="price">$100</div><span class="tag">hot</span>"#;
+    let v2 = r#"<h1>Coffee</h1><div id="price">$100</div><span class="tag">hot</span><span class="tag">new</span>"#;
+    let v3 = r#"<h1>Tea</h1><div id="price">$120</div><span class="tag">hot</span><span class="tag">new</span>"#;
+
+    let data = Incremental::extract_from_str(v1).unwrap();
+    let fingerprint = Incremental::fingerprint_from_str(v1);
+    assert_eq!(
+        fingerprint
+            .iter()
+            .map(|(field, _)| field)
+            .collect::<Vec<_>>(),
+        vec!["title", "currency", "tags"]
+    );
+
+    // nothing changed
+    let result = Incremental::reextract_from_str(data, &fingerprint, v1).unwrap();
+    assert!(result.recomputed.is_empty());
+    assert_eq!(result.fingerprint, fingerprint);
+
+    let result = Incremental::reextract_from_str(result.data, &result.fingerprint, v2).unwrap();
+    assert_eq!(result.recomputed, vec!["tags"]);
+    assert_eq!(result.data.tags, vec!["hot", "new"]);
+
+    let result = Incremental::reextract_from_str(result.data, &result.fingerprint, v3).unwrap();
+    assert_eq!(result.recomputed, vec!["title", "currency"]);
+    assert_eq!(
+        result.data,
+        Incremental {
+            title: "Tea".to_owned(),
+            currency: "$".to_owned(),
+            price: 120,
+            tags: vec!["hot".to_owned(), "new".to_owned()],
+        }
+    );
+
+    // an empty fingerprint recomputes everything, and the struct is validated again
+    let free = r#"<h1>Tea</h1><div id="price">$0</div>"#;
+    assert!(Incremental::reextract_from_str(result.data, &Default::default(), free).is_err());
+}
+
+#[test]
+fn provenance() {
+    use html_extractor::provenance::ProvenanceExtractor;
+
+    html_extractor::html_extractor! {
+        selectors {
+            ITEM = "li",
+        }
+        ProvenanceData {
+            #![provenance]
+            title: String = (text of "h1"),
+            (currency: String, price: usize) = (text of "#price", capture with "^(\\D+)(\\d+)$"),
+            items: Vec<String> = (text of ITEM, collect),
+            note: Option<String> = (text of ".note", optional),
+            sale: bool = (presence of ".sale"),
+            // the nodes are the ones the values came from
+            cheap: Vec<u32> = (attr["data-price"] of ITEM, collect skip 1, filter with |p: &u32| *p < 50),
+            list: String = (attr["id"] of closest "ul" of ITEM),
+            next: String = (text of next of "h1"),
+            only: String = (text of "h2" else text of "h1", unique),
+        }
+    }
+
+    let input = r#"
+        <h1>Menu</h1>
+        <div><span id="price">$100</span></div>
+        <ul id="menu"><li data-price="40">Coffee</li><li data-price="60">Tea</li><li data-price="30">Milk</li></ul>
+        <span class="sale">SALE</span>
+    "#;
+    let (data, provenance) = ProvenanceData::extract_with_provenance_from_str(input).unwrap();
+    assert_eq!(data.title, "Menu");
+    assert_eq!((&*data.currency, data.price), ("$", 100));
+    assert_eq!(data.items, vec!["Coffee", "Tea", "Milk"]);
+    assert_eq!((data.note, data.sale), (None, true));
+    assert_eq!(data.cheap, vec![30]);
+    assert_eq!(
+        (&*data.list, &*data.next, &*data.only),
+        ("menu", "$100", "Menu")
+    );
+    assert_eq!(
+        provenance.iter().map(|f| f.field).collect::<Vec<_>>(),
+        vec![
+            "title", "currency", "price", "items", "note", "sale", "cheap", "list", "next", "only"
+        ]
+    );
+    let get = |field: &str| provenance.get(field).unwrap();
+    assert_eq!(
+        get("title").nodes,
+        vec!["html > body:nth-child(2) > h1:nth-child(1)"]
+    );
+    assert_eq!(get("price").selector, "#price");
+    assert_eq!(get("price").nodes, get("currency").nodes);
+    assert_eq!(get("items").selector, "li");
+    assert_eq!(
+        get("items").nodes,
+        vec![
+            "html > body:nth-child(2) > ul:nth-child(3) > li:nth-child(1)",
+            "html > body:nth-child(2) > ul:nth-child(3) > li:nth-child(2)",
+            "html > body:nth-child(2) > ul:nth-child(3) > li:nth-child(3)",
+        ]
+    );
+    assert!(get("note").nodes.is_empty());
+    assert_eq!(get("sale").nodes.len(), 1);
+    // the skipped and the filtered items are not recorded
+    assert_eq!(
+        get("cheap").nodes,
+        vec!["html > body:nth-child(2) > ul:nth-child(3) > li:nth-child(3)"]
+    );
+    assert_eq!(
+        get("list").nodes,
+        vec!["html > body:nth-child(2) > ul:nth-child(3)"]
+    );
+    assert_eq!(
+        get("next").nodes,
+        vec!["html > body:nth-child(2) > div:nth-child(2)"]
+    );
+    // the fallback target is reported
+    assert_eq!(get("only").selector, "h1");
+    assert_eq!(get("only").nodes, get("title").nodes);
+
+    // the paths select the elements again
+    let html = scraper::Html::parse_document(input);
+    for node in &get("items").nodes {
+        let selector = scraper::Selector::parse(node).unwrap();
+        assert_eq!(html.select(&selector).count(), 1);
+    }
+}
+
+#[test]
+fn render_pretty() {
+    html_extractor::html_extractor! {
+        #[derive(Debug)]
+        Diagnostics {
+            _price: usize = (text of "div.item > span.price"),
+        }
+    }
+
+    let input =
+        "<html><body>\n<div class=\"item\">\n<p class=\"price\">100</p>\n</div>\n</body></html>";
+    let err = Diagnostics::extract_from_str(input).unwrap_err();
+    assert!(
+        matches!(&err, html_extractor::Error::ElementNotFound(e) if e.selector == "div.item > span.price")
+    );
+
+    let rendered = err.render_plain(input);
+    assert!(!rendered.contains('\x1b'));
+    assert_eq!(
+        rendered.lines().collect::<Vec<_>>(),
+        vec![
+            "error: invalid input: extracting the data of field `_price` in struct `Diagnostics`, no element matched the selector",
+            "  selector: div.item > span.price",
+            "  candidates:",
+            "    div.item > p.price (the element is `<p>`)",
+            "    div.item (nothing in it matches the rest of the selector)",
+            "  html:",
+            "    1 | <html><body>",
+            "    2 | <div class=\"item\">",
+            "  > 3 | <p class=\"price\">100</p>",
+            "    4 | </div>",
+            "    5 | </body></html>",
+        ]
+    );
+    assert!(
+        err.render_pretty(input).contains("\x1b[1;33mprice\x1b[0m")
+            || std::env::var_os("NO_COLOR").is_some()
+    );
+
+    // long lines are clipped around the element
+    let input = format!(
+        "<div class=\"item\">{}<p class=\"price\">100</p>{}</div>",
+        "x".repeat(500),
+        "y".repeat(500)
+    );
+    let rendered = Diagnostics::extract_from_str(&input)
+        .unwrap_err()
+        .render_plain(&input);
+    let line = rendered
+        .lines()
+        .find(|line| line.starts_with("  > 1 |"))
+        .unwrap();
+    assert!(
+        line.contains("…xxx")
+            && line.contains("<p class=\"price\">100</p>")
+            && line.ends_with("yyy…")
+    );
+    assert!(line.chars().count() < 120);
+
+    // errors other than unmatched selectors are rendered with the message only
+    let err = html_extractor::Error::InvalidInput("foo".into());
+    assert_eq!(err.render_plain(""), "error: invalid input: foo\n");
+}
+
+#[test]
+fn smart_pointers() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    type Shared = Arc<str>;
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        SmartPointers {
+            title: Arc<str> = (text of "h1"),
+            tags: Vec<Rc<str>> = (text of ".tag", collect),
+            note: Option<Box<str>> = (attr["title"] of "p", optional),
+            (name: std::sync::Arc<str>, age: usize) = (text of "#user", capture with "^(.*), (\\d+)$"),
+            // the conversion is chosen for each value, through aliases
+            items: Vec<(u32, Shared)> = (text of ".item", capture with r"(\d+) (\w+)", collect),
+            inner: Arc<SmartPointersInner> = (elem of "#inner"),
+            inners: Vec<Rc<SmartPointersInner>> = (elem of "#inner", collect),
+        }
+        #[derive(Debug, Clone, PartialEq)]
+        SmartPointersInner {
+            value: Box<str> = (text of "span"),
+            // recursive structures are boxed
+            deeper: Option<Box<SmartPointersInner>> = (elem of "div", optional),
+        }
+    }
+
+    let data = SmartPointers::extract_from_str(
+        r#"
+            <h1>Title</h1>
+            <span class="tag">a</span><span class="tag">b</span>
+            <p title="note"></p>
+            <div id="user">Alice, 20</div>
+            <div class="item">1 apple</div><div class="item">2 banana</div>
+            <div id="inner"><span>inner</span><div><span>deeper</span></div></div>
+        "#,
+    )
+    .unwrap();
+    let inner = SmartPointersInner {
+        value: "inner".into(),
+        deeper: Some(Box::new(SmartPointersInner {
+            value: "deeper".into(),
+            deeper: None,
+        })),
+    };
+    assert_eq!(
+        data,
+        SmartPointers {
+            title: "Title".into(),
+            tags: vec!["a".into(), "b".into()],
+            note: Some("note".into()),
+            name: "Alice".into(),
+            age: 20,
+            items: vec![(1, "apple".into()), (2, "banana".into())],
+            inner: Arc::new(inner.clone()),
+            inners: vec![Rc::new(inner.clone())],
+        }
+    );
+}
+
+#[test]
+fn no_panic() {
+    html_extractor::html_extractor! {
+        #[derive(Debug)]
+        NoPanic {
+            (_a: String, _b: String) = (text of "div", capture with "^(a)?(b)?$"),
+            _inner: Vec<String> = (inner_html of "div", collect),
+            _nested: Option<NoPanicInner> = (elem of "div div div", optional),
+        }
+        #[derive(Debug)]
+        NoPanicInner {
+            _text: Option<String> = (text[100] of "*", optional),
+        }
+    }
+
+    // a group that doesn't participate in the match
+    let err = NoPanic::extract_from_str("<div>b</div>").unwrap_err();
+    assert!(err.to_string().contains("capture group 1 did not match"));
+    assert!(NoPanic::extract_from_str("<div>ab</div>").is_ok());
+
+    // malformed and deeply nested documents
+    for input in [
+        "<div>ab".to_owned(),
+        "</div><div><<>>&#x0;&#xffffffff;</p></div>ab".to_owned(),
+        format!("{}ab", "<div>".repeat(1000)),
+        format!("{}ab{}", "<div><span>".repeat(500), "</span>".repeat(500)),
+    ] {
+        let _ = NoPanic::extract_from_str(&input);
+        let _ = NoPanic::extract_from_fragment(&input);
+        let _ = NoPanic::extract_from_bytes(input.as_bytes());
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_error() {
+    html_extractor::html_extractor! {
+        #[derive(Debug)]
+        SerializeError {
+            _foo: usize = (text of "#foo"),
+        }
+    }
+
+    let err = SerializeError::extract_from_str("").unwrap_err();
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        serde_json::json!({
+            "code": "E_ELEMENT_NOT_FOUND",
+            "message": err.to_string(),
+            "selector": "#foo",
+            "suggestions": [],
+        })
+    );
+    let err = SerializeError::extract_from_str(r#"<p id="foo">x</p>"#).unwrap_err();
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        serde_json::json!({
+            "code": "E_INVALID_INPUT",
+            "message": err.to_string(),
+        })
+    );
+}
+
+#[test]
+fn extract_options() {
+    use html_extractor::options::{CancellationToken, ExtractOptions};
+    use std::time::{Duration, Instant};
+
+    thread_local! {
+        static TOKEN: CancellationToken = CancellationToken::new();
+    }
+    // cancels the extraction after the second item
+    fn cancel_at_2(input: &str) -> Result<usize, std::num::ParseIntError> {
+        let n = input.parse()?;
+        if n == 2 {
+            TOKEN.with(|token| token.cancel());
+        }
+        Ok(n)
+    }
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Options {
+            items: Vec<usize> = (text of "li", collect, parse with cancel_at_2),
+            inner: OptionsInner = (elem of "ul"),
+        }
+        #[derive(Debug, PartialEq)]
+        OptionsInner {
+            first: usize = (text of "li"),
+        }
+    }
+
+    let input = "<ul><li>1</li><li>2</li><li>3</li></ul>";
+    let html = scraper::Html::parse_document(input);
+    let token = TOKEN.with(|token| token.clone());
+    let options = ExtractOptions::new().cancellation_token(token.clone());
+    let err = Options::extract_with_options(&html.root_element(), &options).unwrap_err();
+    assert!(matches!(err, html_extractor::Error::Cancelled));
+    assert_eq!(err.code(), "E_CANCELLED");
+
+    // the options don't apply after the extraction
+    assert!(token.is_cancelled());
+    assert_eq!(
+        Options::extract_from_str(input).unwrap().items,
+        vec![1, 2, 3]
+    );
+
+    let options = ExtractOptions::new().deadline(Instant::now());
+    assert!(matches!(
+        Options::extract_from_str_with_options("", &options),
+        Err(html_extractor::Error::TimedOut)
+    ));
+    let options = ExtractOptions::new().timeout(Duration::from_secs(60));
+    assert_eq!(
+        Options::extract_from_str_with_options(input, &options)
+            .unwrap()
+            .inner,
+        OptionsInner { first: 1 }
+    );
+
+    // the options are passed as an argument, so they apply on other threads too
+    let options = ExtractOptions::new().cancellation_token(token);
+    let result = std::thread::scope(|scope| {
+        scope
+            .spawn(|| OptionsInner::extract_from_str_with_options(input, &options))
+            .join()
+            .unwrap()
+    });
+    assert!(matches!(result, Err(html_extractor::Error::Cancelled)));
+}
+
+#[test]
+fn extract_into() {
+    use html_extractor::UpdatePolicy;
+
+    fn check_stock(data: &ExtractInto) -> Result<(), &'static str> {
+        if data.stock <= 100 {
+            Ok(())
+        } else {
+            Err("too many")
+        }
+    }
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        ExtractInto {
+            #![validate(check_stock)]
+            name: String = (text of "h1"),
+            (currency: String, price: usize) = (text of "#price", capture with "^(\\D+)(\\d+)$"),
+            stock: usize = (text of "#stock"),
+            tags: Vec<String> = (text of ".tag", collect),
+        }
+    }
+    let initial = || ExtractInto {
+        name: "Coffee".to_owned(),
+        currency: "$".to_owned(),
+        price: 100,
+        stock: 10,
+        tags: vec!["hot".to_owned()],
+    };
+
+    let partial = r#"<h1>Tea</h1><div id="price">free</div><div id="stock">5</div>"#;
+    let mut data = initial();
+    let failures = data
+        .extract_into_from_str(partial, UpdatePolicy::KeepPrevious)
+        .unwrap();
+    assert_eq!(
+        failures.iter().map(|f| f.field).collect::<Vec<_>>(),
+        vec!["currency"]
+    );
+    assert_eq!(
+        data,
+        ExtractInto {
+            name: "Tea".to_owned(),
+            stock: 5,
+            tags: vec![],
+            ..initial()
+        }
+    );
+
+    let mut data = initial();
+    assert!(data
+        .extract_into_from_str(partial, UpdatePolicy::AllOrNothing)
+        .is_err());
+    assert_eq!(data, initial());
+
+    // validation fails on the updated struct, and the fields are restored
+    let invalid = r#"<h1>Tea</h1><div id="price">$5</div><div id="stock">500</div>"#;
+    let mut data = initial();
+    assert!(data
+        .extract_into_from_str(invalid, UpdatePolicy::KeepPrevious)
+        .is_err());
+    assert_eq!(data, initial());
+
+    // extractors not implemented with the macro replace the whole value, and keep it on failures with any policy
+    let mut report = html_extractor::prelude::Time {
+        datetime: None,
+        text: "old".to_owned(),
+    };
+    report
+        .extract_into_from_str("<time>new</time>", UpdatePolicy::KeepPrevious)
+        .unwrap();
+    assert_eq!(report.text, "new");
+    assert!(report
+        .extract_into_from_str("<p>newer</p>", UpdatePolicy::KeepPrevious)
+        .is_err());
+    assert_eq!(report.text, "new");
+}
+
+#[test]
+fn owned_fragment() {
+    use html_extractor::OwnedFragment;
+
+    html_extractor::html_extractor! {
+        FragmentPage {
+            rows: Vec<OwnedFragment> = (elem of "tr", collect),
+            cell: OwnedFragment = (elem of "td"),
+            body: OwnedFragment = (elem of "body"),
+        }
+        #[derive(Debug, PartialEq)]
+        FragmentRow {
+            name: String = (text of "td.name"),
+            value: usize = (text of "td.value"),
+        }
+    }
+
+    let page = FragmentPage::extract_from_str(
+        r#"
+            <table>
+                <tr><td class="name">a</td><td class="value">1</td></tr>
+                <tr><td class="name">b</td><td class="value">x</td></tr>
+            </table>
+        "#,
+    )
+    .unwrap();
+    assert_eq!(page.rows.len(), 2);
+    assert_eq!(page.rows[0].name(), "tr");
+    // table parts are parsed in a table
+    assert_eq!(
+        page.rows[0].extract::<FragmentRow>().unwrap(),
+        FragmentRow {
+            name: "a".to_owned(),
+            value: 1
+        }
+    );
+    assert!(page.rows[1].extract::<FragmentRow>().is_err());
+    // the element is extracted itself, as with `elem of ..`
+    assert_eq!(page.cell.extract::<OwnedFragment>().unwrap(), page.cell);
+    // the document is parsed again for `html`, `head` and `body`
+    assert_eq!(page.body.extract::<OwnedFragment>().unwrap(), page.body);
+    let body: OwnedFragment = OwnedFragment::extract_from_str("<p>1</p><p>2</p>").unwrap();
+    assert_eq!(body.name(), "html");
+    assert_eq!(body.extract::<OwnedFragment>().unwrap(), body);
+}
+
+#[test]
+fn localized_number() {
+    use html_extractor::parsers::{self, NumberLocale, ParserSettings};
+    use html_extractor::ExtractWithContext;
+
+    struct Site {
+        lang: &'static str,
+    }
+    impl ParserSettings for Site {
+        fn number_locale(&self) -> NumberLocale {
+            NumberLocale::from_tag(self.lang).unwrap_or_default()
+        }
+    }
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        LocalizedNumbers {
+            #![context(site: Site)]
+            prices: Vec<f64> = (text of ".price", collect, parse with parsers::localized_number::<f64>(site)),
+            count: i64 = (text of "#count", parse with parsers::localized_number(site)),
+        }
+        #[derive(Debug, PartialEq)]
+        Count {
+            #![context(locale: NumberLocale)]
+            count: i64 = (text of "#count", parse with parsers::localized_number(locale)),
+        }
+    }
+    let input = r#"
+        <div class="price">1.234,5</div>
+        <div class="price">0,99</div>
+        <div class="price">12</div>
+        <div id="count">−1.000</div>
+    "#;
+
+    let de = Site { lang: "de_DE" };
+    assert_eq!(
+        LocalizedNumbers::extract_from_str_with_context(input, &de).unwrap(),
+        LocalizedNumbers {
+            prices: vec![1234.5, 0.99, 12.0],
+            count: -1000,
+        }
+    );
+    // `1.234,5` is not a number with `.` as the decimal separator
+    assert!(LocalizedNumbers::extract_from_str_with_context(input, &Site { lang: "en" }).is_err());
+    assert_eq!(
+        Count::extract_from_str_with_context(input, &NumberLocale::COMMA)
+            .unwrap()
+            .count,
+        -1000
+    );
+
+    let fr = parsers::number_in::<f64>(NumberLocale::from_tag("fr-FR").unwrap());
+    assert_eq!(fr("1\u{202f}234,5").unwrap(), 1234.5);
+    assert!(fr("1,2,3").is_err());
+    let ch = parsers::number_in::<u32>(NumberLocale::from_tag("de-CH").unwrap());
+    assert_eq!(ch("1'234'567").unwrap(), 1234567);
+    assert_eq!(NumberLocale::from_tag("xx"), None);
+}
+
+#[test]
+fn shadow_root() {
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        ShadowPage {
+            card: ShadowCard = (shadow of "x-card"),
+            missing: Option<ShadowCard> = (shadow of "x-missing", optional),
+        }
+        #[derive(Debug, PartialEq)]
+        ShadowCard {
+            title: String = (text of "header > h2"),
+            badge: Option<String> = (text of ".badge", optional),
+            counts: Vec<ShadowCount> = (shadow of "x-count", collect),
+        }
+        #[derive(Debug, PartialEq)]
+        ShadowCount {
+            count: usize = (text of "span"),
+        }
+        NoShadow {
+            _card: ShadowCard = (shadow of "x-plain"),
+        }
+    }
+    let input = r#"
+        <x-card>
+            <template shadowrootmode="open">
+                <header><h2>Title</h2></header>
+                <x-count><template shadowroot="open"><span>1</span></template></x-count>
+                <x-count><template shadowrootmode="closed"><span>2</span></template></x-count>
+                <slot></slot>
+            </template>
+            <span class="badge">light DOM</span>
+        </x-card>
+        <x-plain><header><h2>Plain</h2></header></x-plain>
+    "#;
+    assert_eq!(
+        ShadowPage::extract_from_str(input).unwrap(),
+        ShadowPage {
+            card: ShadowCard {
+                title: "Title".to_owned(),
+                // the light DOM of the host is not in the shadow root
+                badge: None,
+                counts: vec![ShadowCount { count: 1 }, ShadowCount { count: 2 }],
+            },
+            missing: None,
+        }
+    );
+
+    let err = NoShadow::extract_from_str(input).err().unwrap();
+    assert!(
+        err.to_string().contains("no declarative shadow root"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn prelude_media() {
+    use html_extractor::prelude::{Media, MediaKind, MediaSource};
+
+    let media = Media::extract_from_str(
+        r#"
+            <figure>
+                <audio src=" /a.ogg " loop>
+                    <source src="/a.mp3" type="audio/mpeg" media="(min-width: 600px)">
+                    <source type="audio/wav">
+                    <source src="">
+                    <p>Your browser does not support audio.</p>
+                </audio>
+            </figure>
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        media,
+        Media {
+            kind: MediaKind::Audio,
+            poster: None,
+            controls: false,
+            autoplay: false,
+            looping: true,
+            muted: false,
+            sources: vec![
+                MediaSource {
+                    src: "/a.ogg".to_owned(),
+                    mime_type: None,
+                    media: None,
+                },
+                MediaSource {
+                    src: "/a.mp3".to_owned(),
+                    mime_type: Some("audio/mpeg".to_owned()),
+                    media: Some("(min-width: 600px)".to_owned()),
+                },
+            ],
+        }
+    );
+    assert!(Media::extract_from_str("<img src=/a.png>").is_err());
+}
+
+#[test]
+fn style() {
+    use html_extractor::style::{self, parse_declarations, Declaration, StyleSheet};
+
+    let sheet = StyleSheet::parse(
+        r#"
+            @charset "utf-8";
+            @import url("a;b.css");
+            /* .commented { color: red } */
+            .a::before { content: "}{;"; COLOR: Red !IMPORTANT; color: blue }
+            @font-face { font-family: x; src: url(x.woff) }
+            @media screen {
+                @media (min-width: 600px) { .a
+                    .b { margin: 0 } }
+            }
+            @supports (display: grid) { .grid { display: grid } }
+            .unclosed { color: green
+        "#,
+    );
+    let selectors = sheet
+        .rules
+        .iter()
+        .map(|r| r.selectors.join(", "))
+        .collect::<Vec<_>>();
+    assert_eq!(selectors, vec![".a::before", ".a .b", ".grid", ".unclosed"]);
+    assert_eq!(sheet.property(".a::before", "color"), Some("Red"));
+    assert_eq!(sheet.property(".a::before", "content"), Some("\"}{;\""));
+    assert_eq!(
+        sheet.rules[1].media.as_deref(),
+        Some("screen and (min-width: 600px)")
+    );
+    assert_eq!(sheet.property(".a  .b", "margin"), Some("0"));
+    assert_eq!(sheet.property(".unclosed", "color"), Some("green"));
+    assert_eq!(sheet.property(".grid", "color"), None);
+
+    // the rules nested too deeply are skipped instead of overflowing the stack
+    let nested = |depth: usize| {
+        format!(
+            ".top {{ color: red }} {}.deep {{ color: blue }}{} .after {{ color: green }}",
+            "@media screen { ".repeat(depth),
+            " }".repeat(depth)
+        )
+    };
+    let sheet = StyleSheet::parse(&nested(style::MAX_NESTING));
+    assert_eq!(sheet.property(".deep", "color"), Some("blue"));
+    let css = nested(20_000);
+    let sheet = StyleSheet::parse(&css);
+    assert_eq!(sheet.property(".top", "color"), Some("red"));
+    assert_eq!(sheet.property(".deep", "color"), None);
+    assert_eq!(sheet.property(".after", "color"), Some("green"));
+    let sheet = StyleSheet::extract_from_str(&format!("<style>{}</style>", css)).unwrap();
+    assert_eq!(sheet.property(".after", "color"), Some("green"));
+
+    assert_eq!(
+        parse_declarations("--Theme: #fff; :x; background:url(data:image/png;base64,AA==)"),
+        vec![
+            Declaration {
+                property: "--Theme".to_owned(),
+                value: "#fff".to_owned(),
+                important: false,
+            },
+            Declaration {
+                property: "background".to_owned(),
+                value: "url(data:image/png;base64,AA==)".to_owned(),
+                important: false,
+            },
+        ]
+    );
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        InlineStyle {
+            widths: Vec<usize> = (style["width"] of ".bar", collect, replace "px" with ""),
+            colors: Vec<String> = (style["--color"] of ".bar", collect),
+            sheet: StyleSheet = (elem of "style"),
+        }
+    }
+    let inline = InlineStyle::extract_from_str(
+        r#"
+            <style>.bar { width: 0 }</style>
+            <div class="bar" style="width: 10px !important; --color: red; width: 20px"></div>
+            <div class="bar" style="WIDTH:30px;--color:blue"></div>
+        "#,
+    )
+    .unwrap();
+    assert_eq!(inline.widths, vec![10, 30]);
+    assert_eq!(inline.colors, vec!["red", "blue"]);
+    assert_eq!(inline.sheet.property(".bar", "width"), Some("0"));
+
+    html_extractor::html_extractor! {
+        MissingStyle {
+            _width: String = (style["width"] of "div"),
+        }
+    }
+    let err = MissingStyle::extract_from_str(r#"<div style="height: 1px"></div>"#)
+        .err()
+        .unwrap();
+    assert!(err
+        .to_string()
+        .contains("style property `width` is not found"));
+}
+
+#[test]
+fn fast_select() {
+    let html = scraper::Html::parse_document(
+        r#"
+            <div id="a" class="x y"><p id="a" class="y">1</p><p id="A" class="Y">2</p></div>
+            <template><span class="y" id="a">3</span></template>
+            <span class="xy"></span>
+        "#,
+    );
+    let root = html.root_element();
+    let div = root
+        .select(&scraper::Selector::parse("div").unwrap())
+        .next()
+        .unwrap();
+    for elem in &[root, div] {
+        for (selector, fast) in [
+            (
+                "#a",
+                html_extractor::select::by_id(elem, "a").collect::<Vec<_>>(),
+            ),
+            (".y", html_extractor::select::by_class(elem, "y").collect()),
+        ] {
+            let general = elem
+                .select(&scraper::Selector::parse(selector).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(fast, general, "{}", selector);
+        }
+    }
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        FastSelect {
+            first: usize = (text of "#a"),
+            ys: Vec<usize> = (text of ".y", collect),
+            unique: usize = (text of "#A", unique),
+        }
+    }
+    assert_eq!(
+        FastSelect::extract(&root).unwrap(),
+        FastSelect {
+            first: 1,
+            ys: vec![1, 1, 3],
+            unique: 2
+        }
+    );
+}
+
+#[test]
+fn derive() {
+    use html_extractor::provenance::ProvenanceExtractor;
+    use html_extractor::{HtmlExtractor, UpdatePolicy};
+
+    #[derive(Debug, PartialEq, HtmlExtractor)]
+    #[extract(provenance)]
+    #[extract(after_extract = |d: &mut Derived| { d.name.push('!'); Ok::<_, html_extractor::Error>(()) })]
+    pub(crate) struct Derived {
+        /// the second text node
+        #[extract(text = "#name", nth = 1, normalize_whitespace, uppercase)]
+        name: String,
+        #[extract(style = "width", selector = "#bar", replace("px", ""), parse = str::parse::<u32>)]
+        width: u32,
+        #[extract(text = "#version", capture = r"v(\d+)")]
+        version: usize,
+        #[extract(inner_html = "ul", optional)]
+        list: Option<String>,
+        #[extract(text = "li", collect)]
+        items: Vec<String>,
+    }
+
+    let input = r#"
+        <p id="name">ignored<br> foo
+            bar </p>
+        <div id="bar" style="width: 10px"></div>
+        <span id="version">v2</span>
+        <ul><li>a</li><li>b</li></ul>
+    "#;
+    let (derived, provenance) = Derived::extract_with_provenance_from_str(input).unwrap();
+    assert_eq!(
+        derived,
+        Derived {
+            name: "FOO BAR!".to_owned(),
+            width: 10,
+            version: 2,
+            list: Some("<li>a</li><li>b</li>".to_owned()),
+            items: vec!["a".to_owned(), "b".to_owned()],
+        }
+    );
+    assert_eq!(provenance.get("items").unwrap().nodes.len(), 2);
+
+    let mut derived = derived;
+    let html = scraper::Html::parse_document(r#"<p id="name">x<br>y</p><li>c</li>"#);
+    let failures = derived
+        .extract_into(&html.root_element(), UpdatePolicy::KeepPrevious)
+        .unwrap();
+    assert_eq!(
+        failures.iter().map(|f| f.field).collect::<Vec<_>>(),
+        vec!["width", "version"]
+    );
+    assert_eq!(derived.name, "Y!");
+    assert_eq!(derived.width, 10);
+    assert_eq!(derived.list, None);
+    assert_eq!(derived.items, vec!["c".to_owned()]);
+}
+
+#[cfg(feature = "schema")]
+#[test]
+fn schema() {
+    use html_extractor::schema::{Schema, SchemaError};
+    use serde_json::json;
+
+    // `Schema` can be deserialized from any format
+    let schema: Schema = serde_json::from_value(json!({
+        "id": { "text": "#id", "unique": true, "type": "integer" },
+        "ratio": { "attr": "data-ratio", "selector": "#id", "type": "float" },
+        "size": { "text": ".size", "capture": r"(\d+)x(\d+)", "type": "integer" },
+        "color": { "style": "color", "selector": "#id", "uppercase": true },
+        "missing": { "text": ".missing", "optional": true },
+        "card": {
+            "shadow": "x-card",
+            "fields": { "title": { "text": "h2", "nth": 0 } }
+        },
+        "flags": { "text": ".flag", "collect": true, "type": "boolean" },
+    }))
+    .unwrap();
+    let input = r#"
+        <div id="id" data-ratio="0.5" style="color: red">42</div>
+        <p class="size">1920x1080</p>
+        <x-card><template shadowrootmode="open"><h2>Card</h2></template></x-card>
+        <i class="flag">true</i><i class="flag"> false </i>
+    "#;
+    assert_eq!(
+        schema.extract_from_str(input).unwrap(),
+        json!({
+            "id": 42,
+            "ratio": 0.5,
+            "size": [1920, 1080],
+            "color": "RED",
+            "missing": null,
+            "card": { "title": "Card" },
+            "flags": [true, false],
+        })
+    );
+    let unique = Schema::from_json(r##"{"id": {"text": "#id", "unique": true}}"##).unwrap();
+    let err = unique
+        .extract_from_str(r#"<div id="id">1</div><div id="id">2</div>"#)
+        .unwrap_err();
+    assert!(err.to_string().contains("2 elements matched"), "{}", err);
+
+    let invalid = |json: &str| match Schema::from_json(json).unwrap_err() {
+        SchemaError::InvalidField { field, message } => format!("{}: {}", field, message),
+        err => panic!("{}", err),
+    };
+    assert!(invalid(r#"{"a": {"text": "p", "inner_html": "p"}}"#).starts_with("a: exactly one of"));
+    assert!(
+        invalid(r#"{"a": {"elem": "p", "fields": {"b": {}}}}"#).starts_with("a.b: exactly one of")
+    );
+    assert!(invalid(r#"{"a": {"attr": "href"}}"#).contains("require `selector`"));
+    assert!(invalid(r#"{"a": {"text": "p["}}"#).contains("cannot parse the selector"));
+    assert!(invalid(r#"{"a": {"text": "p", "capture": "("}}"#).contains("cannot parse the regex"));
+    assert!(
+        invalid(r#"{"a": {"text": "p", "collect": true, "unique": true}}"#).contains("only one of")
+    );
+    assert!(invalid(r#"{"a": {"presence": "p", "type": "integer"}}"#).contains("cannot be used"));
+    assert!(matches!(
+        Schema::from_json(r#"{"a": {"text": "p", "colect": true}}"#),
+        Err(SchemaError::Json(_))
+    ));
+    // an invalid pattern is reported every time, not cached
+    assert!(invalid(r#"{"a": {"text": "p["}}"#).contains("cannot parse the selector"));
+
+    // the schemas sharing the patterns are loaded from several threads
+    let json = r#"{"n": {"text": ".n", "capture": "(\\d+)", "type": "integer", "collect": true}}"#;
+    let values = std::thread::scope(|scope| {
+        let handles = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    Schema::from_json(json)
+                        .unwrap()
+                        .extract_from_str(r#"<p class="n">1 a</p><p class="n">2 b</p>"#)
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert!(values.iter().all(|v| *v == json!({ "n": [1, 2] })));
+}
+
+#[test]
+fn root_selector() {
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Item in ".item" {
+            name: String = (text of ".name"),
+            tags: Vec<String> = (text of ".tag", collect),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    #[extract(root = "li")]
+    struct Entry {
+        #[extract(attr = "href", selector = "a")]
+        href: String,
+    }
+
+    let input = r#"
+        <div class="item"><p class="name">a</p><span class="tag">x</span><span class="tag">y</span></div>
+        <div class="other"><p class="name">ignored</p></div>
+        <div class="item"><p class="name">b</p></div>
+        <ul><li><a href="/1">1</a></li><li><a href="/2">2</a></li></ul>
+    "#;
+    assert_eq!(
+        Item::extract_all_from_str(input).unwrap(),
+        vec![
+            Item {
+                name: "a".to_owned(),
+                tags: vec!["x".to_owned(), "y".to_owned()],
+            },
+            Item {
+                name: "b".to_owned(),
+                tags: vec![],
+            },
+        ]
+    );
+    assert_eq!(
+        Entry::extract_all_from_str(input).unwrap(),
+        vec![
+            Entry {
+                href: "/1".to_owned()
+            },
+            Entry {
+                href: "/2".to_owned()
+            },
+        ]
+    );
+    assert!(Item::extract_all_from_str("<p>nothing</p>")
+        .unwrap()
+        .is_empty());
+    // an element that fails to extract fails the whole
+    assert!(Item::extract_all_from_str(r#"<div class="item"></div>"#).is_err());
+
+    let html = scraper::Html::parse_document(
+        r#"<div class="item"><p class="name">a</p></div><div class="item"></div>"#,
+    );
+    let mut iter = Item::extract_iter(&html.root_element());
+    assert_eq!(iter.next().unwrap().unwrap().name, "a");
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn default_fallback() {
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Fallback {
+            count: usize = (text of ".count", or default),
+            styles: html_extractor::style::StyleSheet = (elem of "#missing", or default),
+            label: String = (attr["title"] of "#label", default with "none".to_owned()),
+            (major: u32, minor: u32) = (text of "#version", capture with r"(\d+)\.(\d+)", default with (1, 0)),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct DerivedFallback {
+        #[extract(text = ".count", default)]
+        count: usize,
+        #[extract(text = "#label", default = String::from("none"))]
+        label: String,
+    }
+
+    assert_eq!(
+        Fallback::extract_from_str("<p>empty</p>").unwrap(),
+        Fallback {
+            count: 0,
+            styles: Default::default(),
+            label: "none".to_owned(),
+            major: 1,
+            minor: 0,
+        }
+    );
+    assert_eq!(
+        DerivedFallback::extract_from_str("<p>empty</p>").unwrap(),
+        DerivedFallback {
+            count: 0,
+            label: "none".to_owned(),
+        }
+    );
+
+    let input = r#"<p class="count">3</p><p id="label" title="x">y</p><p id="version">2.5</p>"#;
+    let fallback = Fallback::extract_from_str(input).unwrap();
+    assert_eq!(fallback.count, 3);
+    assert_eq!(fallback.label, "x");
+    assert_eq!((fallback.major, fallback.minor), (2, 5));
+    // the fallback is only for missing elements
+    assert!(Fallback::extract_from_str(r#"<p class="count">x</p>"#).is_err());
+}
+
+#[test]
+fn fallback_targets() {
+    use html_extractor::incremental::IncrementalExtractor;
+    use html_extractor::provenance::ProvenanceExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Fallbacks {
+            #![incremental]
+            #![provenance]
+            name: String = (text of "#new" else attr["data-name"] of "#old" else text of "h1"),
+            tags: Vec<String> = (text of ".tag" else text of ".label", uppercase, collect),
+            image: bool = (presence of "img" else presence of "picture"),
+            count: usize = (text of ".count" else text of ".total", or default),
+        }
+    }
+
+    let old = r#"<h1>heading</h1><p id="old" data-name="old name"></p><i class="label">a</i><i class="label">b</i>"#;
+    let (data, provenance) = Fallbacks::extract_with_provenance_from_str(old).unwrap();
+    assert_eq!(
+        data,
+        Fallbacks {
+            name: "old name".to_owned(),
+            tags: vec!["A".to_owned(), "B".to_owned()],
+            image: false,
+            count: 0,
+        }
+    );
+    assert_eq!(provenance.get("name").unwrap().selector, "#old");
+    assert_eq!(provenance.get("tags").unwrap().nodes.len(), 2);
+    assert_eq!(provenance.get("count").unwrap().selector, ".total");
+
+    let new = r#"<p id="new">new name</p><p id="old" data-name="old name"></p><picture></picture><b class="total">3</b>"#;
+    let data = Fallbacks::extract_from_str(new).unwrap();
+    assert_eq!(data.name, "new name");
+    assert!(data.tags.is_empty());
+    assert!(data.image);
+    assert_eq!(data.count, 3);
+
+    // a change in a fallback is detected even if it was not used
+    let fingerprint = Fallbacks::fingerprint_from_str(new);
+    let changed = new.replace("old name", "renamed");
+    let result = Fallbacks::reextract_from_str(data, &fingerprint, &changed).unwrap();
+    assert_eq!(result.recomputed, vec!["name"]);
+    assert_eq!(result.data.name, "new name");
+
+    // the last target decides the error
+    assert!(Fallbacks::extract_from_str("<p>nothing</p>").is_err());
+}
+
+#[test]
+fn selector_composition() {
+    use html_extractor::provenance::ProvenanceExtractor;
+
+    html_extractor::html_extractor! {
+        selector TABLE = "table#prices";
+        selector ROW = TABLE + " > tbody > tr";
+        selectors {
+            PRICE = ROW + " td.price",
+        }
+        #[derive(Debug, PartialEq)]
+        Row in ROW {
+            name: String = (text of "td.name"),
+            price: usize = (text of "td.price"),
+        }
+        #[derive(Debug, PartialEq)]
+        Prices {
+            #![provenance]
+            prices: Vec<usize> = (text of PRICE, collect),
+            first: String = (text of ROW + ":first-child" + " td.name"),
+            id: String = (attr["id"] of "#" + "prices"),
+        }
+    }
+
+    let input = r#"
+        <table id="prices"><tbody>
+            <tr><td class="name">a</td><td class="price">1</td></tr>
+            <tr><td class="name">b</td><td class="price">2</td></tr>
+        </tbody></table>
+    "#;
+    let (prices, provenance) = Prices::extract_with_provenance_from_str(input).unwrap();
+    assert_eq!(
+        prices,
+        Prices {
+            prices: vec![1, 2],
+            first: "a".to_owned(),
+            id: "prices".to_owned(),
+        }
+    );
+    assert_eq!(
+        provenance.get("first").unwrap().selector,
+        "table#prices > tbody > tr:first-child td.name"
+    );
+    assert_eq!(provenance.get("id").unwrap().selector, "#prices");
+    assert_eq!(
+        Row::extract_all_from_str(input).unwrap(),
+        vec![
+            Row {
+                name: "a".to_owned(),
+                price: 1
+            },
+            Row {
+                name: "b".to_owned(),
+                price: 2
+            },
+        ]
+    );
+}
+
+#[test]
+fn scope_blocks() {
+    use html_extractor::incremental::IncrementalExtractor;
+    use html_extractor::provenance::ProvenanceExtractor;
+
+    html_extractor::html_extractor! {
+        mixin Heading {
+            heading: String = (text of "h2"),
+        }
+        #[derive(Debug, PartialEq)]
+        Scoped {
+            #![incremental]
+            #![provenance]
+            title: String = (text of "h2"),
+            in "#sidebar" {
+                use Heading;
+                in "ul" {
+                    (first: usize,) = (text of "li", capture with r"^#(\d+)$"),
+                    items: Vec<String> = (text of "li", collect),
+                }
+            }
+            in "#footer" {
+                note: Option<String> = (text of "p", optional),
+                year: usize = (text of ".year", or default),
+            }
+        }
+    }
+
+    let input = r#"
+        <h2>main</h2>
+        <ul><li>outside</li></ul>
+        <div id="sidebar">
+            <h2>side</h2>
+            <ul><li>#1</li><li>#2</li></ul>
+            <ul><li>#3</li></ul>
+        </div>
+    "#;
+    let (scoped, provenance) = Scoped::extract_with_provenance_from_str(input).unwrap();
+    assert_eq!(
+        scoped,
+        Scoped {
+            title: "main".to_owned(),
+            heading: "side".to_owned(),
+            first: 1,
+            items: vec!["#1".to_owned(), "#2".to_owned()],
+            note: None,
+            year: 0,
+        }
+    );
+    assert_eq!(provenance.get("items").unwrap().nodes.len(), 2);
+    assert!(provenance.get("note").is_none());
+
+    // a change outside the scope doesn't recompute the field
+    let fingerprint = Scoped::fingerprint_from_str(input);
+    let changed = input.replace("outside", "changed");
+    let result = Scoped::reextract_from_str(scoped, &fingerprint, &changed).unwrap();
+    assert!(result.recomputed.is_empty());
+
+    let err = Scoped::extract_from_str("<h2>main</h2><ul><li>#1</li></ul>").unwrap_err();
+    assert!(err.to_string().contains("scope selector"), "{}", err);
+}
+
+#[test]
+fn enum_variants() {
+    html_extractor::html_extractor! {
+        selector ERROR = "#error";
+        mixin Title {
+            title: String = (text of "h1"),
+        }
+        #[derive(Debug, PartialEq)]
+        Listing {
+            names: Vec<String> = (text of "li", collect),
+        }
+        #[derive(Debug, PartialEq)]
+        enum Page {
+            /// An error page
+            Error if presence of ERROR {
+                (code: u16,) = (text of ERROR, capture with r"(\d+)"),
+            },
+            Article if presence of "article" {
+                use Title;
+                in "article" {
+                    body: String = (text of "p"),
+                }
+            },
+            Listing if presence of "ul" {
+                listing: Listing = (elem of "ul"),
+                count: usize = (text of ".count", or default),
+            },
+            Empty if presence of "body:empty" {},
+        }
+        #[derive(Debug, PartialEq)]
+        enum Status {
+            Sale if presence of ".sale",
+            Normal,
+        }
+    }
+
+    assert_eq!(
+        Page::extract_from_str(r#"<p id="error">Error 404</p><article></article>"#).unwrap(),
+        Page::Error { code: 404 }
+    );
+    assert_eq!(
+        Page::extract_from_str("<h1>Hi</h1><article><p>text</p></article>").unwrap(),
+        Page::Article {
+            title: "Hi".to_owned(),
+            body: "text".to_owned(),
+        }
+    );
+    assert_eq!(
+        Page::extract_from_str("<ul><li>a</li></ul>").unwrap(),
+        Page::Listing {
+            listing: Listing {
+                names: vec!["a".to_owned()],
+            },
+            count: 0,
+        }
+    );
+    assert_eq!(Page::extract_from_str("").unwrap(), Page::Empty {});
+    // the chosen variant fails if its fields fail, without trying the next ones
+    assert!(Page::extract_from_str(r#"<p id="error">unknown</p>"#).is_err());
+    let err = Page::extract_from_str("<p>other</p>").unwrap_err();
+    assert!(err.to_string().contains("no variant matched"), "{}", err);
+
+    assert_eq!(
+        Status::extract_from_str(r#"<b class="sale"></b>"#).unwrap(),
+        Status::Sale
+    );
+    assert_eq!(Status::extract_from_str("").unwrap(), Status::Normal);
+}
+
+#[test]
+fn generic_structs() {
+    use html_extractor::provenance::ProvenanceExtractor;
+    use html_extractor::HtmlExtractor;
+    use std::fmt::Debug;
+    use std::str::FromStr;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Pair<K: FromStr, V: HtmlExtractor> in "li" where K::Err: Debug {
+            #![provenance]
+            key: K = (attr["data-key"] of "span"),
+            value: V = (elem of "span"),
+            keys: Vec<K> = (attr["data-key"] of "span", collect),
+        }
+        #[derive(Debug, PartialEq)]
+        Text {
+            text: String = (text of "b"),
+        }
+        Callback<F: Fn() -> u8> {
+            callback: Option<F> = (text of "p", optional, parse with never),
+        }
+    }
+    fn never<F>(_: &str) -> Result<F, &'static str> {
+        Err("cannot parse a function")
+    }
+    #[derive(Debug, PartialEq, HtmlExtractor)]
+    struct Derived<T>
+    where
+        T: FromStr,
+        T::Err: Debug,
+    {
+        #[extract(text = "span b")]
+        value: T,
+    }
+
+    let input = r#"
+        <ul>
+            <li><span data-key="1"><b>one</b></span></li>
+            <li><span data-key="2"><b>two</b></span></li>
+        </ul>
+    "#;
+    let pairs = Pair::<u8, Text>::extract_all_from_str(input).unwrap();
+    assert_eq!(
+        pairs[1],
+        Pair {
+            key: 2,
+            value: Text {
+                text: "two".to_owned()
+            },
+            keys: vec![2],
+        }
+    );
+    let (_, provenance) = Pair::<u8, Text>::extract_with_provenance_from_str(input).unwrap();
+    assert_eq!(provenance.get("value").unwrap().selector, "span");
+    assert!(Pair::<bool, Text>::extract_all_from_str(input).is_err());
+    assert_eq!(
+        Derived::<String>::extract_from_str(input).unwrap().value,
+        "one"
+    );
+    let callback = Callback::<fn() -> u8>::extract_from_str(input).unwrap();
+    assert!(callback.callback.is_none());
+}
+
+#[test]
+fn field_types_with_commas() {
+    use std::collections::{BTreeMap, HashMap};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Entry<K: std::str::FromStr, V: std::str::FromStr>
+        where
+            K::Err: std::fmt::Debug,
+            V::Err: std::fmt::Debug,
+        {
+            key: K = (attr["data-key"] of "span"),
+            value: V = (text of "span"),
+        }
+        #[derive(Debug, PartialEq)]
+        Types {
+            map: HashMap<String, usize> = (text of "li", capture with r"^(\w+)=(\d+)$", collect),
+            sorted: BTreeMap<String, usize> = (text of "li", capture with r"^(\w+)=(\d+)$", collect),
+            entry: Entry<u8, String> = (elem of "p"),
+            (first: Option<Entry<u8, String>>, pair: (u8, u8)) = (text of "#pair", capture with r"^(\d+),(\d+)$", parse with parse_pair),
+        }
+    }
+    fn parse_pair<T: Default>(_: &str) -> Result<T, std::convert::Infallible> {
+        Ok(T::default())
+    }
+
+    let input = r#"
+        <ul><li>a=1</li><li>b=2</li></ul>
+        <p><span data-key="3">three</span></p>
+        <div id="pair">1,2</div>
+    "#;
+    let types = Types::extract_from_str(input).unwrap();
+    assert_eq!(types.map.get("b"), Some(&2));
+    assert_eq!(
+        types.sorted.into_iter().collect::<Vec<_>>(),
+        vec![("a".to_owned(), 1), ("b".to_owned(), 2)]
+    );
+    assert_eq!(
+        types.entry,
+        Entry {
+            key: 3,
+            value: "three".to_owned()
+        }
+    );
+    assert_eq!((types.first, types.pair), (None, (0, 0)));
+}
+
+#[test]
+fn attribute_macro() {
+    use html_extractor::provenance::ProvenanceExtractor;
+    use html_extractor::HtmlExtractor;
+
+    #[html_extractor::extractor(provenance, validate = |a: &Annotated| if a.value > 0 { Ok(()) } else { Err("zero") })]
+    #[derive(Debug, Clone, PartialEq)]
+    struct Annotated {
+        #[extract(text = "#value")]
+        value: u8,
+        #[extract(attr = "href", selector = "a", collect)]
+        links: Vec<String>,
+    }
+    #[html_extractor::extractor]
+    struct Unit {}
+
+    let input = r#"<p id="value">3</p><a href="/a"></a>"#;
+    let (annotated, provenance) = Annotated::extract_with_provenance_from_str(input).unwrap();
+    assert_eq!(
+        annotated.clone(),
+        Annotated {
+            value: 3,
+            links: vec!["/a".to_owned()],
+        }
+    );
+    assert_eq!(provenance.get("links").unwrap().selector, "a");
+    assert!(Annotated::extract_from_str(r#"<p id="value">0</p>"#).is_err());
+    assert!(Unit::extract_from_str("").is_ok());
+}
+
+#[test]
+fn tuple_structs() {
+    use html_extractor::incremental::IncrementalExtractor;
+    use html_extractor::provenance::ProvenanceExtractor;
+    use html_extractor::{HtmlExtractor, UpdatePolicy};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Cell<T> in "tr" (
+            #![incremental]
+            #![provenance]
+            /// the name
+            pub String = (text of ".name"),
+            pub(crate) T = (text of ".value", or default),
+            Vec<u8> = (text of ".tag", collect),
+        ) where T: std::str::FromStr + Default, T::Err: std::fmt::Debug;
+        #[derive(Debug, PartialEq)]
+        Wrapper(Cell<u32> = (elem of "tr"))
+    }
+
+    let input = r#"
+        <table>
+            <tr><td class="name">a</td><td class="value">1</td><td class="tag">7</td></tr>
+            <tr><td class="name">b</td></tr>
+        </table>
+    "#;
+    assert_eq!(
+        Cell::<u32>::extract_all_from_str(input).unwrap(),
+        vec![
+            Cell("a".to_owned(), 1, vec![7]),
+            Cell("b".to_owned(), 0, vec![])
+        ]
+    );
+    assert_eq!(
+        Wrapper::extract_from_str(input).unwrap(),
+        Wrapper(Cell("a".to_owned(), 1, vec![7]))
+    );
+
+    let (cell, provenance) = Cell::<u32>::extract_with_provenance_from_str(input).unwrap();
+    assert_eq!(
+        provenance.iter().map(|f| f.field).collect::<Vec<_>>(),
+        vec!["0", "1", "2"]
+    );
+    let fingerprint = Cell::<u32>::fingerprint_from_str(input);
+    let changed = input.replace(">1<", ">2<");
+    let result = Cell::<u32>::reextract_from_str(cell, &fingerprint, &changed).unwrap();
+    assert_eq!(result.recomputed, vec!["1"]);
+    assert_eq!(result.data.1, 2);
+
+    let mut cell = result.data;
+    let html = scraper::Html::parse_document(r#"<p class="name">c</p><p class="value">x</p>"#);
+    let failures = cell
+        .extract_into(&html.root_element(), UpdatePolicy::KeepPrevious)
+        .unwrap();
+    assert_eq!(
+        failures.iter().map(|f| f.field).collect::<Vec<_>>(),
+        vec!["1"]
+    );
+    assert_eq!(cell, Cell("c".to_owned(), 2, vec![]));
+    let err = Cell::<u32>::extract_from_str("<p></p>").unwrap_err();
+    assert!(
+        err.to_string().contains("field `0` in struct `Cell`"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn cfg_attributes() {
+    use html_extractor::incremental::IncrementalExtractor;
+    use html_extractor::provenance::ProvenanceExtractor;
+    use html_extractor::{HtmlExtractor, UpdatePolicy};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Conditional {
+            #![incremental]
+            #![provenance]
+            #![getters]
+            pub name: String = (text of "#name"),
+            #[cfg(any())]
+            pub missing: NotAType = (text of "#missing"),
+            (#[cfg(any())] major: NotAType, minor: NotAType) = (text of "#version", capture with "(.*)\\.(.*)"),
+            #[cfg(all())]
+            pub count: usize = (text of "#count"),
+        }
+        #[cfg(any())]
+        Removed {
+            field: NotAType = (text of "p"),
+        }
+        #[derive(Debug, PartialEq)]
+        enum Kind {
+            Link if presence of "a",
+            #[cfg(any())]
+            Other,
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "#name")]
+        name: String,
+        #[cfg(any())]
+        #[extract(text = "#missing")]
+        missing: NotAType,
+    }
+
+    let input = r#"<p id="name">a</p><p id="count">1</p>"#;
+    let data = Conditional::extract_from_str(input).unwrap();
+    assert_eq!(data.name(), "a");
+    assert_eq!(*data.count(), 1);
+    let (_, provenance) = Conditional::extract_with_provenance_from_str(input).unwrap();
+    assert_eq!(
+        provenance.iter().map(|f| f.field).collect::<Vec<_>>(),
+        vec!["name", "count"]
+    );
+    let fingerprint = Conditional::fingerprint_from_str(input);
+    let changed = input.replace(">1<", ">2<");
+    let result = Conditional::reextract_from_str(data, &fingerprint, &changed).unwrap();
+    assert_eq!(result.recomputed, vec!["count"]);
+    let mut data = result.data;
+    let html = scraper::Html::parse_document(r#"<p id="name">b</p>"#);
+    let failures = data
+        .extract_into(&html.root_element(), UpdatePolicy::KeepPrevious)
+        .unwrap();
+    assert_eq!(
+        failures.iter().map(|f| f.field).collect::<Vec<_>>(),
+        vec!["count"]
+    );
+    assert_eq!(data.name(), "b");
+
+    assert_eq!(Kind::extract_from_str("<a></a>").unwrap(), Kind::Link);
+    assert!(Kind::extract_from_str("<p></p>").is_err());
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived {
+            name: "a".to_owned()
+        }
+    );
+}
+
+#[test]
+fn skip_fields() {
+    use html_extractor::incremental::IncrementalExtractor;
+    use html_extractor::{HtmlExtractor, UpdatePolicy};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Page {
+            #![incremental]
+            title: String = (text of "h1"),
+            visits: usize = (skip),
+            tags: Vec<String> = (skip, init with vec!["new".to_owned(), "page".to_owned()]),
+        }
+        #[derive(Debug, PartialEq)]
+        Pair(String = (text of "h1"), u8 = (skip, init with 7));
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "h1")]
+        title: String,
+        #[extract(skip)]
+        visits: usize,
+        #[extract(skip, init = u32::MAX)]
+        limit: u32,
+    }
+
+    let input = "<h1>a</h1>";
+    let mut page = Page::extract_from_str(input).unwrap();
+    assert_eq!(
+        page,
+        Page {
+            title: "a".to_owned(),
+            visits: 0,
+            tags: vec!["new".to_owned(), "page".to_owned()],
+        }
+    );
+    page.visits = 3;
+    let html = scraper::Html::parse_document("<h1>b</h1>");
+    let failures = page
+        .extract_into(&html.root_element(), UpdatePolicy::AllOrNothing)
+        .unwrap();
+    assert!(failures.is_empty());
+    assert_eq!((&*page.title, page.visits), ("b", 3));
+
+    let fingerprint = Page::fingerprint_from_str("<h1>b</h1>");
+    let result = Page::reextract_from_str(page, &fingerprint, "<h1>c</h1>").unwrap();
+    assert_eq!(result.recomputed, vec!["title"]);
+    assert_eq!((&*result.data.title, result.data.visits), ("c", 3));
+
+    assert_eq!(
+        Pair::extract_from_str(input).unwrap(),
+        Pair("a".to_owned(), 7)
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived {
+            title: "a".to_owned(),
+            visits: 0,
+            limit: u32::MAX,
+        }
+    );
+}
+
+#[test]
+fn earlier_fields() {
+    use html_extractor::incremental::IncrementalExtractor;
+    use html_extractor::{HtmlExtractor, UpdatePolicy};
+
+    // the function of the same name as the field is not shadowed
+    fn unit(s: &str, unit: &str) -> Result<u32, std::num::ParseIntError> {
+        let value = s.parse::<u32>()?;
+        Ok(if unit == "k" { value * 1000 } else { value })
+    }
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Measure {
+            #![incremental]
+            unit: String = (text of ".unit"),
+            value: u32 = (text of ".value", parse with |s| unit(s, field_unit)),
+            (r#min: u32, max: u32) = (text of ".range", capture with r"(\d+)-(\d+)"),
+            width: u32 = (skip, init with *field_max - *field_min),
+            fallback: u32 = (text of ".fallback", default with *field_value + 1),
+        }
+    }
+
+    let input = r#"
+        <span class="unit">k</span>
+        <span class="value">2</span>
+        <span class="range">3-10</span>
+    "#;
+    let mut measure = Measure::extract_from_str(input).unwrap();
+    assert_eq!(
+        measure,
+        Measure {
+            unit: "k".to_owned(),
+            value: 2000,
+            r#min: 3,
+            max: 10,
+            width: 7,
+            fallback: 2001,
+        }
+    );
+
+    // the unit fails and the previous one is used
+    let html = scraper::Html::parse_document(
+        r#"<span class="value">5</span><span class="range">1-2</span>"#,
+    );
+    let failures = measure
+        .extract_into(&html.root_element(), UpdatePolicy::KeepPrevious)
+        .unwrap();
+    assert_eq!(
+        failures.iter().map(|f| f.field).collect::<Vec<_>>(),
+        vec!["unit"]
+    );
+    assert_eq!((measure.value, measure.fallback), (5000, 5001));
+
+    let fingerprint = Measure::fingerprint_from_str(input);
+    let changed = input.replace(">k<", ">m<");
+    let result = Measure::reextract_from_str(measure, &fingerprint, &changed).unwrap();
+    // the value is not recomputed, since its own element has not changed
+    assert_eq!(result.recomputed, vec!["unit"]);
+    assert_eq!(result.data.value, 5000);
+}
+
+#[test]
+fn extract_with_context() {
+    use html_extractor::{ExtractWithContext, UpdatePolicy};
+
+    struct Site {
+        base: String,
+    }
+    fn join(base: &str, path: &str) -> Result<String, std::convert::Infallible> {
+        Ok(format!("{}{}", base, path))
+    }
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Link {
+            #![context(site: Site)]
+            href: String = (attr["href"] of "a", parse with |s: &str| join(&site.base, s)),
+        }
+        #[derive(Debug, PartialEq)]
+        Page {
+            #![context(site: Site)]
+            links: Vec<Link> = (elem of "li", collect),
+            texts: Vec<Text> = (elem of "li", collect),
+            source: String = (skip, init with site.base.clone()),
+        }
+        // a structure without context can be nested in one with it
+        #[derive(Debug, PartialEq)]
+        Text {
+            text: String = (text of "a"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    #[extract(context(site: Site))]
+    struct Derived {
+        #[extract(skip, init = site.base.len())]
+        base_len: usize,
+    }
+
+    let site = Site {
+        base: "https://example.com".to_owned(),
+    };
+    let input = r#"<ul><li><a href="/a">A</a></li><li><a href="/b">B</a></li></ul>"#;
+    let mut page = Page::extract_from_str_with_context(input, &site).unwrap();
+    assert_eq!(
+        page,
+        Page {
+            links: vec![
+                Link {
+                    href: "https://example.com/a".to_owned()
+                },
+                Link {
+                    href: "https://example.com/b".to_owned()
+                },
+            ],
+            texts: vec![
+                Text {
+                    text: "A".to_owned()
+                },
+                Text {
+                    text: "B".to_owned()
+                },
+            ],
+            source: "https://example.com".to_owned(),
+        }
+    );
+
+    // the context is passed as an argument, so it is given on other threads too
+    let from_thread = std::thread::scope(|scope| {
+        scope
+            .spawn(|| Link::extract_from_str_with_context(input, &site))
+            .join()
+            .unwrap()
+    })
+    .unwrap();
+    assert_eq!(from_thread.href, "https://example.com/a");
+
+    let other = Site {
+        base: "https://example.org".to_owned(),
+    };
+    let html = scraper::Html::parse_document(r#"<li><a href="/c">C</a></li>"#);
+    page.extract_into_with_context(&html.root_element(), UpdatePolicy::AllOrNothing, &other)
+        .unwrap();
+    assert_eq!(page.links[0].href, "https://example.org/c");
+    assert_eq!(page.texts[0].text, "C");
+    assert_eq!(page.source, "https://example.com");
+
+    assert_eq!(
+        Derived::extract_from_str_with_context("", &site).unwrap(),
+        Derived { base_len: 19 }
+    );
+}
+
+#[test]
+fn runtime_selectors() {
+    use html_extractor::{ExtractWithContext, HtmlExtractor};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Cell {
+            #![context(column: String)]
+            id: String = (attr["data-id"] of "tr"),
+            value: u32 = (text of format!("#{}-{}", field_id, column)),
+            notes: Vec<String> = (text of format!(".{}-note", column), collect),
+            flagged: bool = (presence of format!("#{}-flag", field_id)),
+            fallback: u32 = (text of format!("#{}-missing", field_id) else text of "#none", or default),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = format!("#{}", "b-price"))]
+        price: u32,
+    }
+
+    let input = r#"
+        <table><tr data-id="b">
+            <td id="b-price">10</td><td id="b-flag"></td>
+            <td class="price-note">x</td><td class="price-note">y</td>
+        </tr></table>
+    "#;
+    assert_eq!(
+        Cell::extract_from_str_with_context(input, &"price".to_owned()).unwrap(),
+        Cell {
+            id: "b".to_owned(),
+            value: 10,
+            notes: vec!["x".to_owned(), "y".to_owned()],
+            flagged: true,
+            fallback: 0,
+        }
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived { price: 10 }
+    );
+
+    let err = Cell::extract_from_str_with_context(input, &"size".to_owned()).unwrap_err();
+    assert!(
+        matches!(&err, html_extractor::Error::ElementNotFound(e) if e.selector == "#b-size"),
+        "{}",
+        err
+    );
+    let err = Cell::extract_from_str_with_context(input, &"[".to_owned()).unwrap_err();
+    assert!(
+        err.to_string().contains("cannot parse the selector `#b-[`"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn field_validation() {
+    use html_extractor::{HtmlExtractor, UpdatePolicy};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Listing {
+            min: u32 = (text of "#min"),
+            price: u32 = (text of "#price", validate with |price| *price >= *field_min),
+            tags: Vec<String> = (text of "li", collect, validate with |tags: &Vec<String>| tags.len() <= 2),
+            note: Option<String> = (text of "#note", optional, validate with Option::is_none),
+            (major: u8, minor: u8) = (text of "#version", capture with r"(\d+)\.(\d+)", validate with |v: &(u8, u8)| v.0 > 0),
+            in "#box" {
+                label: String = (text of "span", or default, validate with |s: &String| s.is_empty()),
+            }
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "#price", validate = |p: &u32| *p > 100)]
+        price: u32,
+    }
+
+    let input = r#"<p id="min">10</p><p id="price">20</p><li>a</li><p id="version">1.2</p>"#;
+    let mut listing = Listing::extract_from_str(input).unwrap();
+    assert_eq!((listing.price, listing.major), (20, 1));
+
+    let errors = [
+        input.replace(">20<", ">5<"),
+        input.replace("<li>a</li>", "<li>a</li><li>b</li><li>c</li>"),
+        format!(r#"{}<p id="note">x</p>"#, input),
+        input.replace(">1.2<", ">0.2<"),
+        format!(r#"{}<div id="box"><span>x</span></div>"#, input),
+    ];
+    for (html, field) in errors
+        .iter()
+        .zip(&["price", "tags", "note", "major", "label"])
+    {
+        let err = Listing::extract_from_str(html).unwrap_err();
+        assert!(
+            err.to_string().contains(&format!(
+                "field `{}` in struct `Listing`, the validation failed",
+                field
+            )),
+            "{}",
+            err
+        );
+    }
+
+    let html = scraper::Html::parse_document(&errors[0]);
+    let failures = listing
+        .extract_into(&html.root_element(), UpdatePolicy::KeepPrevious)
+        .unwrap();
+    assert_eq!(
+        failures.iter().map(|f| f.field).collect::<Vec<_>>(),
+        vec!["price"]
+    );
+    assert_eq!(listing.price, 20);
+
+    assert!(Derived::extract_from_str(input).is_err());
+}
+
+#[test]
+fn struct_validation() {
+    use html_extractor::HtmlExtractor;
+    use std::error::Error as _;
+
+    #[derive(Debug, PartialEq)]
+    struct OutOfStock {
+        name: String,
+    }
+    impl std::fmt::Display for OutOfStock {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "`{}` is out of stock", self.name)
+        }
+    }
+    impl std::error::Error for OutOfStock {}
+
+    html_extractor::html_extractor! {
+        #[derive(Debug)]
+        Stock {
+            #![validate(|s: &Stock| if s.count > 0 { Ok(()) } else { Err(OutOfStock { name: s.name.clone() }) })]
+            name: String = (text of "h1"),
+            count: usize = (text of "#count"),
+        }
+    }
+
+    let err = Stock::extract_from_str(r#"<h1>Tea</h1><p id="count">0</p>"#).unwrap_err();
+    assert_eq!(err.code(), "E_VALIDATION");
+    assert_eq!(
+        err.to_string(),
+        "validating struct `Stock`: `Tea` is out of stock"
+    );
+    assert!(matches!(
+        err,
+        html_extractor::Error::Validation {
+            struct_name: "Stock",
+            ..
+        }
+    ));
+    assert_eq!(
+        err.source().unwrap().downcast_ref::<OutOfStock>(),
+        Some(&OutOfStock {
+            name: "Tea".to_owned()
+        })
+    );
+    #[cfg(feature = "serde")]
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        serde_json::json!({
+            "code": "E_VALIDATION",
+            "message": err.to_string(),
+            "struct": "Stock",
+        })
+    );
+
+    // a string is accepted as the error, too
+    let err = Annotated::extract_from_str("").unwrap_err();
+    assert_eq!(err.source().unwrap().to_string(), "empty");
+    #[html_extractor::extractor(validate = |a: &Annotated| if a.texts.is_empty() { Err("empty") } else { Ok(()) })]
+    #[derive(Debug)]
+    struct Annotated {
+        #[extract(text = "p", collect)]
+        texts: Vec<String>,
+    }
+}
+
+#[test]
+fn map_fields() {
+    use html_extractor::HtmlExtractor;
+    use std::sync::Arc;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Product {
+            name: String = (text of "#name", map with |s: String| s.to_lowercase()),
+            tags: Vec<String> = (text of "li", collect, map with |s: String| s.trim_start_matches('#').to_owned()),
+            rating: Option<u8> = (text of "#rating", optional, map with |n: u8| n.min(5)),
+            label: Arc<str> = (text of "#name", map with |s: String| Arc::from(s.as_str())),
+            (w: u32, h: u32) = (text of "#size", capture with r"(\d+)x(\d+)", map with |(w, h): (u32, u32)| (h, w)),
+            doubled: u32 = (text of "#size", parse with first_number, map with |n: u32| n * 2),
+        }
+    }
+    fn first_number(s: &str) -> Result<u32, std::num::ParseIntError> {
+        s.split('x').next().unwrap().parse()
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "#rating", map = |n: u32| n * 10, validate = |n: &u32| *n == 90)]
+        rating: u32,
+    }
+
+    let input =
+        r##"<p id="name">FOO</p><li>#a</li><li>b</li><p id="rating">9</p><p id="size">3x4</p>"##;
+    let product = Product::extract_from_str(input).unwrap();
+    assert_eq!(
+        product,
+        Product {
+            name: "foo".to_owned(),
+            tags: vec!["a".to_owned(), "b".to_owned()],
+            rating: Some(5),
+            label: Arc::from("FOO"),
+            w: 4,
+            h: 3,
+            doubled: 6,
+        }
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived { rating: 90 }
+    );
+
+    let err = Product::extract_from_str(&input.replace(">9<", ">x<")).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("field `rating` in struct `Product`, cannot parse `x`"));
+}
+
+#[test]
+fn filter_collected_items() {
+    use html_extractor::HtmlExtractor;
+    use std::collections::BTreeSet;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Page {
+            tags: Vec<String> = (text of ".tag", collect, filter with |t: &String| !t.is_empty()),
+            odd: BTreeSet<u32> = (filter with is_odd, text of "li", collect),
+            pairs: Vec<(u32,)> = (text of "li", capture with r"(\d)", collect, filter with |_: &(u32,)| true, validate with |p| p.is_empty()),
+            empty: Vec<u32> = (text of ".none", collect, filter with is_odd),
+        }
+    }
+    fn is_odd(n: &u32) -> bool {
+        n % 2 == 1
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "li", collect, filter = |n: &u32| *n > 2)]
+        large: Vec<u32>,
+    }
+
+    let input = r#"<p class="tag">a</p><p class="tag"> </p><p class="tag">b</p><li>1</li><li>2</li><li>3</li><li>5</li>"#;
+    let page = Page::extract_from_str(input).unwrap_err();
+    assert!(page.to_string().contains("field `pairs`"), "{}", page);
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Filtered {
+            tags: Vec<String> = (text of ".tag", collect, filter with |t: &String| !t.is_empty()),
+            odd: BTreeSet<u32> = (filter with is_odd, text of "li", collect),
+            empty: Vec<u32> = (text of ".none", collect, filter with is_odd),
+        }
+    }
+    assert_eq!(
+        Filtered::extract_from_str(input).unwrap(),
+        Filtered {
+            tags: vec!["a".to_owned(), "b".to_owned()],
+            odd: vec![1, 3, 5].into_iter().collect(),
+            empty: vec![],
+        }
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived { large: vec![3, 5] }
+    );
+}
+
+#[test]
+fn collect_skip_and_limit() {
+    use html_extractor::{provenance::ProvenanceExtractor, HtmlExtractor};
+
+    const LIMIT: usize = 2;
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Table {
+            skipped: Vec<u32> = (text of "td", collect skip 1),
+            limited: Vec<String> = (text of "td", collect limit 2),
+            both: Vec<u32> = (text of "td", collect limit LIMIT skip 1, filter with |n: &u32| *n != 2),
+            beyond: Vec<u32> = (text of "td", collect skip 10),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    #[extract(provenance)]
+    struct Derived {
+        #[extract(text = "td", collect(skip = 2, limit = 1))]
+        third: Vec<u32>,
+    }
+
+    let input = r#"<table><tr><td>0</td><td>1</td><td>2</td><td>3</td></tr></table>"#;
+    assert_eq!(
+        Table::extract_from_str(input).unwrap(),
+        Table {
+            skipped: vec![1, 2, 3],
+            limited: vec!["0".to_owned(), "1".to_owned()],
+            both: vec![1],
+            beyond: vec![],
+        }
+    );
+
+    let html = scraper::Html::parse_document(input);
+    let (derived, provenance) = Derived::extract_with_provenance(&html.root_element()).unwrap();
+    assert_eq!(derived, Derived { third: vec![2] });
+    assert_eq!(provenance.get("third").unwrap().nodes.len(), 1);
+}
+
+#[test]
+fn collect_into_arrays() {
+    use html_extractor::HtmlExtractor;
+
+    const COLUMNS: usize = 3;
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Row {
+            values: [f64; 3] = (text of "td", collect),
+            names: [String; COLUMNS] = (attr["class"] of "td", collect),
+            empty: [u32; 0] = (text of "th", collect),
+            pairs: [(u32, u32); 1] = (text of "#pair", capture with r"(\d+) (\d+)", collect),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "td", collect(skip = 1))]
+        tail: [f64; 2],
+    }
+
+    let input = r#"<table><tr><td class="a">1</td><td class="b">2.5</td><td class="c">3</td></tr></table><p id="pair">1 2</p>"#;
+    assert_eq!(
+        Row::extract_from_str(input).unwrap(),
+        Row {
+            values: [1.0, 2.5, 3.0],
+            names: ["a".to_owned(), "b".to_owned(), "c".to_owned()],
+            empty: [],
+            pairs: [(1, 2)],
+        }
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived { tail: [2.5, 3.0] }
+    );
+
+    let err = Row::extract_from_str(&input.replace("</tr>", "<td>4</td></tr>")).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("field `values` in struct `Row`, 4 items are extracted, but 3 are expected"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn joined_selectors() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        selector OLD = ".old";
+
+        #[derive(Debug, PartialEq)]
+        Page {
+            items: Vec<u32> = (text of OLD + "-item" and ".new-item", collect),
+            first: u32 = (text of ".new-item" and OLD + "-item"),
+            titles: Vec<String> = (attr["title"] of "a" and "abbr", collect),
+            missing: Option<u32> = (text of ".none" and ".nothing", optional),
+        }
+    }
+
+    let input = r#"<ul><li class="old-item">1</li><li class="new-item">2</li><li class="old-item">3</li></ul><abbr title="x"></abbr><a title="y"></a>"#;
+    assert_eq!(
+        Page::extract_from_str(input).unwrap(),
+        Page {
+            items: vec![1, 2, 3],
+            first: 1,
+            titles: vec!["x".to_owned(), "y".to_owned()],
+            missing: None,
+        }
+    );
+
+    match Page::extract_from_str("").unwrap_err() {
+        html_extractor::Error::ElementNotFound(not_found) => {
+            assert_eq!(not_found.selector, ".new-item, .old-item")
+        }
+        err => panic!("{}", err),
+    }
+}
+
+#[test]
+fn enumerated_collect() {
+    use html_extractor::HtmlExtractor;
+    use std::collections::HashMap;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Leaderboard {
+            names: Vec<(usize, String)> = (text of "td", collect enumerate),
+            ranks: HashMap<usize, String> = (text of "td", collect skip 1 enumerate, filter with |(_, name): &(usize, String)| name != "c"),
+            scores: [(usize, u32); 2] = (attr["data-score"] of "td", collect enumerate limit 2, map with |s: u32| s * 10),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "td", collect(enumerate, limit = 1))]
+        first: Vec<(usize, String)>,
+    }
+
+    let input = r#"<table><tr><td data-score="3">a</td><td data-score="2">b</td><td data-score="1">c</td><td data-score="0">d</td></tr></table>"#;
+    let leaderboard = Leaderboard::extract_from_str(input).unwrap();
+    assert_eq!(
+        leaderboard.names,
+        vec![
+            (0, "a".to_owned()),
+            (1, "b".to_owned()),
+            (2, "c".to_owned()),
+            (3, "d".to_owned())
+        ]
+    );
+    assert_eq!(
+        leaderboard.ranks,
+        vec![(0, "b".to_owned()), (2, "d".to_owned())]
+            .into_iter()
+            .collect()
+    );
+    assert_eq!(leaderboard.scores, [(0, 30), (1, 20)]);
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived {
+            first: vec![(0, "a".to_owned())]
+        }
+    );
+}
+
+#[test]
+fn keyed_collect() {
+    use html_extractor::HtmlExtractor;
+    use std::collections::{BTreeMap, HashMap};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Users {
+            names: HashMap<u32, String> = (elem of ".user", collect, key with (attr["data-id"] of ":scope"), value with (text of ".name")),
+            users: BTreeMap<String, User> = (elem of ".user", collect, key with (text of ".name", uppercase)),
+            later: Vec<(u32, String)> = (elem of ".user", collect, key with (attr["data-id"] of ":scope"), value with (text of ".name"), filter with |(id, _): &(u32, String)| *id > 1),
+            roles: BTreeMap<u32, Vec<String>> = (elem of ".user", collect, key with (text of ".name", map with |s: String| s.len() as u32), value with (text of ".role", collect)),
+        }
+        #[derive(Debug, PartialEq)]
+        User {
+            age: u32 = (text of ".age"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(elem = ".user", collect, key(attr["data-id"] of ":scope"), value = (text of ".age"))]
+        ages: BTreeMap<u32, u32>,
+    }
+
+    let input = r#"
+        <div class="user" data-id="1"><span class="name">foo</span><span class="age">20</span><i class="role">a</i></div>
+        <div class="user" data-id="2"><span class="name">barbaz</span><span class="age">30</span><i class="role">b</i><i class="role">c</i></div>
+    "#;
+    let users = Users::extract_from_str(input).unwrap();
+    assert_eq!(
+        users.names,
+        vec![(1, "foo".to_owned()), (2, "barbaz".to_owned())]
+            .into_iter()
+            .collect()
+    );
+    assert_eq!(
+        users.users.into_iter().collect::<Vec<_>>(),
+        vec![
+            ("BARBAZ".to_owned(), User { age: 30 }),
+            ("FOO".to_owned(), User { age: 20 })
+        ]
+    );
+    assert_eq!(users.later, vec![(2, "barbaz".to_owned())]);
+    assert_eq!(users.roles.len(), 2);
+    assert_eq!(users.roles[&3], vec!["a".to_owned()]);
+    assert_eq!(users.roles[&6], vec!["b".to_owned(), "c".to_owned()]);
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap().ages,
+        vec![(1, 20), (2, 30)].into_iter().collect()
+    );
+
+    let err = Users::extract_from_str(&input.replace(r#"data-id="2""#, "")).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("field `names` in struct `Users`, attribute `data-id` is not found"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn grouped_collect() {
+    use html_extractor::HtmlExtractor;
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Catalog {
+            names: HashMap<String, Vec<String>> = (elem of "tr", collect, group by (attr["data-category"] of ":scope"), value with (text of ".name")),
+            prices: BTreeMap<String, BTreeSet<u32>> = (text of ".price", collect, replace "$" with "", group by (attr["class"] of ":scope")),
+            rows: BTreeMap<bool, Vec<Row>> = (elem of "tr", collect skip 1, group by (presence of ".sale")),
+        }
+        #[derive(Debug, PartialEq)]
+        Row {
+            name: String = (text of ".name"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(elem = "tr", collect, group(attr["data-category"] of ":scope"), value(text of ".name"))]
+        names: BTreeMap<String, Vec<String>>,
+    }
+
+    let input = r#"<table>
+        <tr data-category="fruit"><td class="name">apple</td><td class="price">$3</td></tr>
+        <tr data-category="vegetable"><td class="name">carrot</td><td class="price">$2</td></tr>
+        <tr data-category="fruit"><td class="name">banana</td><td class="price sale">$2</td></tr>
+    </table>"#;
+    let catalog = Catalog::extract_from_str(input).unwrap();
+    assert_eq!(catalog.names.len(), 2);
+    assert_eq!(catalog.names["fruit"], vec!["apple", "banana"]);
+    assert_eq!(catalog.names["vegetable"], vec!["carrot"]);
+    assert_eq!(
+        catalog.prices,
+        vec![
+            ("price".to_owned(), vec![2, 3].into_iter().collect()),
+            ("price sale".to_owned(), vec![2].into_iter().collect())
+        ]
+        .into_iter()
+        .collect()
+    );
+    assert_eq!(
+        catalog.rows[&false],
+        vec![Row {
+            name: "carrot".to_owned()
+        }]
+    );
+    assert_eq!(
+        catalog.rows[&true],
+        vec![Row {
+            name: "banana".to_owned()
+        }]
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap().names["fruit"],
+        vec!["apple", "banana"]
+    );
+}
+
+#[test]
+fn zipped_collect() {
+    use html_extractor::HtmlExtractor;
+    use std::collections::HashMap;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Specs {
+            pairs: Vec<(String, String)> = (text of ".label", collect, zip with (text of ".value")),
+            map: HashMap<String, u32> = (text of ".label", collect, zip with (attr["data-n"] of ".value")),
+            numbered: Vec<(usize, (String, u32))> = (text of ".label", collect enumerate skip 1, zip with (attr["data-n"] of ".value", collect skip 1), filter with |(i, _): &(usize, (String, u32))| *i == 0),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = ".label", collect, zip(text of ".value"))]
+        pairs: Vec<(String, String)>,
+    }
+
+    let input = r#"<dl>
+        <dt class="label">color</dt><dd class="value" data-n="1">red</dd>
+        <dt class="label">size</dt><dd class="value" data-n="2">L</dd>
+    </dl>"#;
+    let specs = Specs::extract_from_str(input).unwrap();
+    assert_eq!(
+        specs.pairs,
+        vec![
+            ("color".to_owned(), "red".to_owned()),
+            ("size".to_owned(), "L".to_owned())
+        ]
+    );
+    assert_eq!(specs.map["size"], 2);
+    assert_eq!(specs.numbered, vec![(0, ("size".to_owned(), 2))]);
+    assert_eq!(Derived::extract_from_str(input).unwrap().pairs, specs.pairs);
+
+    let err = Specs::extract_from_str(
+        &input.replace("</dl>", r#"<dd class="value" data-n="3">x</dd></dl>"#),
+    )
+    .unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "field `pairs` in struct `Specs`, 2 elements matched the selector, but 3 items are extracted to zip with"
+        ),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn folded_collect() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Cart {
+            total: u32 = (text of ".price", collect, fold with |sum, price: u32| sum + price),
+            max: Option<u32> = (text of ".price", collect, fold with |max: Option<u32>, price: u32| max.max(Some(price))),
+            expensive: usize = (text of ".price", collect, filter with |p: &u32| *p >= 20, fold with |n, _: u32| n + 1),
+            summary: String = (text of ".name", collect, zip with (text of ".price"), fold from "items:".to_owned() with |s: String, (name, price): (String, u32)| format!("{} {}={}", s, name, price)),
+            none: u32 = (text of ".none", collect, fold from 7 with |sum, n: u32| sum + n),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = ".price", collect, fold = |p, n: u32| p * n, fold_from = 1)]
+        product: u32,
+    }
+
+    let input = r#"<li><span class="name">a</span><span class="price">10</span></li><li><span class="name">b</span><span class="price">20</span></li><li><span class="name">c</span><span class="price">30</span></li>"#;
+    assert_eq!(
+        Cart::extract_from_str(input).unwrap(),
+        Cart {
+            total: 60,
+            max: Some(30),
+            expensive: 2,
+            summary: "items: a=10 b=20 c=30".to_owned(),
+            none: 7,
+        }
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived { product: 6000 }
+    );
+}
+
+#[test]
+fn joined_collect() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Article {
+            description: String = (text of "p", join with "\n\n"),
+            words: String = (text of "p", normalize_whitespace, uppercase, collect limit 2, join with " "),
+            rest: String = (text of "p", collect skip 1, join with "|", filter with |p: &String| p.starts_with('c')),
+            total: u32 = (attr["data-n"] of "p", join with "", map with |n: u32| n + 1),
+            none: String = (text of ".none", join with ", "),
+            (first: u32, second: u32) = (attr["data-n"] of "p", join with "-", capture with r"(\d+)-(\d+)"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "p", join = "/")]
+        paragraphs: String,
+    }
+
+    let input = r#"<p data-n="1">a  b</p><p data-n="2">c</p><p data-n="3">d</p>"#;
+    assert_eq!(
+        Article::extract_from_str(input).unwrap(),
+        Article {
+            description: "a  b\n\nc\n\nd".to_owned(),
+            words: "A B C".to_owned(),
+            rest: "c".to_owned(),
+            total: 124,
+            none: String::new(),
+            first: 1,
+            second: 2,
+        }
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap().paragraphs,
+        "a  b/c/d"
+    );
+}
+
+#[test]
+fn nth_matched_element() {
+    use html_extractor::HtmlExtractor;
+
+    const LAST_INDEX: usize = 3;
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        List {
+            first: u32 = (text of "li"[0]),
+            third: u32 = (text of ".item"[2]),
+            last: u32 = (text of "li"[LAST_INDEX]),
+            second_text: String = (text[1] of "li"[1]),
+            attr: String = (attr["id"] of "li" and "p"[1]),
+            has_fifth: bool = (presence of "li"[4]),
+            missing: Option<u32> = (text of "li"[4], optional),
+            fallback: u32 = (text of "li"[9] else text of "li"[1]),
+            collected: Vec<u32> = (text of "li"[1], collect),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "li"[3])]
+        fourth: u32,
+    }
+
+    let input = r#"<ul><li id="a" class="item">1</li><li id="b" class="item">2<b>-</b>x</li></ul><p id="p">p</p><ul><li id="c" class="item">3</li><li>4</li></ul>"#;
+    assert_eq!(
+        List::extract_from_str(input).unwrap(),
+        List {
+            first: 1,
+            third: 3,
+            last: 4,
+            second_text: "-".to_owned(),
+            attr: "b".to_owned(),
+            has_fifth: false,
+            missing: None,
+            fallback: 2,
+            collected: vec![2],
+        }
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived { fourth: 4 }
+    );
+    assert!(List::extract_from_str("<li>1</li><li>2</li>").is_err());
+}
+
+#[test]
+fn last_matched_element() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Comments {
+            newest: String = (text of ".comment"[last]),
+            last_page: u32 = (attr["data-page"] of ".pagination a"[last]),
+            none: Option<String> = (text of ".none"[last], optional),
+            fallback: String = (text of ".none"[last] else text of ".comment"[last], uppercase),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = ".comment"[last])]
+        newest: String,
+    }
+
+    let input = r#"
+        <div><p class="comment">a</p><p class="comment">b</p></div>
+        <div><p class="comment">c</p></div>
+        <nav class="pagination"><a data-page="1"></a><a data-page="2"></a><a data-page="9"></a></nav>
+    "#;
+    assert_eq!(
+        Comments::extract_from_str(input).unwrap(),
+        Comments {
+            newest: "c".to_owned(),
+            last_page: 9,
+            none: None,
+            fallback: "C".to_owned(),
+        }
+    );
+    assert_eq!(Derived::extract_from_str(input).unwrap().newest, "c");
+}
+
+#[test]
+fn collect_count_bounds() {
+    use html_extractor::HtmlExtractor;
+
+    const MAX: usize = 3;
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Results {
+            items: Vec<u32> = (text of "li", collect at least 1 at most MAX),
+            odd: Vec<u32> = (text of "li", collect at least 2, filter with |n: &u32| n % 2 == 1),
+            total: u32 = (text of "li", collect skip 1 at most 3, fold with |sum, n: u32| sum + n),
+            // the bounds count the elements before `skip` and `limit`
+            first: Vec<u32> = (text of "li", collect limit 1 at least 3),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text = "li", collect(at_least = 2, at_most = 2))]
+        items: Vec<u32>,
+    }
+
+    let input = "<li>1</li><li>2</li><li>3</li>";
+    assert_eq!(
+        Results::extract_from_str(input).unwrap(),
+        Results {
+            items: vec![1, 2, 3],
+            odd: vec![1, 3],
+            total: 5,
+            first: vec![1],
+        }
+    );
+
+    let err = Results::extract_from_str("").unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "field `items` in struct `Results`, 0 elements matched the selector, but at least 1 are expected"
+        ),
+        "{}",
+        err
+    );
+    let err = Results::extract_from_str("<li>1</li><li>2</li><li>3</li><li>4</li>").unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "field `items` in struct `Results`, 4 elements matched the selector, but at most 3 are expected"
+        ),
+        "{}",
+        err
+    );
+    let err = Results::extract_from_str("<li>1</li><li>3</li>").unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "field `first` in struct `Results`, 2 elements matched the selector, but at least 3 are expected"
+        ),
+        "{}",
+        err
+    );
+    let err = Results::extract_from_str("<li>1</li><li>3</li>").unwrap_err();
+    assert!(
+        err.to_string().contains(
+            "field `first` in struct `Results`, 2 elements matched the selector, but at least 3 are expected"
+        ),
+        "{}",
+        err
+    );
+    assert!(Derived::extract_from_str(input).is_err());
+    assert_eq!(
+        Derived::extract_from_str("<li>1</li><li>2</li>").unwrap(),
+        Derived { items: vec![1, 2] }
+    );
+}
+
+#[test]
+fn own_text_target() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Price {
+            amount: u32 = (own_text of ".price", replace "$" with ""),
+            labels: Vec<String> = (own_text of "li", collect),
+            empty: String = (own_text of ".empty"),
+            nested: Option<String> = (own_text of ".none", optional),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(own_text = ".price")]
+        price: String,
+    }
+
+    let input = r#"
+        <p class="price">
+            $<span class="currency">USD</span>1<!-- comment -->00 <small>(tax included)</small>
+        </p>
+        <ul><li><b>new</b> a</li><li>b <i>sale</i></li></ul>
+        <p class="empty"><span>only child</span></p>
+    "#;
+    assert_eq!(
+        Price::extract_from_str(input).unwrap(),
+        Price {
+            amount: 100,
+            labels: vec!["a".to_owned(), "b".to_owned()],
+            empty: String::new(),
+            nested: None,
+        }
+    );
+    assert_eq!(Derived::extract_from_str(input).unwrap().price, "$100");
+}
+
+#[test]
+fn text_all_target() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Article {
+            body: String = (text_all of "article"),
+            paragraphs: Vec<String> = (text_all of "p", collect),
+            empty: String = (text_all of ".empty"),
+            (count: usize,) = (text_all of ".count", capture with r"(\d+) items"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(text_all = "article")]
+        body: String,
+    }
+
+    let input = r#"
+        <article>
+            <p>The <b>qu</b>ick  brown
+                fox</p>
+            <p><a href="\#">jumps</a>
+                over</p>
+        </article>
+        <div class="empty"> <span> </span> </div>
+        <div class="count"><b>42</b> items</div>
+    "#;
+    let article = Article::extract_from_str(input).unwrap();
+    assert_eq!(article.body, "The quick brown fox jumps over");
+    assert_eq!(
+        article.paragraphs,
+        vec!["The quick brown fox", "jumps over"]
+    );
+    assert_eq!(article.empty, "");
+    assert_eq!(article.count, 42);
+    assert_eq!(Derived::extract_from_str(input).unwrap().body, article.body);
+}
+
+#[test]
+fn attrs_target() {
+    use html_extractor::HtmlExtractor;
+    use std::collections::{BTreeMap, HashMap};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Widget {
+            config: HashMap<String, String> = (attrs of "#widget"),
+            sorted: BTreeMap<String, String> = (attrs of "#widget"),
+            empty: HashMap<String, String> = (attrs of "span"),
+            items: Vec<BTreeMap<String, String>> = (attrs of "li", collect),
+            count: usize = (attrs of "#widget", map with |attrs: HashMap<String, String>| attrs.len()),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(attrs = "#widget")]
+        config: HashMap<String, String>,
+    }
+
+    let input = r#"
+        <div id="widget" data-theme="dark" data-user-id="42" hidden></div>
+        <span></span>
+        <ul>
+            <li data-a="1"></li>
+            <li data-b="2" class="x"></li>
+        </ul>
+    "#;
+    let widget = Widget::extract_from_str(input).unwrap();
+    let expected = |pairs: &[(&str, &str)]| {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let config = expected(&[
+        ("id", "widget"),
+        ("data-theme", "dark"),
+        ("data-user-id", "42"),
+        ("hidden", ""),
+    ]);
+    assert_eq!(widget.config, config.clone().into_iter().collect());
+    assert_eq!(widget.sorted, config);
+    assert!(widget.empty.is_empty());
+    assert_eq!(
+        widget.items,
+        vec![
+            expected(&[("data-a", "1")]),
+            expected(&[("data-b", "2"), ("class", "x")]),
+        ]
+    );
+    assert_eq!(widget.count, 4);
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap().config,
+        widget.config
+    );
+}
+
+#[test]
+fn position_target() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Ranking {
+            mine: usize = (position of ".mine"),
+            featured: Vec<usize> = (position of ".featured", collect),
+            rank: usize = (position of ".mine", map with |i: usize| i + 1),
+            first: usize = (position of "li"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(position = ".mine")]
+        mine: usize,
+    }
+
+    let input = r#"
+        <ol>
+            text is not counted
+            <li class="featured">a</li>
+            <!-- neither are comments -->
+            <li>b</li>
+            <li class="mine featured">c</li>
+        </ol>
+        <ol>
+            <li>d</li>
+            <li class="featured">e</li>
+        </ol>
+    "#;
+    let ranking = Ranking::extract_from_str(input).unwrap();
+    assert_eq!(
+        ranking,
+        Ranking {
+            mine: 2,
+            featured: vec![0, 2, 1],
+            rank: 3,
+            first: 0,
+        }
+    );
+    assert_eq!(Derived::extract_from_str(input).unwrap().mine, 2);
+}
+
+#[test]
+fn parent_navigation() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Listing {
+            cheapest: String = (attr["data-sku"] of parent of ".cheapest"),
+            grandparent: String = (attr["id"] of parent of parent of ".cheapest"),
+            levels: String = (attr["id"] of parent[2] of ".cheapest"),
+            second: String = (attr["data-sku"] of parent of ".price"[1]),
+            last: String = (attr["data-sku"] of parent of ".price"[last]),
+            skus: Vec<String> = (attr["data-sku"] of parent of ".price", collect),
+            fallback: String = (attr["data-sku"] of parent of ".missing" else attr["data-sku"] of parent of ".price"),
+            top: Option<String> = (attr["id"] of parent[10] of ".cheapest", optional),
+            items: Vec<Item> = (elem of parent of ".price", collect),
+        }
+        #[derive(Debug, PartialEq)]
+        Item {
+            name: String = (text of ".name"),
+            // the parent of the matched element can be the element being extracted from
+            sku: String = (attr["data-sku"] of parent of ".name"),
+        }
+    }
+
+    let input = r#"
+        <section id="results">
+            <div data-sku="a1"><span class="name">A</span><span class="price">10</span></div>
+            <div data-sku="b2"><span class="name">B</span><span class="price cheapest">5</span></div>
+            <div data-sku="c3"><span class="name">C</span><span class="price">20</span></div>
+        </section>
+    "#;
+    let listing = Listing::extract_from_str(input).unwrap();
+    assert_eq!(listing.cheapest, "b2");
+    assert_eq!(listing.grandparent, "results");
+    assert_eq!(listing.levels, "results");
+    assert_eq!(listing.second, "b2");
+    assert_eq!(listing.last, "c3");
+    assert_eq!(listing.skus, vec!["a1", "b2", "c3"]);
+    assert_eq!(listing.fallback, "a1");
+    assert_eq!(listing.top, None);
+    assert_eq!(listing.items.len(), 3);
+    assert_eq!(listing.items[2].name, "C");
+    assert_eq!(listing.items[2].sku, "c3");
+}
+
+#[test]
+fn closest_navigation() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        selector ROW = "tr";
+
+        #[derive(Debug, PartialEq)]
+        Table {
+            row: String = (attr["id"] of closest "tr" of ".discount"),
+            aliased: String = (attr["id"] of closest ROW of ".discount"),
+            itself: String = (attr["id"] of closest "tr" of "tr"[1]),
+            section: String = (attr["id"] of closest "section" of parent of ".discount"),
+            rows: Vec<String> = (attr["id"] of closest "tr[id]" of "td", collect),
+            missing: Option<String> = (attr["id"] of closest "article" of ".discount", optional),
+            count: usize = (position of closest "tr" of ".discount"),
+        }
+    }
+
+    let input = r#"
+        <section id="prices">
+            <table>
+                <tr id="r1"><td><b>$10</b></td></tr>
+                <tr id="r2"><td><b class="discount">$5</b></td></tr>
+                <tr><td>$0</td></tr>
+            </table>
+        </section>
+    "#;
+    let table = Table::extract_from_str(input).unwrap();
+    assert_eq!(
+        table,
+        Table {
+            row: "r2".to_owned(),
+            aliased: "r2".to_owned(),
+            itself: "r2".to_owned(),
+            section: "prices".to_owned(),
+            rows: vec!["r1".to_owned(), "r2".to_owned()],
+            missing: None,
+            count: 1,
+        }
+    );
+}
+
+#[test]
+fn following_sibling() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Profile {
+            name: String = (next_text of "b.name"),
+            (age: usize,) = (next_text of "b.age", capture with r"(\d+)"),
+            labels: Vec<String> = (next_text of "b", collect),
+            empty: String = (next_text of "b.empty"),
+            link: String = (attr["href"] of next of "b.site"),
+            after_link: String = (next_text of next of "b.site"),
+            last: Option<String> = (text of next of "p > :last-child", optional),
+            count: usize = (next_text of "dt", parse with |s: &str| s.parse::<usize>()),
+            dd: String = (text of next of next of "dt"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(next_text = "b.name")]
+        name: String,
+    }
+
+    let input = r#"
+        <p>
+            <b class="name">Name:</b>  Alice <!-- comment --> Smith <br>
+            <b class="age">Age:</b> 30 years<br>
+            <b class="empty">Empty:</b><br>
+            <b class="site">Site:</b> <a href="https://example.com">link</a> (personal)
+            <i>end</i>
+        </p>
+        <dl><dt>Count:</dt>3<span></span><dd>ignored</dd></dl>
+    "#;
+    let profile = Profile::extract_from_str(input).unwrap();
+    assert_eq!(
+        profile,
+        Profile {
+            name: "Alice  Smith".to_owned(),
+            age: 30,
+            labels: vec![
+                "Alice  Smith".to_owned(),
+                "30 years".to_owned(),
+                "".to_owned(),
+                "".to_owned()
+            ],
+            empty: "".to_owned(),
+            link: "https://example.com".to_owned(),
+            after_link: "(personal)".to_owned(),
+            last: None,
+            count: 3,
+            dd: "ignored".to_owned(),
+        }
+    );
+    assert_eq!(Derived::extract_from_str(input).unwrap().name, profile.name);
+}
+
+#[test]
+fn self_selector() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Page {
+            cards: Vec<Card> = (elem of ".card", collect),
+            root: bool = (presence of self),
+        }
+        #[derive(Debug, PartialEq)]
+        Card {
+            id: usize = (attr["data-id"] of self),
+            class: String = (attr["class"] of self),
+            text: String = (text_all of self),
+            index: usize = (position of self),
+            featured: bool = (presence of self),
+            list: String = (attr["id"] of parent of self),
+            badge: Option<String> = (attr["title"] of ".badge" else attr["title"] of self, optional),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(attr = "data-id", selector = self)]
+        id: usize,
+    }
+
+    let input = r#"
+        <div id="list">
+            <div class="card" data-id="1" title="plain"><h2>foo</h2></div>
+            <div class="card featured" data-id="2"><h2>bar</h2> <span class="badge" title="new"></span></div>
+        </div>
+    "#;
+    let page = Page::extract_from_str(input).unwrap();
+    assert!(page.root);
+    assert_eq!(
+        page.cards[0],
+        Card {
+            id: 1,
+            class: "card".to_owned(),
+            text: "foo".to_owned(),
+            index: 0,
+            featured: true,
+            list: "list".to_owned(),
+            badge: Some("plain".to_owned()),
+        }
+    );
+    assert_eq!(page.cards[1].id, 2);
+    assert_eq!(page.cards[1].badge, Some("new".to_owned()));
+    let html = scraper::Html::parse_fragment(input);
+    let card = html
+        .select(&scraper::Selector::parse(".featured").unwrap())
+        .next()
+        .unwrap();
+    assert_eq!(Derived::extract(&card).unwrap(), Derived { id: 2 });
+}
+
+#[test]
+fn dataset_target() {
+    use html_extractor::HtmlExtractor;
+    use std::collections::{BTreeMap, HashMap};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        App {
+            state: BTreeMap<String, String> = (dataset of "#app"),
+            none: HashMap<String, String> = (dataset of "span"),
+            items: Vec<BTreeMap<String, String>> = (dataset of "li", collect),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(dataset = "#app")]
+        state: HashMap<String, String>,
+    }
+
+    let input = r#"
+        <div id="app" class="root" data-user-id="42" data-theme="dark" data-="empty" database="not data"></div>
+        <span class="x" title="y"></span>
+        <ul><li data-a="1"></li><li data-B="2"></li></ul>
+    "#;
+    let app = App::extract_from_str(input).unwrap();
+    let expected = |pairs: &[(&str, &str)]| {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<BTreeMap<_, _>>()
+    };
+    assert_eq!(
+        app.state,
+        expected(&[("user-id", "42"), ("theme", "dark"), ("", "empty")])
+    );
+    assert!(app.none.is_empty());
+    // attribute names are lowercased by the parser
+    assert_eq!(
+        app.items,
+        vec![expected(&[("a", "1")]), expected(&[("b", "2")])]
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap().state,
+        app.state.into_iter().collect()
+    );
+}
+
+#[test]
+fn attribute_tuples() {
+    use html_extractor::HtmlExtractor;
+    use std::sync::Arc;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Links {
+            (href: String, title: Arc<str>) = (attrs["href", "title"] of "a#link"),
+            (width: u32, height: u32,) = (attrs["width", "height"] of "img"),
+            (alt: String,) = (attrs["alt"] of "img"),
+            all: Vec<(String, usize)> = (attrs["href", "data-rank"] of "li > a", collect),
+            area: u32 = (attrs["width", "height"] of "img", map with |(w, h): (u32, u32)| w * h),
+            fallback: (String, String) = (attrs["href", "title"] of "a.missing" else attrs["href", "title"] of "a#link"),
+        }
+        #[derive(Debug, PartialEq)]
+        Broken {
+            (width: u32, height: u32) = (attrs["width", "height"] of "a#link"),
+        }
+        #[derive(Debug, PartialEq)]
+        Invalid {
+            (href: String, title: u32) = (attrs["href", "title"] of "a#link"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(attrs = ["href", "data-rank"], selector = "li > a", collect)]
+        all: Vec<(String, usize)>,
+    }
+
+    let input = r#"
+        <a id="link" href="/about" title="About us">about</a>
+        <img src="a.png" width="20" height="10" alt="logo">
+        <ul>
+            <li><a href="/1" data-rank="1">1</a></li>
+            <li><a href="/2" data-rank="2">2</a></li>
+        </ul>
+    "#;
+    let links = Links::extract_from_str(input).unwrap();
+    assert_eq!(
+        links,
+        Links {
+            href: "/about".to_owned(),
+            title: Arc::from("About us"),
+            width: 20,
+            height: 10,
+            alt: "logo".to_owned(),
+            all: vec![("/1".to_owned(), 1), ("/2".to_owned(), 2)],
+            area: 200,
+            fallback: ("/about".to_owned(), "About us".to_owned()),
+        }
+    );
+    assert_eq!(Derived::extract_from_str(input).unwrap().all, links.all);
+    let err = Broken::extract_from_str(input).unwrap_err().to_string();
+    assert!(err.contains("attribute `width` is not found"), "{}", err);
+    let err = Invalid::extract_from_str(input).unwrap_err().to_string();
+    assert!(
+        err.contains("cannot parse attribute `title` `About us`"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn text_nodes_target() {
+    use html_extractor::HtmlExtractor;
+    use std::collections::BTreeSet;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Contact {
+            address: Vec<String> = (texts of "#address"),
+            numbers: Vec<u32> = (texts of "#numbers"),
+            unique: BTreeSet<u32> = (texts of "#numbers"),
+            empty: Vec<String> = (texts of "#empty"),
+            rows: Vec<Vec<String>> = (texts of "tr", collect),
+            lines: usize = (texts of "#address", map with |lines: Vec<String>| lines.len()),
+        }
+        #[derive(Debug, PartialEq)]
+        Invalid {
+            numbers: Vec<u32> = (texts of "#address"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(texts = "#address")]
+        address: Vec<String>,
+    }
+
+    let input = r#"
+        <p id="address">
+            1-2-3 Example St.<br>
+            <b>Suite</b> 400<br>
+
+            Tokyo
+        </p>
+        <p id="numbers">3<br>1<br> 3 </p>
+        <p id="empty"> <br> </p>
+        <table><tr><td>a</td><td>b</td></tr><tr><td>c</td></tr></table>
+    "#;
+    let contact = Contact::extract_from_str(input).unwrap();
+    assert_eq!(
+        contact,
+        Contact {
+            address: vec![
+                "1-2-3 Example St.".to_owned(),
+                "Suite".to_owned(),
+                "400".to_owned(),
+                "Tokyo".to_owned()
+            ],
+            numbers: vec![3, 1, 3],
+            unique: vec![1, 3].into_iter().collect(),
+            empty: vec![],
+            rows: vec![vec!["a".to_owned(), "b".to_owned()], vec!["c".to_owned()]],
+            lines: 4,
+        }
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap().address,
+        contact.address
+    );
+    let err = Invalid::extract_from_str(input).unwrap_err().to_string();
+    assert!(err.contains("cannot parse `1-2-3 Example St.`"), "{}", err);
+}
+
+#[test]
+fn no_trim() {
+    use html_extractor::HtmlExtractor;
+    use std::collections::HashMap;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Code {
+            trimmed: String = (text of "pre"),
+            code: String = (text of "pre", no_trim),
+            own: String = (own_text of "p", no_trim),
+            after: String = (next_text of "b", no_trim),
+            html: String = (inner_html of "p", no_trim),
+            texts: Vec<String> = (texts of "p", no_trim),
+            (indent: String,) = (text of "pre", no_trim, capture with r"^( *)"),
+        }
+        #[derive(Debug, PartialEq)]
+        AllCode {
+            #![no_trim]
+            code: String = (text of "pre"),
+            lines: HashMap<String, String> = (
+                elem of "pre",
+                collect,
+                key with (text of ":scope"),
+                value with (own_text of ":scope"),
+            ),
+        }
+        #[derive(Debug, PartialEq)]
+        Tuple(
+            #![no_trim]
+            String = (text of "pre"),
+        );
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    #[extract(no_trim)]
+    struct DerivedStruct {
+        #[extract(text = "pre")]
+        code: String,
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct DerivedField {
+        #[extract(text = "pre", no_trim)]
+        code: String,
+        #[extract(text = "pre")]
+        trimmed: String,
+    }
+
+    let input = "<pre>  let x = 1;\n</pre><p> a <b>b</b> c </p>";
+    let code = Code::extract_from_str(input).unwrap();
+    assert_eq!(
+        code,
+        Code {
+            trimmed: "let x = 1;".to_owned(),
+            code: "  let x = 1;\n".to_owned(),
+            own: " a  c ".to_owned(),
+            after: " c ".to_owned(),
+            html: " a <b>b</b> c ".to_owned(),
+            texts: vec![" a ".to_owned(), "b".to_owned(), " c ".to_owned()],
+            indent: "  ".to_owned(),
+        }
+    );
+    let all = AllCode::extract_from_str(input).unwrap();
+    assert_eq!(all.code, code.code);
+    assert_eq!(all.lines[&code.code], code.code);
+    assert_eq!(Tuple::extract_from_str(input).unwrap().0, code.code);
+    assert_eq!(
+        DerivedStruct::extract_from_str(input).unwrap().code,
+        code.code
+    );
+    assert_eq!(
+        DerivedField::extract_from_str(input).unwrap(),
+        DerivedField {
+            code: code.code.clone(),
+            trimmed: code.trimmed.clone(),
+        }
+    );
+}
+
+#[test]
+fn decode_entities() {
+    use html_extractor::entities::decode;
+    use html_extractor::HtmlExtractor;
+
+    assert_eq!(decode("no references"), "no references");
+    assert_eq!(decode("a &amp;&amp; b"), "a && b");
+    assert_eq!(decode("&lt;&gt;&quot;&apos;&nbsp;"), "<>\"'\u{a0}");
+    assert_eq!(decode("&#65;&#x42;&#X43;&#x1F600;"), "ABC\u{1f600}");
+    // without `;`, as the parser does
+    assert_eq!(decode("&amp &copy2024 &#65 x"), "& \u{a9}2024 A x");
+    assert_eq!(decode("&notit; &notin;"), "\u{ac}it; \u{2209}");
+    assert_eq!(decode("&NotEqualTilde;"), "\u{2242}\u{338}");
+    // invalid or unknown references
+    assert_eq!(
+        decode("& &; &# &#x; &foo; &#xD800; &#x110000;"),
+        "& &; &# &#x; &foo; \u{fffd} \u{fffd}"
+    );
+    assert_eq!(
+        decode("&#128; &#99999999999999999999;"),
+        "\u{20ac} \u{fffd}"
+    );
+    assert_eq!(decode("日本&amp;語"), "日本&語");
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Post {
+            html: String = (inner_html of "#post", decode_entities),
+            raw: String = (inner_html of "#post"),
+            title: String = (attr["title"] of "#post", decode_entities),
+            escaped: String = (inner_html of "#post", replace "&amp;" with "and", decode_entities),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(inner_html = "#post", decode_entities)]
+        html: String,
+    }
+
+    let input = r#"<div id="post" title="&amp;amp;">It&#x2019;s <em>Tom &amp; Jerry</em> &copy; &quot;2024&quot;</div>"#;
+    let post = Post::extract_from_str(input).unwrap();
+    assert_eq!(
+        post,
+        Post {
+            html: "It\u{2019}s <em>Tom & Jerry</em> \u{a9} \"2024\"".to_owned(),
+            raw: "It\u{2019}s <em>Tom &amp; Jerry</em> \u{a9} \"2024\"".to_owned(),
+            title: "&".to_owned(),
+            escaped: "It\u{2019}s <em>Tom and Jerry</em> \u{a9} \"2024\"".to_owned(),
+        }
+    );
+    assert_eq!(Derived::extract_from_str(input).unwrap().html, post.html);
+}
+
+#[test]
+fn rendered_text() {
+    use html_extractor::render::html_to_text;
+    use html_extractor::HtmlExtractor;
+
+    let render = |html: &str| html_to_text(&scraper::Html::parse_fragment(html).root_element());
+    assert_eq!(render("  plain \n text  "), "plain text");
+    assert_eq!(render("<p>a</p><p>b</p>"), "a\n\nb");
+    assert_eq!(render("<div>a</div><div>b</div>text"), "a\nb\ntext");
+    assert_eq!(render("a<br>b<br><br>c"), "a\nb\n\nc");
+    assert_eq!(render("<p>a<br></p><p>b</p>"), "a\n\nb");
+    assert_eq!(render("<p> a <b>bold</b> <i> c </i> </p>"), "a bold c");
+    assert_eq!(
+        render("<ul><li>one</li><li>two <ul><li>nested</li></ul></li></ul>after"),
+        "one\ntwo\nnested\nafter"
+    );
+    assert_eq!(
+        render("<pre>  fn main() {\n      x\n  }</pre><p>p</p>"),
+        "fn main() {\n      x\n  }\n\np"
+    );
+    assert_eq!(
+        render("<p>before</p><pre>  indented</pre>"),
+        "before\n\n  indented"
+    );
+    assert_eq!(
+        render("<table><tr><th>k</th><th>v</th></tr><tr><td>a</td><td> 1 </td></tr></table>"),
+        "k\tv\na\t1"
+    );
+    assert_eq!(render("a<script>var x;</script><style>p{}</style>b"), "ab");
+    assert_eq!(render("<h1>Title</h1>text<hr>end"), "Title\n\ntext\n\nend");
+    assert_eq!(render(""), "");
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Article {
+            body: String = (rendered_text of "article"),
+            items: Vec<String> = (rendered_text of "li", collect),
+            (first_line: String,) = (rendered_text of "article", capture with r"^(.*)"),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(rendered_text = "article")]
+        body: String,
+    }
+
+    let input = r#"
+        <article>
+            <h2>Release   notes</h2>
+            <p>
+                Version <b>1.0</b> is out.<br>
+                Changes:
+            </p>
+            <ul>
+                <li>Faster <code>select</code></li>
+                <li>Fewer bugs</li>
+            </ul>
+        </article>
+    "#;
+    let article = Article::extract_from_str(input).unwrap();
+    assert_eq!(
+        article.body,
+        "Release notes\n\nVersion 1.0 is out.\nChanges:\n\nFaster select\nFewer bugs"
+    );
+    assert_eq!(article.items, vec!["Faster select", "Fewer bugs"]);
+    assert_eq!(article.first_line, "Release notes");
+    assert_eq!(Derived::extract_from_str(input).unwrap().body, article.body);
+
+    // deeply nested elements do not overflow the stack
+    let depth = 100_000;
+    let input = format!(
+        "<article><p>{}deep{}</p><p>end</p></article>",
+        "<span>".repeat(depth),
+        "</span>".repeat(depth)
+    );
+    assert_eq!(
+        Derived::extract_from_str(&input).unwrap().body,
+        "deep\n\nend"
+    );
+}
+
+#[test]
+fn named_capture_groups() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Product {
+            (currency: String, price: f64) = (text of ".price", capture by name with r"(?P<price>[\d.]+) (?P<currency>\w+)"),
+            // the unnamed group is skipped
+            (r#type: String, id: u32) = (attr["data-sku"] of ".product", capture by name with r"(\w+)-(?P<type>[a-z]+)-(?P<id>\d+)"),
+            stock: Option<u32> = (text of ".stock", capture by name with r"(?P<stock>\d+) left", optional),
+            // the named groups are mapped by position without `by name`
+            (major: u32, minor: u32) = (text of ".version", capture with r"v(?P<x>\d+)\.(?P<y>\d+)"),
+            // even if some of them are named after the fields
+            (amount: String, unit: String) = (text of ".price", capture with r"(?P<unit>[\d.]+) (?P<amount>\w+)"),
+            // and with `collect`
+            tags: Vec<(String, u32)> = (text of ".tag", capture with r"(?P<tags>\w+)\((?P<count>\d+)\)", collect),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(
+            text = ".stock",
+            capture_by_name = r"(?P<left>\w+ )?(?P<stock>\d+) left"
+        )]
+        stock: u32,
+    }
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Note {
+            (price: f64, note: String) = (text of ".price", capture by name with r"(?P<price>[\d.]+)(?: \((?P<note>.*)\))?"),
+        }
+    }
+
+    let input = r#"
+        <div class="product" data-sku="ab-book-42">
+            <span class="price">12.5 EUR</span>
+            <span class="stock">3 left</span>
+            <span class="version">v1.20</span>
+            <span class="tag">new(2)</span>
+            <span class="tag">sale(5)</span>
+        </div>
+    "#;
+    assert_eq!(
+        Product::extract_from_str(input).unwrap(),
+        Product {
+            currency: "EUR".to_owned(),
+            price: 12.5,
+            r#type: "book".to_owned(),
+            id: 42,
+            stock: Some(3),
+            major: 1,
+            minor: 20,
+            amount: "12.5".to_owned(),
+            unit: "EUR".to_owned(),
+            tags: vec![("new".to_owned(), 2), ("sale".to_owned(), 5)],
+        }
+    );
+    assert_eq!(
+        Derived::extract_from_str(input).unwrap(),
+        Derived { stock: 3 }
+    );
+
+    assert_eq!(
+        Note::extract_from_str(r#"<span class="price">10 (tax included)</span>"#).unwrap(),
+        Note {
+            price: 10.0,
+            note: "tax included".to_owned()
+        }
+    );
+    let err = Note::extract_from_str(input).unwrap_err().to_string();
+    assert!(
+        err.contains("capture group `note` did not match"),
+        "{}",
+        err
+    );
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn inner_markdown() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Post {
+            title: String = (inner_markdown of "h1"),
+            body: String = (inner_markdown of "article"),
+            links: Vec<String> = (inner_markdown of "a", collect),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(inner_markdown = "article")]
+        body: String,
+    }
+
+    let input = r#"
+        <h1>Hello, <em>world</em></h1>
+        <article>
+            <p>See <a href="/docs">the <b>docs</b></a>.</p>
+            <ul><li>one</li><li>two</li></ul>
+        </article>
+    "#;
+    let post = Post::extract_from_str(input).unwrap();
+    assert_eq!(post.title, "Hello, *world*");
+    assert_eq!(post.body, "See [the **docs**](/docs).\n\n* one\n* two");
+    assert_eq!(post.links, vec!["the **docs**"]);
+    assert_eq!(Derived::extract_from_str(input).unwrap().body, post.body);
+}
+
+#[cfg(feature = "sanitize")]
+#[test]
+fn sanitized_html() {
+    use html_extractor::HtmlExtractor;
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Comment {
+            body: String = (sanitized_html of ".comment"),
+            raw: String = (inner_html of ".comment"),
+            signatures: Vec<String> = (sanitized_html of ".signature", collect),
+        }
+    }
+    #[derive(Debug, PartialEq, html_extractor::HtmlExtractor)]
+    struct Derived {
+        #[extract(sanitized_html = ".comment")]
+        body: String,
+    }
+
+    let input = r#"
+        <div class="comment">
+            <p onclick="steal()">Nice <b>post</b>!</p>
+            <script>steal()</script>
+            <a href="javascript:steal()">click</a> <a href="https://example.com/">me</a>
+            <img src="cat.png" onerror="steal()">
+        </div>
+        <div class="signature"><i>Alice</i><style>* { display: none }</style></div>
+    "#;
+    let comment = Comment::extract_from_str(input).unwrap();
+    assert!(comment.raw.contains("<script>"));
+    assert!(!comment.body.contains("steal"));
+    assert!(!comment.body.contains("<script>"));
+    assert!(comment.body.starts_with("<p>Nice <b>post</b>!</p>"));
+    assert!(comment
+        .body
+        .contains(r#"<a rel="noopener noreferrer">click</a>"#));
+    assert!(comment
+        .body
+        .contains(r#"<a href="https://example.com/" rel="noopener noreferrer">me</a>"#));
+    assert!(comment.body.ends_with(r#"<img src="cat.png">"#));
+    assert_eq!(comment.signatures, vec!["<i>Alice</i>"]);
+    assert_eq!(Derived::extract_from_str(input).unwrap().body, comment.body);
+}
+
+/// A minimal HTTP server for testing the `fetch` feature.
+#[cfg(feature = "fetch")]
+mod test_server {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    pub struct Response {
+        pub status: u16,
+        pub headers: Vec<(&'static str, String)>,
+        pub body: String,
+    }
+    impl Response {
+        pub fn ok(body: &str) -> Response {
+            Response {
+                status: 200,
+                headers: Vec::new(),
+                body: body.to_owned(),
+            }
+        }
+        pub fn status(status: u16) -> Response {
+            Response {
+                status,
+                headers: Vec::new(),
+                body: String::new(),
+            }
+        }
+    }
+
+    /// A request received by the server, which consists of the request line, the headers and the body if any.
+    pub type Request = Vec<String>;
+
+    /// Starts a server on a random port and returns its base URL and the received requests.
+    pub fn serve(
+        handler: impl Fn(&Request) -> Response + Send + 'static,
+    ) -> (String, Arc<Mutex<Vec<Request>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let requests_ = Arc::clone(&requests);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request = (&mut reader)
+                    .lines()
+                    .map(|line| line.unwrap())
+                    .take_while(|line| !line.is_empty())
+                    .collect::<Request>();
+                let content_length = request
+                    .iter()
+                    .find_map(|line| {
+                        line.to_ascii_lowercase()
+                            .strip_prefix("content-length: ")?
+                            .parse()
+                            .ok()
+                    })
+                    .unwrap_or(0);
+                if content_length > 0 {
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).unwrap();
+                    request.push(String::from_utf8(body).unwrap());
+                }
+                let response = handler(&request);
+                requests_.lock().unwrap().push(request);
+
+                let mut head = format!(
+                    "HTTP/1.1 {} Test\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    response.status,
+                    response.body.len()
+                );
+                for (name, value) in &response.headers {
+                    head += &format!("{}: {}\r\n", name, value);
+                }
+                head += "\r\n";
+                let _ = stream.write_all(head.as_bytes());
+                let _ = stream.write_all(response.body.as_bytes());
+            }
+        });
+        (base_url, requests)
+    }
+
+    pub fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetch_retry() {
+    use html_extractor::fetch::{FetchError, Fetcher, RetryPolicy};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use test_server::{block_on, serve, Response};
+
+    let count = AtomicUsize::new(0);
+    let (base_url, requests) = serve(move |_| match count.fetch_add(1, Ordering::SeqCst) {
+        0 => Response::status(503),
+        1 => Response {
+            headers: vec![("Retry-After", "0".to_owned())],
+            ..Response::status(429)
+        },
+        _ => Response::ok(r#"<div class="data1-1">1</div>"#),
+    });
+    let fetcher = Fetcher::builder()
+        .retry(RetryPolicy::new().initial_backoff(Duration::from_millis(1)))
+        .build()
+        .unwrap();
+    let data: InnerData = block_on(fetcher.extract(&base_url)).unwrap();
+    assert_eq!(data, InnerData { data1_1: 1 });
+    assert_eq!(requests.lock().unwrap().len(), 3);
+
+    // 404 is not retried
+    let (base_url, requests) = serve(|_| Response::status(404));
+    let err = block_on(fetcher.fetch(&base_url)).unwrap_err();
+    assert_eq!(err.status(), Some(404));
+    assert_eq!(requests.lock().unwrap().len(), 1);
+    let err = html_extractor::Error::from(err);
+    assert_eq!(err.code(), "E_FETCH_STATUS");
+    assert!(
+        matches!(&err, html_extractor::Error::Fetch(failure) if failure.fetch_error().status() == Some(404))
+    );
+
+    // gives up after the max retries
+    let (base_url, requests) = serve(|_| Response::status(500));
+    let fetcher = Fetcher::builder()
+        .retry(
+            RetryPolicy::new()
+                .max_retries(2)
+                .initial_backoff(Duration::from_millis(1)),
+        )
+        .build()
+        .unwrap();
+    assert!(matches!(
+        block_on(fetcher.fetch(&base_url)),
+        Err(FetchError::Status { status: 500, .. })
+    ));
+    assert_eq!(requests.lock().unwrap().len(), 3);
+
+    // classifies 500 as permanent
+    let (base_url, requests) = serve(|_| Response::status(500));
+    let fetcher = Fetcher::builder()
+        .retry(RetryPolicy::new().classify(|err, transient| transient && err.status() != Some(500)))
+        .build()
+        .unwrap();
+    assert!(block_on(fetcher.fetch(&base_url)).is_err());
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetch_robots_txt() {
+    use html_extractor::fetch::{FetchError, Fetcher};
+    use test_server::{block_on, serve, Response};
+
+    let (base_url, requests) = serve(|request| {
+        if request[0].starts_with("GET /robots.txt ") {
+            Response::ok("User-agent: TestBot\nDisallow: /private\n\nUser-agent: *\nDisallow: /")
+        } else {
+            Response::ok("")
+        }
+    });
+    let fetcher = Fetcher::builder()
+        .respect_robots_txt("TestBot/1.0")
+        .build()
+        .unwrap();
+    block_on(async {
+        assert!(fetcher.fetch(&format!("{}/public", base_url)).await.is_ok());
+        assert!(matches!(
+            fetcher.fetch(&format!("{}/private?a=b", base_url)).await,
+            Err(FetchError::Disallowed { .. })
+        ));
+    });
+    // robots.txt is fetched only once
+    assert_eq!(requests.lock().unwrap().len(), 2);
+
+    let (base_url, _) = serve(|request| {
+        if request[0].starts_with("GET /robots.txt ") {
+            Response::status(404)
+        } else {
+            Response::ok("")
+        }
+    });
+    assert!(block_on(fetcher.fetch(&base_url)).is_ok());
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetch_rate_limit() {
+    use html_extractor::fetch::Fetcher;
+    use std::time::{Duration, Instant};
+    use test_server::{block_on, serve, Response};
+
+    let (base_url1, _) = serve(|_| Response::ok(""));
+    let (base_url2, _) = serve(|_| Response::ok(""));
+    let fetcher = Fetcher::builder()
+        .requests_per_second_per_host(20.0)
+        .max_concurrency(2)
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    block_on(async {
+        for _ in 0..3 {
+            fetcher.fetch(&base_url1).await.unwrap();
+            fetcher.fetch(&base_url2).await.unwrap();
+        }
+    });
+    // 3 requests to each host take at least 2 intervals of 50ms
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(100));
+    assert!(elapsed < Duration::from_millis(1000));
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetch_session() {
+    use html_extractor::fetch::Fetcher;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use test_server::{block_on, serve, Response};
+
+    let (base_url, requests) = serve(|request| {
+        if request[0].starts_with("POST /login ") {
+            if request.last().unwrap() == "user=foo&password=bar" {
+                let mut response = Response::ok("");
+                response
+                    .headers
+                    .push(("Set-Cookie", "session=abc; Path=/".to_owned()));
+                response
+            } else {
+                Response::status(403)
+            }
+        } else if request.iter().any(|line| line == "cookie: session=abc") {
+            Response::ok("<title>secret</title>")
+        } else {
+            Response::status(401)
+        }
+    });
+    let mut headers = HeaderMap::new();
+    headers.insert("x-test", HeaderValue::from_static("1"));
+    let login_url = format!("{}/login", base_url);
+    let fetcher = Fetcher::builder()
+        .retry(html_extractor::fetch::RetryPolicy::none())
+        .default_headers(headers)
+        .cookie_store(true)
+        .bootstrap(move |fetcher| {
+            let login_url = login_url.clone();
+            async move {
+                fetcher
+                    .post_form(&login_url, &[("user", "foo"), ("password", "bar")])
+                    .await?;
+                Ok(())
+            }
+        })
+        .build()
+        .unwrap();
+
+    block_on(async {
+        for _ in 0..2 {
+            let body = fetcher.fetch(&format!("{}/page", base_url)).await.unwrap();
+            assert_eq!(body, "<title>secret</title>");
+        }
+    });
+    let requests = requests.lock().unwrap();
+    // the hook runs only once
+    assert_eq!(requests.len(), 3);
+    assert!(requests
+        .iter()
+        .all(|request| request.iter().any(|line| line == "x-test: 1")));
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetch_sitemap() {
+    use futures_util::StreamExt;
+    use html_extractor::fetch::Fetcher;
+    use std::sync::{Arc, Mutex};
+    use test_server::{block_on, serve, Response};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Article {
+            title: String = (text of "h1"),
+        }
+    }
+
+    let base_url = Arc::new(Mutex::new(String::new()));
+    let base_url_ = Arc::clone(&base_url);
+    let (url, _) = serve(move |request| {
+        let base_url = base_url_.lock().unwrap();
+        let path = request[0].split(' ').nth(1).unwrap();
+        match path {
+            "/sitemap_index.xml" => Response::ok(&format!(
+                "<sitemapindex><sitemap><loc>{0}/sitemap1.xml</loc></sitemap><sitemap><loc>{0}/sitemap2.xml</loc></sitemap></sitemapindex>",
+                base_url
+            )),
+            "/sitemap1.xml" => Response::ok(&format!(
+                "<urlset><url><loc>{0}/articles/1</loc></url><url><loc>{0}/about</loc></url></urlset>",
+                base_url
+            )),
+            "/sitemap2.xml" => Response::ok(&format!(
+                // a loop is ignored
+                "<sitemapindex><sitemap><loc>{0}/sitemap1.xml</loc></sitemap></sitemapindex><urlset><url><loc>{0}/articles/2</loc></url></urlset>",
+                base_url
+            )),
+            "/articles/1" => Response::ok("<h1>one</h1>"),
+            "/articles/2" => Response::ok("<p>no title</p>"),
+            _ => Response::status(404),
+        }
+    });
+    *base_url.lock().unwrap() = url.clone();
+
+    let fetcher = Fetcher::new();
+    block_on(async {
+        let urls = fetcher
+            .sitemap_urls(&format!("{}/sitemap_index.xml", url))
+            .await
+            .unwrap();
+        assert_eq!(
+            urls,
+            vec![
+                format!("{}/articles/1", url),
+                format!("{}/about", url),
+                format!("{}/articles/2", url)
+            ]
+        );
+
+        let articles = fetcher
+            .extract_sitemap::<Article>(&format!("{}/sitemap_index.xml", url), |url| {
+                url.contains("/articles/")
+            })
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(articles.len(), 2);
+        assert_eq!(articles[0].0, format!("{}/articles/1", url));
+        assert_eq!(
+            articles[0].1.as_ref().unwrap(),
+            &Article {
+                title: "one".to_owned()
+            }
+        );
+        assert!(articles[1].1.is_err());
+    });
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetch_crawler() {
+    use futures_util::StreamExt;
+    use html_extractor::fetch::{Crawler, Fetcher};
+    use regex::Regex;
+    use scraper::Selector;
+    use test_server::{block_on, serve, Response};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Article {
+            title: String = (text of "h1"),
+        }
+    }
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Author {
+            name: String = (text of ".name"),
+        }
+    }
+    #[derive(Debug, PartialEq)]
+    enum Page {
+        Article(Article),
+        Author(Author),
+    }
+
+    let (base_url, requests) = serve(|request| {
+        let path = request[0].split(' ').nth(1).unwrap();
+        match path {
+            "/" => Response::ok(
+                r#"<a href="/articles/1">1</a> <a href="articles/2#comments">2</a> <a href="https://example.com/">external</a> <a href="/deep">deep</a>"#,
+            ),
+            "/articles/1" => {
+                Response::ok(r#"<h1>one</h1><a href="/authors/foo">foo</a><a href="/">top</a>"#)
+            }
+            "/articles/2" => Response::ok("<p>no title</p>"),
+            "/authors/foo" => {
+                Response::ok(r#"<span class="name">Foo</span><a href="/hidden">hidden</a>"#)
+            }
+            "/deep" => Response::ok(r#"<a href="/deeper">deeper</a>"#),
+            "/deeper" => Response::ok(r#"<a href="/hidden">hidden</a>"#),
+            _ => Response::status(404),
+        }
+    });
+
+    let results = block_on(
+        Crawler::new(Fetcher::new())
+            .seed(&format!("{}/", base_url))
+            .follow_links(Selector::parse("a").unwrap())
+            .route(Regex::new(r"/articles/\d+$").unwrap(), Page::Article)
+            .route(Regex::new(r"/authors/").unwrap(), Page::Author)
+            .max_depth(2)
+            .crawl()
+            .collect::<Vec<_>>(),
+    );
+    let mut results = results;
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    let paths = results
+        .iter()
+        .map(|(url, _)| url.strip_prefix(&base_url).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, vec!["/articles/1", "/articles/2", "/authors/foo"]);
+    assert_eq!(
+        results[0].1.as_ref().unwrap(),
+        &Page::Article(Article {
+            title: "one".to_owned()
+        })
+    );
+    assert!(results[1].1.is_err());
+    assert_eq!(
+        results[2].1.as_ref().unwrap(),
+        &Page::Author(Author {
+            name: "Foo".to_owned()
+        })
+    );
+    // each page is fetched once, external links and pages deeper than 2 are not
+    assert_eq!(requests.lock().unwrap().len(), 6);
+
+    let results = block_on(
+        Crawler::new(Fetcher::new())
+            .seed(&format!("{}/", base_url))
+            .follow_links(Selector::parse("a").unwrap())
+            .route(Regex::new(r"/articles/\d+$").unwrap(), Page::Article)
+            .max_pages(2)
+            .crawl()
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(results.len(), 1);
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetch_crawler_url_store() {
+    use futures_util::StreamExt;
+    use html_extractor::fetch::{Crawler, Fetcher, MemoryUrlStore, UrlStore};
+    use regex::Regex;
+    use scraper::Selector;
+    use std::sync::{Arc, Mutex};
+    use test_server::{block_on, serve, Response};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Title {
+            title: String = (text of "h1"),
+        }
+    }
+
+    /// A store that outlives a crawl, as a database would.
+    #[derive(Clone, Default)]
+    struct SharedStore(Arc<Mutex<MemoryUrlStore>>);
+    impl UrlStore for SharedStore {
+        fn push(&mut self, url: &str, depth: usize) -> bool {
+            self.0.lock().unwrap().push(url, depth)
+        }
+        fn pop(&mut self) -> Option<(String, usize)> {
+            self.0.lock().unwrap().pop()
+        }
+    }
+
+    let (base_url, requests) = serve(|request| {
+        let path = request[0].split(' ').nth(1).unwrap();
+        Response::ok(&format!(
+            r#"<h1>{0}</h1><a href="/1">1</a><a href="/2">2</a><a href="/3">3</a>"#,
+            path
+        ))
+    });
+    let store = SharedStore::default();
+    let crawl = |max_pages| {
+        block_on(
+            Crawler::new(Fetcher::new())
+                .seed(&format!("{}/", base_url))
+                .follow_links(Selector::parse("a").unwrap())
+                .route(Regex::new(".").unwrap(), |title: Title| title.title)
+                .url_store(store.clone())
+                .max_pages(max_pages)
+                .crawl()
+                .map(|(_, title)| title.unwrap())
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_eq!(crawl(2), vec!["/", "/1"]);
+    // resumed without visiting the seed again
+    let mut resumed = crawl(10);
+    resumed.sort();
+    assert_eq!(resumed, vec!["/2", "/3"]);
+    assert_eq!(requests.lock().unwrap().len(), 4);
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetch_crawler_politeness() {
+    use futures_util::StreamExt;
+    use html_extractor::fetch::{Crawler, Fetcher};
+    use regex::Regex;
+    use scraper::Selector;
+    use std::time::{Duration, Instant};
+    use test_server::{block_on, serve, Response};
+
+    html_extractor::html_extractor! {
+        Links {
+            links: Vec<String> = (attr["href"] of "a", collect),
+        }
+    }
+
+    let (base_url, _) = serve(|request| {
+        let path = request[0].split(' ').nth(1).unwrap();
+        match path {
+            "/robots.txt" => Response::ok("User-agent: *\nCrawl-delay: 0.1"),
+            _ => Response::ok(r#"<a href="/1">1</a><a href="/2">2</a><a href="/3">3</a>"#),
+        }
+    });
+    let crawl = |fetcher: Fetcher, crawl_delay| {
+        let start = Instant::now();
+        let results = block_on(
+            Crawler::new(fetcher)
+                .seed(&format!("{}/", base_url))
+                .follow_links(Selector::parse("a").unwrap())
+                .route(Regex::new(".").unwrap(), |links: Links| links.links)
+                .crawl_delay(crawl_delay)
+                .crawl()
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(results.len(), 4);
+        start.elapsed()
+    };
+
+    // 4 pages from a host take at least 3 delays
+    let elapsed = crawl(Fetcher::new(), Duration::from_millis(50));
+    assert!(elapsed >= Duration::from_millis(150));
+    // the crawl delay in robots.txt is longer
+    let fetcher = Fetcher::builder()
+        .respect_robots_txt("TestBot")
+        .build()
+        .unwrap();
+    let elapsed = crawl(fetcher, Duration::from_millis(50));
+    assert!(elapsed >= Duration::from_millis(300));
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn fetch_extract_batch() {
+    use html_extractor::fetch::{self, Fetcher};
+    use test_server::{block_on, serve, Response};
+
+    html_extractor::html_extractor! {
+        #[derive(Debug, PartialEq)]
+        Number {
+            n: usize = (text of "p"),
+        }
+    }
+
+    let (base_url, requests) = serve(|request| {
+        let path = request[0].split(' ').nth(1).unwrap();
+        match path {
+            "/missing" => Response::status(404),
+            _ => Response::ok(&format!("<p>{}</p>", &path[1..])),
+        }
+    });
+    let fetcher = Fetcher::builder()
+        .retry(fetch::RetryPolicy::none())
+        .build()
+        .unwrap();
+    let mut results = Vec::new();
+    block_on(
+        fetcher.extract_batch(
+            ["/1", "/missing", "/2", "/x", "/3"]
+                .iter()
+                .map(|path| format!("{}{}", base_url, path)),
+            2,
+            |url, n: Result<Number, _>| results.push((url[base_url.len()..].to_owned(), n.ok())),
+        ),
+    );
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        results,
+        vec![
+            ("/1".to_owned(), Some(Number { n: 1 })),
+            ("/2".to_owned(), Some(Number { n: 2 })),
+            ("/3".to_owned(), Some(Number { n: 3 })),
+            ("/missing".to_owned(), None),
+            ("/x".to_owned(), None),
+        ]
+    );
+    assert_eq!(requests.lock().unwrap().len(), 5);
+
+    let mut results = vec![None, None, None];
+    block_on(fetch::extract_documents_batch(
+        vec!["<p>1</p>".to_owned(), "".to_owned(), "<p>3</p>".to_owned()],
+        2,
+        |i, n: Result<Number, _>| results[i] = Some(n.ok()),
+    ));
+    assert_eq!(
+        results,
+        vec![
+            Some(Some(Number { n: 1 })),
+            Some(None),
+            Some(Some(Number { n: 3 }))
+        ]
+    );
+}